@@ -1,7 +1,4 @@
-use bevy::prelude::*;
-use std::f32::consts::{FRAC_PI_2, PI};
-
-use crate::level::{direction::Dir, trains::TrainColor};
+use crate::{direction::Dir, trains::TrainColor};
 
 /// A struct representing possible active and passive connections on a track, with the data represented as a single byte.
 ///
@@ -10,7 +7,7 @@ use crate::level::{direction::Dir, trains::TrainColor};
 /// The active connection is represented in the least significant 4 bits, and the passive connection is represented in the most significant 4 bits.
 /// Each connection is composed of two `Dir`s
 /// A value of 0 in both dirs for either connection represents the lack of connection.
-#[derive(Component, Default, Clone, Copy, Eq, PartialEq)]
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
 pub struct TileConnections {
     data: u8,
 }
@@ -33,28 +30,53 @@ pub enum ConnectionType {
     Ji,
 }
 
-impl ConnectionType {
-    pub fn get_asset_path(&self) -> &'static str {
-        match self {
-            ConnectionType::None => "sprites/Tracktile_blank.png",
-            ConnectionType::I => "sprites/Tracktile_i.png",
-            ConnectionType::C => "sprites/Tracktile_c.png",
-            ConnectionType::H => "sprites/Tracktile_h.png",
-            ConnectionType::Z => "sprites/Tracktile_z.png",
-            ConnectionType::M => "sprites/Tracktile_m.png",
-            ConnectionType::Jc => "sprites/Tracktile_jc.png",
-            ConnectionType::Ji => "sprites/Tracktile_ji.png",
+/// How the sprite for a `ConnectionType` has to be turned to match a tile's connections:
+/// a number of counterclockwise quarter turns, optionally preceded by a mirror image about the vertical axis.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TileRotation {
+    pub quarter_turns: u8,
+    pub flipped: bool,
+}
+
+impl TileRotation {
+    pub fn new(quarter_turns: i8) -> Self {
+        Self {
+            quarter_turns: quarter_turns.rem_euclid(4) as u8,
+            flipped: false,
         }
     }
+    pub fn flipped(quarter_turns: i8) -> Self {
+        Self {
+            flipped: true,
+            ..Self::new(quarter_turns)
+        }
+    }
+}
 
-    pub fn should_toggle_active_and_passive_when_trains_pass(&self) -> bool {
-        match self {
-            ConnectionType::M | ConnectionType::Jc | ConnectionType::Ji => true,
-            _ => false,
+#[cfg(feature = "bevy")]
+impl From<TileRotation> for bevy_math::Quat {
+    fn from(value: TileRotation) -> Self {
+        use bevy_math::Quat;
+        use std::f32::consts::{FRAC_PI_2, PI};
+
+        let rotation = Quat::from_rotation_z(value.quarter_turns as f32 * FRAC_PI_2);
+        if value.flipped {
+            rotation * Quat::from_rotation_y(PI)
+        } else {
+            rotation
         }
     }
 }
 
+impl ConnectionType {
+    pub fn should_toggle_active_and_passive_when_trains_pass(&self) -> bool {
+        matches!(
+            self,
+            ConnectionType::M | ConnectionType::Jc | ConnectionType::Ji
+        )
+    }
+}
+
 impl TileConnections {
     pub fn empty() -> Self {
         TileConnections { data: 0 }
@@ -66,6 +88,17 @@ impl TileConnections {
         let connections = Self { data };
         connections.to_normal_form()
     }
+    /// Like `from_data`, but returns `None` for data that drawing tracks can never produce: a passive connection
+    /// without an active one. Data read from outside the game should go through this, since `type_and_rotation`
+    /// has no shape for those connections.
+    pub fn try_from_data(data: u8) -> Option<Self> {
+        let connections = Self::from_data(data);
+        if connections.get_active_conn().is_empty() && !connections.get_passive_conn().is_empty() {
+            None
+        } else {
+            Some(connections)
+        }
+    }
     pub fn get_data(&self) -> u8 {
         self.data
     }
@@ -127,25 +160,22 @@ impl TileConnections {
         }
     }
 
-    pub fn type_and_rotation(&self) -> (ConnectionType, Quat) {
+    /// Which sprite draws these connections, and how to turn it.
+    ///
+    /// Panics on connections that `try_from_data` rejects.
+    pub fn type_and_rotation(&self) -> (ConnectionType, TileRotation) {
         if self.data == 0x00 {
-            return (ConnectionType::None, Quat::from_rotation_z(0.0));
+            return (ConnectionType::None, TileRotation::default());
         }
         if (self.data >> 4) & 0xf == 0 {
             let rotation_for_type_i =
                 self.has_connection_up_to_rot(Connection::from_dirs(Dir::Up, Dir::Down));
             if rotation_for_type_i != -1 {
-                return (
-                    ConnectionType::I,
-                    Quat::from_rotation_z(rotation_for_type_i as f32 * FRAC_PI_2),
-                );
+                return (ConnectionType::I, TileRotation::new(rotation_for_type_i));
             } else {
                 let rotation_for_type_c =
                     self.has_connection_up_to_rot(Connection::from_dirs(Dir::Left, Dir::Down));
-                return (
-                    ConnectionType::C,
-                    Quat::from_rotation_z(rotation_for_type_c as f32 * FRAC_PI_2),
-                );
+                return (ConnectionType::C, TileRotation::new(rotation_for_type_c));
             }
         }
         // now we can assume that there is both an active and passive connection
@@ -154,9 +184,9 @@ impl TileConnections {
             Connection::from_dirs(Dir::Left, Dir::Right),
         ) {
             if self.get_active_conn() == Connection::from_dirs(Dir::Up, Dir::Down) {
-                return (ConnectionType::H, Quat::from_rotation_z(0.0));
+                return (ConnectionType::H, TileRotation::new(0));
             } else {
-                return (ConnectionType::H, Quat::from_rotation_z(FRAC_PI_2));
+                return (ConnectionType::H, TileRotation::new(1));
             }
         }
         let rot_for_z = self.has_connections_up_to_rot(
@@ -164,10 +194,7 @@ impl TileConnections {
             Connection::from_dirs(Dir::Down, Dir::Left),
         );
         if rot_for_z != -1 {
-            return (
-                ConnectionType::Z,
-                Quat::from_rotation_z(rot_for_z as f32 * FRAC_PI_2),
-            );
+            return (ConnectionType::Z, TileRotation::new(rot_for_z));
         }
 
         let rot_for_m = self.has_connections_up_to_rot(
@@ -175,15 +202,12 @@ impl TileConnections {
             Connection::from_dirs(Dir::Right, Dir::Down),
         );
         if rot_for_m != -1 {
-            let flip_quat = if self.get_active_conn().rotate_ccw() == self.get_passive_conn() {
-                Quat::IDENTITY
+            let rotation = if self.get_active_conn().rotate_ccw() == self.get_passive_conn() {
+                TileRotation::new(rot_for_m)
             } else {
-                Quat::from_rotation_y(PI)
+                TileRotation::flipped(rot_for_m)
             };
-            return (
-                ConnectionType::M,
-                Quat::from_rotation_z(rot_for_m as f32 * FRAC_PI_2) * flip_quat,
-            );
+            return (ConnectionType::M, rotation);
         }
 
         let rot_for_j_not_flipped = self.has_connections_up_to_rot(
@@ -198,10 +222,7 @@ impl TileConnections {
             } else {
                 ConnectionType::Jc
             };
-            return (
-                conn_type,
-                Quat::from_rotation_z(rot_for_j_not_flipped as f32 * FRAC_PI_2),
-            );
+            return (conn_type, TileRotation::new(rot_for_j_not_flipped));
         }
 
         let rot_for_j_flipped = self.has_connections_up_to_rot(
@@ -216,14 +237,9 @@ impl TileConnections {
             } else {
                 ConnectionType::Jc
             };
-            return (
-                conn_type,
-                Quat::from_rotation_z(rot_for_j_flipped as f32 * FRAC_PI_2)
-                    * Quat::from_rotation_y(PI),
-            );
+            return (conn_type, TileRotation::flipped(rot_for_j_flipped));
         }
-        println!("reached unreachable code with value: {:?}", self.data);
-        unreachable!()
+        unreachable!("no connection type matches the track data {:?}", self.data)
     }
 
     pub fn rotate_cw(&self) -> Self {
@@ -387,7 +403,7 @@ impl Connection {
     }
 }
 
-#[derive(Default, Debug, PartialEq, Eq, Clone)]
+#[derive(Default, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct TileBorderState {
     data: [Option<TrainColor>; 4],
 }
@@ -416,3 +432,199 @@ impl TileBorderState {
         self.data[index as usize]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn all_bytes() -> impl Iterator<Item = u8> {
+        0..=u8::MAX
+    }
+
+    fn all_dir_pairs() -> impl Iterator<Item = (Dir, Dir)> {
+        Dir::all_dirs().flat_map(|d1| Dir::all_dirs().map(move |d2| (d1, d2)))
+    }
+
+    /// Every `TileConnections` that drawing tracks can produce, found by drawing every track on every one of them
+    /// (and letting trains switch them) until nothing new turns up.
+    fn reachable_connections() -> HashSet<u8> {
+        let mut reachable = HashSet::from([TileConnections::empty().get_data()]);
+        let mut to_visit = vec![TileConnections::empty()];
+        while let Some(connections) = to_visit.pop() {
+            let next = all_dir_pairs()
+                .map(|(d1, d2)| connections.add_connection(d1, d2))
+                .chain([connections.switch_active_passive()]);
+            for connections in next {
+                if reachable.insert(connections.get_data()) {
+                    to_visit.push(connections);
+                }
+            }
+        }
+        reachable
+    }
+
+    #[test]
+    fn data_round_trips() {
+        for data in all_bytes() {
+            let connections = TileConnections::from_data(data);
+            assert_eq!(
+                TileConnections::from_data(connections.get_data()),
+                connections
+            );
+            assert_eq!(
+                TileConnections::from_active_passive(
+                    connections.get_active_conn(),
+                    connections.get_passive_conn()
+                ),
+                connections,
+                "{:#04x}",
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn dirs_round_trip() {
+        for (d1, d2) in all_dir_pairs() {
+            let connection = Connection::from_dirs(d1, d2);
+            if d1 == d2 {
+                assert!(connection.is_empty());
+                continue;
+            }
+            let (e1, e2) = connection.get_dirs();
+            assert!((e1, e2) == (d1, d2) || (e1, e2) == (d2, d1));
+            assert_eq!(connection.get_other_dir(d1), Some(d2));
+            assert_eq!(connection.get_other_dir(d2), Some(d1));
+        }
+    }
+
+    #[test]
+    fn normal_form_is_idempotent() {
+        for data in all_bytes() {
+            let normal = TileConnections { data }.to_normal_form();
+            assert_eq!(normal.to_normal_form(), normal, "{:#04x}", data);
+
+            let normal = Connection { data: data & 0x0f }.to_normal_form();
+            assert_eq!(normal.to_normal_form(), normal, "{:#04x}", data);
+        }
+    }
+
+    #[test]
+    fn rotations_and_flips_are_a_group() {
+        for data in all_bytes() {
+            let connections = TileConnections::from_data(data);
+            let rotated_four_times = (0..4).fold(connections, |c, _| c.rotate_cw());
+            assert_eq!(rotated_four_times, connections, "{:#04x}", data);
+            assert_eq!(connections.rotate_cw().rotate_ccw(), connections);
+            assert_eq!(connections.flip().flip(), connections, "{:#04x}", data);
+            // a half turn is the same as a flip, since flipping swaps each direction with its opposite
+            assert_eq!(connections.rotate_cw().rotate_cw(), connections.flip());
+        }
+    }
+
+    #[test]
+    fn switching_twice_is_the_identity() {
+        for data in all_bytes() {
+            let connections = TileConnections::from_data(data);
+            assert_eq!(
+                connections.switch_active_passive().switch_active_passive(),
+                connections
+            );
+        }
+    }
+
+    #[test]
+    fn valid_data_is_exactly_what_drawing_reaches() {
+        let reachable = reachable_connections();
+        for data in all_bytes() {
+            let valid = TileConnections::try_from_data(data);
+            assert_eq!(
+                valid.is_some_and(|c| reachable.contains(&c.get_data())),
+                valid.is_some(),
+                "{:#04x}",
+                data
+            );
+            if let Some(connections) = valid {
+                // must not panic
+                connections.type_and_rotation();
+            }
+        }
+        for data in reachable {
+            assert_eq!(
+                TileConnections::try_from_data(data).map(|c| c.get_data()),
+                Some(data)
+            );
+        }
+    }
+
+    #[test]
+    fn loading_invalid_data_leaves_the_tile_empty() {
+        for data in all_bytes() {
+            let mut yard = crate::yard::Yard::new(1, 1);
+            yard.set_progress(&[data]);
+            let drawn = TileConnections::from_data(yard.get_progress()[0]);
+            drawn.type_and_rotation();
+            if TileConnections::try_from_data(data).is_none() {
+                assert!(drawn.is_empty(), "{:#04x}", data);
+            }
+
+            let mut yard = crate::yard::Yard::new(1, 1);
+            yard.apply_progress(&[data]);
+            TileConnections::from_data(yard.get_progress()[0]).type_and_rotation();
+        }
+    }
+
+    fn dir() -> impl Strategy<Value = Dir> {
+        (0..4u8).prop_map(Dir::from)
+    }
+
+    /// Something that can happen to a tile's tracks while a level is played.
+    #[derive(Debug, Clone)]
+    enum Edit {
+        Draw(Dir, Dir),
+        Switch,
+        Erase,
+    }
+
+    fn edit() -> impl Strategy<Value = Edit> {
+        prop_oneof![
+            4 => (dir(), dir()).prop_map(|(d1, d2)| Edit::Draw(d1, d2)),
+            2 => Just(Edit::Switch),
+            1 => Just(Edit::Erase),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn drawn_tracks_always_have_a_shape(edits in prop::collection::vec(edit(), 0..32)) {
+            let mut connections = TileConnections::empty();
+            for edit in edits {
+                connections = match edit {
+                    Edit::Draw(d1, d2) => connections.add_connection(d1, d2),
+                    Edit::Switch => connections.switch_active_passive(),
+                    Edit::Erase => TileConnections::empty(),
+                };
+                prop_assert!(TileConnections::try_from_data(connections.get_data()).is_some());
+                connections.type_and_rotation();
+            }
+        }
+
+        #[test]
+        fn rotating_drawn_tracks_keeps_their_shape(
+            d1 in dir(),
+            d2 in dir(),
+            d3 in dir(),
+            d4 in dir(),
+            turns in 0..4usize,
+        ) {
+            let connections = TileConnections::empty().add_connection(d1, d2).add_connection(d3, d4);
+            let rotated = (0..turns).fold(connections, |c, _| c.rotate_cw());
+            prop_assert_eq!(rotated.type_and_rotation().0, connections.type_and_rotation().0);
+            prop_assert_eq!(rotated.flip().type_and_rotation().0, connections.type_and_rotation().0);
+        }
+    }
+}
@@ -1,6 +1,4 @@
-use bevy::math::Quat;
 use serde::{Deserialize, Serialize};
-use std::f32::consts::{FRAC_PI_2, PI};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Dir {
@@ -47,7 +45,7 @@ impl Dir {
     }
 
     pub fn all_dirs() -> impl Iterator<Item = Dir> {
-        (0..4).map(|dir_u8| Dir::from(dir_u8))
+        (0..4).map(Dir::from)
     }
 
     pub fn to_local_coords_of_edge(&self) -> (f32, f32) {
@@ -103,8 +101,12 @@ impl From<Dir> for u8 {
     }
 }
 
-impl From<Dir> for Quat {
+#[cfg(feature = "bevy")]
+impl From<Dir> for bevy_math::Quat {
     fn from(value: Dir) -> Self {
+        use bevy_math::Quat;
+        use std::f32::consts::{FRAC_PI_2, PI};
+
         match value {
             Dir::Up => Quat::IDENTITY,
             Dir::Right => Quat::from_rotation_z(3.0 * FRAC_PI_2),
@@ -0,0 +1,181 @@
+use crate::direction::Dir;
+use crate::tiles::{construct_new_tile, TileConstructionInfo};
+use crate::trains::TrainColor;
+use crate::yard::Yard;
+use crate::{NUM_COLS, NUM_ROWS};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelLoadInfo {
+    pub name: String,
+    /// The city this level is grouped under in the level picker.
+    #[serde(default)]
+    pub city: String,
+    /// How hard the level is, as a number of stars. Levels without a rating have 0.
+    #[serde(default)]
+    pub stars: u8,
+    /// The number of columns in this level's yard.
+    #[serde(default = "default_width")]
+    pub width: u8,
+    /// The number of rows in this level's yard.
+    #[serde(default = "default_height")]
+    pub height: u8,
+    pub sources: Vec<SourceInfo>,
+    pub sinks: Vec<SinkInfo>,
+    #[serde(default)]
+    // if this field is not present when deserializing, it should be set to the default value of an empty Vec
+    pub rocks: Vec<(u8, u8)>,
+    #[serde(default)]
+    pub painters: Vec<PainterInfo>,
+    #[serde(default)]
+    pub splitters: Vec<SplitterInfo>,
+    /// Tracks that are known to beat this level, in the same format as `Yard::get_progress`.
+    #[serde(default)]
+    pub solution: Option<Vec<u8>>,
+}
+
+/// A source in a level. Like every position in a level, `position` is (row, col).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceInfo {
+    /// The trains the source sends out, in the order they leave.
+    pub trains: Vec<TrainColor>,
+    pub out_dir: Dir,
+    pub position: (u8, u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SinkInfo {
+    /// The trains the sink is waiting for, in no particular order.
+    pub trains: Vec<TrainColor>,
+    pub in_dirs: Vec<Dir>,
+    pub position: (u8, u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PainterInfo {
+    pub color: TrainColor,
+    /// Trains come in from this side, and leave painted from the `out_dir` side.
+    pub in_dir: Dir,
+    pub out_dir: Dir,
+    pub position: (u8, u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitterInfo {
+    pub in_dir: Dir,
+    pub position: (u8, u8),
+}
+
+fn default_width() -> u8 {
+    NUM_COLS
+}
+
+fn default_height() -> u8 {
+    NUM_ROWS
+}
+
+impl Default for LevelLoadInfo {
+    fn default() -> Self {
+        LevelLoadInfo {
+            name: String::new(),
+            city: String::new(),
+            stars: 0,
+            width: default_width(),
+            height: default_height(),
+            sources: Vec::new(),
+            sinks: Vec::new(),
+            rocks: Vec::new(),
+            painters: Vec::new(),
+            splitters: Vec::new(),
+            solution: None,
+        }
+    }
+}
+
+impl LevelLoadInfo {
+    /// How many tiles this level's yard has, which is also how long its tracks are in `Yard::get_progress`.
+    pub fn num_tiles(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Every non-empty tile in this level, along with its position as (row, col).
+    pub fn tiles(&self) -> Vec<(TileConstructionInfo, (u8, u8))> {
+        let mut tiles = Vec::new();
+
+        for source in self.sources.iter() {
+            tiles.push((
+                TileConstructionInfo::SourceTile {
+                    out: source.out_dir,
+                    trains: source.trains.clone(),
+                },
+                source.position,
+            ));
+        }
+        for sink in self.sinks.iter() {
+            let mut ins = [false; 4];
+            for in_dir in sink.in_dirs.iter() {
+                ins[u8::from(*in_dir) as usize] = true;
+            }
+            tiles.push((
+                TileConstructionInfo::SinkTile {
+                    ins,
+                    trains: sink.trains.clone(),
+                },
+                sink.position,
+            ));
+        }
+        for position in self.rocks.iter() {
+            tiles.push((TileConstructionInfo::Rock, *position));
+        }
+        for painter in self.painters.iter() {
+            tiles.push((
+                TileConstructionInfo::Painter {
+                    color: painter.color,
+                    in_dir: painter.in_dir,
+                    out_dir: painter.out_dir,
+                },
+                painter.position,
+            ));
+        }
+        for splitter in self.splitters.iter() {
+            tiles.push((
+                TileConstructionInfo::Splitter {
+                    in_dir: splitter.in_dir,
+                },
+                splitter.position,
+            ));
+        }
+
+        tiles
+    }
+
+    /// Takes whatever is at `position` (as (row, col)) off of this level, returning whether there was anything there.
+    pub fn remove_tile(&mut self, position: (u8, u8)) -> bool {
+        let count_before = self.tile_count();
+        self.sources.retain(|source| source.position != position);
+        self.sinks.retain(|sink| sink.position != position);
+        self.rocks.retain(|p| *p != position);
+        self.painters.retain(|painter| painter.position != position);
+        self.splitters
+            .retain(|splitter| splitter.position != position);
+        self.tile_count() != count_before
+    }
+
+    fn tile_count(&self) -> usize {
+        self.sources.len()
+            + self.sinks.len()
+            + self.rocks.len()
+            + self.painters.len()
+            + self.splitters.len()
+    }
+
+    pub fn to_yard(&self) -> Yard {
+        let mut yard = Yard::new(self.width as usize, self.height as usize);
+
+        for (tile, (row, col)) in self.tiles() {
+            yard.replace_tile(row as usize, col as usize, construct_new_tile(tile));
+        }
+
+        yard
+    }
+}
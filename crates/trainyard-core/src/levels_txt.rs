@@ -0,0 +1,727 @@
+//! Reading and writing the `levels.txt` authoring format.
+//!
+//! ```text
+//! CITY:Abbotsford
+//!
+//! // a level called "Red Line", with a difficulty of 1 star
+//! Red Line:1
+//! # 7x7                    <- the size of the yard, as width x height. Optional, and 7x7 if left out
+//! + 1,3 red right          <- source: position, trains, exit direction
+//! o 5,3 red left           <- sink: position, trains, entry directions
+//! * 3,3 4,4                <- rocks
+//! p 2,2 blue left,right    <- painter: position, color, the side trains come in from, the side they leave by
+//! s 4,1 up                 <- splitter: position, entry direction
+//! = .. .. 0c 0d 0e .. ..   <- a solution: one line per row, top row first. Optional
+//! ---
+//! ----
+//! ```
+//!
+//! Positions are written as `x,y` with `y` counting down from the top of the yard, so the size has to come before
+//! any tiles. Three dashes end a level, and four end a city. Lines starting with `//` and blank lines are ignored,
+//! and nothing after a `// END HERE` line is read.
+//!
+//! A solution gives the tracks on every tile of a row, as the byte `TileConnections::get_data` gives for them written
+//! in hex, or `..` for no tracks. It needs a line for every row of the yard.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::{
+    connections::TileConnections,
+    direction::Dir,
+    level::{LevelLoadInfo, PainterInfo, SinkInfo, SourceInfo, SplitterInfo},
+    trains::TrainColor,
+    MAX_YARD_SIZE, NUM_COLS, NUM_ROWS,
+};
+
+const CITY_PREFIX: &str = "CITY:";
+const END_OF_LEVEL: &str = "---";
+const END_OF_CITY: &str = "----";
+const END_OF_FILE: &str = "// END HERE";
+/// The symbol that starts each row of a solution.
+const SOLUTION_ROW: &str = "=";
+/// How a tile with no tracks is written in a solution.
+const NO_TRACK: &str = "..";
+
+/// A problem with a line of `levels.txt`. Line numbers start from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelsTxtError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LevelsTxtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for LevelsTxtError {}
+
+/// Parses every level in `text`, in the order they appear.
+pub fn parse_levels_txt(text: &str) -> Result<Vec<LevelLoadInfo>, LevelsTxtError> {
+    let levels = parse_levels_with_lines(text)?;
+    Ok(levels.into_iter().map(|(level, _)| level).collect())
+}
+
+/// Gives each level in `text` the solution `solution_for` returns for its name, in place of the one it had. Levels it
+/// returns `None` for, and everything that isn't part of a level (comments and all), are left as they were.
+pub fn replace_solutions<'a>(
+    text: &str,
+    solution_for: impl Fn(&str) -> Option<&'a [u8]>,
+) -> Result<String, LevelsTxtError> {
+    let mut replaced_lines = HashSet::new();
+    let mut new_solutions = HashMap::new();
+    for (level, lines) in parse_levels_with_lines(text)? {
+        if let Some(solution) = solution_for(&level.name) {
+            replaced_lines.extend(lines.solution);
+            new_solutions.insert(lines.end, solution_to_txt(solution, level.width));
+        }
+    }
+
+    let mut output = String::new();
+    for (index, line) in text.lines().enumerate() {
+        if let Some(solution) = new_solutions.get(&index) {
+            output.push_str(solution);
+        }
+        if !replaced_lines.contains(&index) {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
+
+/// Where a level is in the text it was parsed from, as indexes of its lines.
+struct LevelLines {
+    /// The `=` lines of the level's solution.
+    solution: Vec<usize>,
+    /// The `---` line that ends the level.
+    end: usize,
+}
+
+fn parse_levels_with_lines(text: &str) -> Result<Vec<(LevelLoadInfo, LevelLines)>, LevelsTxtError> {
+    let mut levels = Vec::new();
+    let mut city = String::new();
+    let mut curr_level: Option<LevelLoadInfo> = None;
+    let mut solution_lines = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: String| LevelsTxtError {
+            line: line_number,
+            message,
+        };
+        let line = line.trim_end();
+
+        if line == END_OF_FILE {
+            break;
+        }
+        if line.starts_with("//") || line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(city_name) = line.strip_prefix(CITY_PREFIX) {
+            if let Some(level) = curr_level.take() {
+                return Err(error(format!(
+                    "level {:?} has no \"{}\" before the next city",
+                    level.name, END_OF_LEVEL
+                )));
+            }
+            city = city_name.trim().to_string();
+        } else if line == END_OF_LEVEL {
+            let level = curr_level
+                .take()
+                .ok_or_else(|| error(String::from("\"---\" without a level to end")))?;
+            if let Some(solution) = &level.solution {
+                if solution.len() != level.num_tiles() {
+                    return Err(error(format!(
+                        "the solution has {} rows, but the yard is {} tall",
+                        solution.len() / level.width as usize,
+                        level.height
+                    )));
+                }
+            }
+            let lines = LevelLines {
+                solution: std::mem::take(&mut solution_lines),
+                end: index,
+            };
+            levels.push((level, lines));
+        } else if line == END_OF_CITY {
+            if let Some(level) = curr_level.take() {
+                return Err(error(format!(
+                    "level {:?} has no \"{}\" before the end of the city",
+                    level.name, END_OF_LEVEL
+                )));
+            }
+        } else if let Some(level) = curr_level.as_mut() {
+            parse_tile_line(level, line).map_err(error)?;
+            if line.split_whitespace().next() == Some(SOLUTION_ROW) {
+                solution_lines.push(index);
+            }
+        } else if let Some((name, stars)) = line.rsplit_once(':') {
+            let stars = match stars.trim() {
+                "" => 0,
+                stars => stars
+                    .parse()
+                    .map_err(|_| error(format!("{:?} is not a number of stars", stars)))?,
+            };
+            curr_level = Some(LevelLoadInfo {
+                name: name.to_string(),
+                city: city.clone(),
+                stars,
+                ..Default::default()
+            });
+        } else {
+            return Err(error(format!(
+                "expected a level header like \"Name:1\", found {:?}",
+                line
+            )));
+        }
+    }
+
+    match curr_level {
+        Some(level) => Err(LevelsTxtError {
+            line: text.lines().count(),
+            message: format!("level {:?} is missing its \"{}\"", level.name, END_OF_LEVEL),
+        }),
+        None => Ok(levels),
+    }
+}
+
+fn parse_tile_line(level: &mut LevelLoadInfo, line: &str) -> Result<(), String> {
+    let mut words = line.split_whitespace();
+    let symbol = words.next().unwrap_or_default();
+    let words: Vec<&str> = words.collect();
+    let (width, height) = (level.width, level.height);
+
+    let expect_words = |count: usize| {
+        if words.len() == count {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected {} values after {:?}, found {}",
+                count,
+                symbol,
+                words.len()
+            ))
+        }
+    };
+
+    match symbol {
+        "#" => {
+            expect_words(1)?;
+            if !level.tiles().is_empty() || level.solution.is_some() {
+                return Err(String::from(
+                    "the size of the yard has to come before its tiles",
+                ));
+            }
+            (level.width, level.height) = parse_size(words[0])?;
+        }
+        "+" => {
+            expect_words(3)?;
+            level.sources.push(SourceInfo {
+                trains: parse_list(words[1], parse_color)?,
+                out_dir: parse_dir(words[2])?,
+                position: parse_position(words[0], width, height)?,
+            });
+        }
+        "o" => {
+            expect_words(3)?;
+            level.sinks.push(SinkInfo {
+                trains: parse_list(words[1], parse_color)?,
+                in_dirs: parse_list(words[2], parse_dir)?,
+                position: parse_position(words[0], width, height)?,
+            });
+        }
+        "*" => {
+            if words.is_empty() {
+                return Err(String::from("expected at least one rock position"));
+            }
+            for word in words {
+                level.rocks.push(parse_position(word, width, height)?);
+            }
+        }
+        "p" => {
+            expect_words(3)?;
+            let dirs = parse_list(words[2], parse_dir)?;
+            let [in_dir, out_dir] = dirs[..] else {
+                return Err(format!(
+                    "a painter connects exactly two directions, found {:?}",
+                    words[2]
+                ));
+            };
+            level.painters.push(PainterInfo {
+                color: parse_color(words[1])?,
+                in_dir,
+                out_dir,
+                position: parse_position(words[0], width, height)?,
+            });
+        }
+        "s" => {
+            expect_words(2)?;
+            level.splitters.push(SplitterInfo {
+                in_dir: parse_dir(words[1])?,
+                position: parse_position(words[0], width, height)?,
+            });
+        }
+        SOLUTION_ROW => {
+            expect_words(width as usize)?;
+            let row = words
+                .iter()
+                .map(|word| parse_track(word))
+                .collect::<Result<Vec<u8>, String>>()?;
+            // rows are written top first, but a solution starts from the bottom row
+            level
+                .solution
+                .get_or_insert_with(Vec::new)
+                .splice(0..0, row);
+        }
+        _ => {
+            return Err(format!(
+                "unknown tile {:?} in level {:?}, expected one of # + o * p s =",
+                symbol, level.name
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn parse_list<T>(word: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    word.split(',').map(parse).collect()
+}
+
+/// Turns `width`x`height` into (width, height).
+fn parse_size(word: &str) -> Result<(u8, u8), String> {
+    let invalid = || format!("{:?} is not a size like \"7x7\"", word);
+    let (width, height) = word.split_once('x').ok_or_else(invalid)?;
+    let width: u8 = width.parse().map_err(|_| invalid())?;
+    let height: u8 = height.parse().map_err(|_| invalid())?;
+    let size_range = 1..=MAX_YARD_SIZE;
+    if !size_range.contains(&width) || !size_range.contains(&height) {
+        return Err(format!(
+            "{:?} is not between 1x1 and {}x{}",
+            word, MAX_YARD_SIZE, MAX_YARD_SIZE
+        ));
+    }
+    Ok((width, height))
+}
+
+/// Turns `x,y` (with y counting down from the top of a `width` by `height` yard) into (row, col).
+fn parse_position(word: &str, width: u8, height: u8) -> Result<(u8, u8), String> {
+    let invalid = || format!("{:?} is not a position like \"3,4\"", word);
+    let (x, y) = word.split_once(',').ok_or_else(invalid)?;
+    let x: u8 = x.parse().map_err(|_| invalid())?;
+    let y: u8 = y.parse().map_err(|_| invalid())?;
+    if x >= width || y >= height {
+        return Err(format!(
+            "{:?} is outside of the {}x{} grid",
+            word, width, height
+        ));
+    }
+    Ok((height - 1 - y, x))
+}
+
+fn parse_track(word: &str) -> Result<u8, String> {
+    if word == NO_TRACK {
+        return Ok(0);
+    }
+    u8::from_str_radix(word, 16)
+        .ok()
+        .filter(|data| word.len() == 2 && TileConnections::try_from_data(*data).is_some())
+        .ok_or_else(|| format!("{:?} is not a tile's tracks", word))
+}
+
+fn parse_color(word: &str) -> Result<TrainColor, String> {
+    ALL_COLORS
+        .into_iter()
+        .find(|color| color.to_str().eq_ignore_ascii_case(word))
+        .ok_or_else(|| format!("{:?} is not a train color", word))
+}
+
+fn parse_dir(word: &str) -> Result<Dir, String> {
+    Dir::all_dirs()
+        .find(|dir| dir_name(*dir).eq_ignore_ascii_case(word))
+        .ok_or_else(|| format!("{:?} is not a direction", word))
+}
+
+const ALL_COLORS: [TrainColor; 7] = [
+    TrainColor::Brown,
+    TrainColor::Red,
+    TrainColor::Blue,
+    TrainColor::Yellow,
+    TrainColor::Purple,
+    TrainColor::Green,
+    TrainColor::Orange,
+];
+
+fn dir_name(dir: Dir) -> &'static str {
+    match dir {
+        Dir::Up => "up",
+        Dir::Right => "right",
+        Dir::Down => "down",
+        Dir::Left => "left",
+    }
+}
+
+/// Writes `levels` out in the `levels.txt` format, so that `parse_levels_txt` gives them back unchanged.
+pub fn levels_to_txt(levels: &[LevelLoadInfo]) -> String {
+    let mut text = String::new();
+    let mut curr_city: Option<&str> = None;
+
+    for level in levels {
+        if curr_city != Some(level.city.as_str()) {
+            if curr_city.is_some() {
+                text.push_str(END_OF_CITY);
+                text.push_str("\n\n");
+            }
+            text.push_str(&format!("{}{}\n\n", CITY_PREFIX, level.city));
+            curr_city = Some(&level.city);
+        }
+        write_level(&mut text, level);
+    }
+    if curr_city.is_some() {
+        text.push_str(END_OF_CITY);
+        text.push('\n');
+    }
+    text
+}
+
+fn write_level(text: &mut String, level: &LevelLoadInfo) {
+    text.push_str(&format!("{}:{}\n", level.name, level.stars));
+    if (level.width, level.height) != (NUM_COLS, NUM_ROWS) {
+        text.push_str(&format!("# {}x{}\n", level.width, level.height));
+    }
+    let position_str = |position| position_str(position, level.height);
+    for source in level.sources.iter() {
+        text.push_str(&format!(
+            "+ {} {} {}\n",
+            position_str(source.position),
+            colors_str(&source.trains),
+            dir_name(source.out_dir)
+        ));
+    }
+    for sink in level.sinks.iter() {
+        let dirs: Vec<&str> = sink.in_dirs.iter().map(|dir| dir_name(*dir)).collect();
+        text.push_str(&format!(
+            "o {} {} {}\n",
+            position_str(sink.position),
+            colors_str(&sink.trains),
+            dirs.join(",")
+        ));
+    }
+    if !level.rocks.is_empty() {
+        let rocks: Vec<String> = level.rocks.iter().map(|rock| position_str(*rock)).collect();
+        text.push_str(&format!("* {}\n", rocks.join(" ")));
+    }
+    for painter in level.painters.iter() {
+        text.push_str(&format!(
+            "p {} {} {},{}\n",
+            position_str(painter.position),
+            painter.color.to_str().to_lowercase(),
+            dir_name(painter.in_dir),
+            dir_name(painter.out_dir)
+        ));
+    }
+    for splitter in level.splitters.iter() {
+        text.push_str(&format!(
+            "s {} {}\n",
+            position_str(splitter.position),
+            dir_name(splitter.in_dir)
+        ));
+    }
+    if let Some(solution) = &level.solution {
+        text.push_str(&solution_to_txt(solution, level.width));
+    }
+    text.push_str(END_OF_LEVEL);
+    text.push_str("\n\n");
+}
+
+/// Writes a solution for a yard `width` tiles wide as the `=` lines of a level.
+pub fn solution_to_txt(solution: &[u8], width: u8) -> String {
+    let mut text = String::new();
+    for row in solution.chunks(width.max(1) as usize).rev() {
+        let tracks: Vec<String> = row
+            .iter()
+            .map(|data| match data {
+                0 => NO_TRACK.to_string(),
+                data => format!("{:02x}", data),
+            })
+            .collect();
+        text.push_str(&format!("{} {}\n", SOLUTION_ROW, tracks.join(" ")));
+    }
+    text
+}
+
+fn position_str((row, col): (u8, u8), height: u8) -> String {
+    format!("{},{}", col, height.saturating_sub(1).saturating_sub(row))
+}
+
+fn colors_str(trains: &[TrainColor]) -> String {
+    let colors: Vec<String> = trains
+        .iter()
+        .map(|color| color.to_str().to_lowercase())
+        .collect();
+    colors.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STOCK_LEVELS: &str = include_str!("../../../assets/levels.txt");
+
+    /// Two cities, with every kind of tile, written the way `levels_to_txt` writes them.
+    const EVERY_TILE: &str = "\
+CITY:Abbotsford
+
+Red Line:1
++ 1,3 red right
+o 5,3 red left
+---
+
+Everything:4
+# 5x4
++ 0,0 red,blue right
+o 4,0 purple up,left
+* 1,1 2,2
+p 3,1 yellow left,down
+s 2,3 down
+= 0c 0d 0e .. ..
+= .. .. 09 .. ..
+= .. .. 08 .. ..
+= .. .. 98 0d 0e
+---
+
+----
+
+CITY:Brampton
+
+Unrated:0
+o 6,6 brown down
+---
+
+----
+";
+
+    const EVERY_TILE_SOLUTION: &str = "\
+= 0c 0d 0e .. ..
+= .. .. 09 .. ..
+= .. .. 08 .. ..
+= .. .. 98 0d 0e
+";
+
+    fn parse_error(text: &str) -> LevelsTxtError {
+        parse_levels_txt(text).expect_err("the text should not parse")
+    }
+
+    #[test]
+    fn stock_levels_round_trip() {
+        let levels = parse_levels_txt(STOCK_LEVELS).unwrap();
+        assert!(!levels.is_empty());
+        let text = levels_to_txt(&levels);
+        assert_eq!(parse_levels_txt(&text).unwrap(), levels);
+        assert_eq!(levels_to_txt(&parse_levels_txt(&text).unwrap()), text);
+    }
+
+    #[test]
+    fn every_kind_of_tile_round_trips() {
+        let levels = parse_levels_txt(EVERY_TILE).unwrap();
+        let names: Vec<(&str, &str, u8)> = levels
+            .iter()
+            .map(|level| (level.city.as_str(), level.name.as_str(), level.stars))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Abbotsford", "Red Line", 1),
+                ("Abbotsford", "Everything", 4),
+                ("Brampton", "Unrated", 0),
+            ]
+        );
+        assert_eq!(levels_to_txt(&levels), EVERY_TILE);
+    }
+
+    #[test]
+    fn tiles_are_read_into_the_level() {
+        let level = &parse_levels_txt(EVERY_TILE).unwrap()[1];
+        // y counts down from the top of the 4 row yard, while rows count up from the bottom
+        assert_eq!(
+            level.sources,
+            vec![SourceInfo {
+                trains: vec![TrainColor::Red, TrainColor::Blue],
+                out_dir: Dir::Right,
+                position: (3, 0),
+            }]
+        );
+        assert_eq!(
+            level.sinks,
+            vec![SinkInfo {
+                trains: vec![TrainColor::Purple],
+                in_dirs: vec![Dir::Up, Dir::Left],
+                position: (3, 4),
+            }]
+        );
+        assert_eq!(level.rocks, vec![(2, 1), (1, 2)]);
+        assert_eq!(
+            level.painters,
+            vec![PainterInfo {
+                color: TrainColor::Yellow,
+                in_dir: Dir::Left,
+                out_dir: Dir::Down,
+                position: (2, 3),
+            }]
+        );
+        assert_eq!(
+            level.splitters,
+            vec![SplitterInfo {
+                in_dir: Dir::Down,
+                position: (0, 2),
+            }]
+        );
+    }
+
+    #[test]
+    fn solution_rows_are_read_bottom_row_first() {
+        let levels = parse_levels_txt(EVERY_TILE).unwrap();
+        assert_eq!(levels[0].solution, None);
+        let solution = levels[1].solution.as_ref().unwrap();
+        assert_eq!(solution.len(), 5 * 4);
+        assert_eq!(&solution[..5], &[0, 0, 0x98, 0x0d, 0x0e]);
+        assert_eq!(&solution[15..], &[0x0c, 0x0d, 0x0e, 0, 0]);
+        assert_eq!(solution_to_txt(solution, 5), EVERY_TILE_SOLUTION);
+    }
+
+    #[test]
+    fn solutions_are_replaced_and_the_rest_is_kept() {
+        let text = format!("// a comment\n{}", EVERY_TILE);
+        let mut levels = parse_levels_txt(EVERY_TILE).unwrap();
+        let mut red_line = vec![0; 7 * 7];
+        red_line[3 * 7 + 2..3 * 7 + 5].fill(0x0d);
+        levels[0].solution = Some(red_line);
+        levels[1].solution = Some(vec![0; 5 * 4]);
+
+        let solution_for = |name: &str| {
+            let level = levels[..2].iter().find(|level| level.name == name)?;
+            level.solution.as_deref()
+        };
+        let replaced = replace_solutions(&text, solution_for).unwrap();
+        assert_eq!(
+            replaced,
+            format!("// a comment\n{}", levels_to_txt(&levels))
+        );
+    }
+
+    #[test]
+    fn solution_is_checked() {
+        let cases = [
+            (
+                "Short:1\n# 3x2\n= .. ..\n= .. .. ..\n---\n",
+                3,
+                "expected 3 values",
+            ),
+            (
+                "Track:1\n# 2x1\n= .. zz\n---\n",
+                3,
+                "is not a tile's tracks",
+            ),
+            ("Track:1\n# 2x1\n= .. 0\n---\n", 3, "is not a tile's tracks"),
+            (
+                "Rows:1\n# 2x2\n= .. ..\n---\n",
+                4,
+                "has 1 rows, but the yard is 2 tall",
+            ),
+            (
+                "Late:1\n= .. .. .. .. .. .. ..\n# 7x1\n---\n",
+                3,
+                "has to come before",
+            ),
+        ];
+        for (text, line, message) in cases {
+            let error = parse_error(text);
+            assert_eq!(error.line, line, "{:?}: {}", text, error);
+            assert!(error.message.contains(message), "{:?}: {}", text, error);
+        }
+    }
+
+    #[test]
+    fn size_line_sets_the_size_of_the_yard() {
+        let levels = parse_levels_txt(EVERY_TILE).unwrap();
+        assert_eq!((levels[0].width, levels[0].height), (NUM_COLS, NUM_ROWS));
+        assert_eq!((levels[1].width, levels[1].height), (5, 4));
+
+        let wide = parse_levels_txt("Wide:1\n# 12x3\n+ 11,0 red left\n---\n").unwrap();
+        assert_eq!((wide[0].width, wide[0].height), (12, 3));
+        assert_eq!(wide[0].sources[0].position, (2, 11));
+    }
+
+    #[test]
+    fn size_line_is_checked() {
+        let error = parse_error("Late:1\n+ 1,1 red right\n# 5x5\n---\n");
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("has to come before"), "{}", error);
+
+        for size in ["0x5", "13x7", "7", "7xseven"] {
+            let error = parse_error(&format!("Sized:1\n# {}\n---\n", size));
+            assert_eq!(error.line, 2, "{}", size);
+        }
+
+        // positions are checked against the level's own size, not the usual 7x7
+        let error = parse_error("Small:1\n# 3x3\n+ 3,0 red right\n---\n");
+        assert_eq!(error.line, 3);
+        assert!(
+            error.message.contains("outside of the 3x3 grid"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn errors_point_at_their_line() {
+        let cases = [
+            (
+                "// a comment\n\nBad Stars:x\n---\n",
+                3,
+                "is not a number of stars",
+            ),
+            ("Tiles:1\n+ 1,1 red right\nq 1,1\n---\n", 3, "unknown tile"),
+            (
+                "Colors:1\n+ 1,1 pink right\n---\n",
+                2,
+                "is not a train color",
+            ),
+            ("Dirs:1\no 1,1 red sideways\n---\n", 2, "is not a direction"),
+            (
+                "Painter:1\np 1,1 red left\n---\n",
+                2,
+                "exactly two directions",
+            ),
+            ("Words:1\n+ 1,1 red\n---\n", 2, "expected 3 values"),
+            ("---\n", 1, "without a level to end"),
+            (
+                "Open:1\n+ 1,1 red right\nCITY:Next\n",
+                3,
+                "before the next city",
+            ),
+            (
+                "Open:1\n+ 1,1 red right\n----\n",
+                3,
+                "before the end of the city",
+            ),
+            ("Open:1\n+ 1,1 red right\n", 2, "is missing its"),
+            ("just some words\n", 1, "expected a level header"),
+        ];
+        for (text, line, message) in cases {
+            let error = parse_error(text);
+            assert_eq!(error.line, line, "{:?}: {}", text, error);
+            assert!(error.message.contains(message), "{:?}: {}", text, error);
+            assert!(error.to_string().starts_with(&format!("line {}: ", line)));
+        }
+    }
+
+    #[test]
+    fn nothing_after_end_here_is_read() {
+        let levels = parse_levels_txt("Kept:1\n---\n// END HERE\nnot a level at all\n").unwrap();
+        assert_eq!(levels.len(), 1);
+    }
+}
@@ -0,0 +1,24 @@
+//! The rules of trainyard, without any rendering, input or audio.
+//!
+//! A level is described by a `LevelLoadInfo`, which is turned into a `Yard`.
+//! Tracks are drawn by adding connections to the yard's tiles, and `Yard::step` then moves every train forward
+//! by one tick, returning the events that happened along the way.
+
+pub mod connections;
+pub mod direction;
+pub mod level;
+pub mod levels_txt;
+pub mod lint;
+pub mod replay;
+pub mod score;
+pub mod solver;
+pub mod tiles;
+pub mod trains;
+pub mod yard;
+pub mod yard_code;
+
+/// The size of the yard in the original game, which is what levels that don't give their own size get.
+pub const NUM_ROWS: u8 = 7;
+pub const NUM_COLS: u8 = 7;
+/// The most rows or columns a level can have.
+pub const MAX_YARD_SIZE: u8 = 12;
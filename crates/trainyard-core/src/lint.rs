@@ -0,0 +1,471 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::connections::TileConnections;
+use crate::direction::Dir;
+use crate::level::LevelLoadInfo;
+use crate::solver::check_solution;
+use crate::tiles::TileConstructionInfo;
+use crate::trains::TrainColor;
+use crate::MAX_YARD_SIZE;
+
+/// A problem with a level that would either crash the game when it loads, or make the level impossible to beat.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LintError {
+    InvalidSize {
+        level: String,
+        width: u8,
+        height: u8,
+    },
+    OutOfBounds {
+        level: String,
+        tile: &'static str,
+        position: (u8, u8),
+        width: u8,
+        height: u8,
+    },
+    OverlappingTiles {
+        level: String,
+        position: (u8, u8),
+    },
+    SinkWithoutEntries {
+        level: String,
+        position: (u8, u8),
+    },
+    ExitFacesEdge {
+        level: String,
+        tile: &'static str,
+        position: (u8, u8),
+        dir: Dir,
+    },
+    DuplicateLevelName {
+        level: String,
+    },
+    UnreachableColor {
+        level: String,
+        position: (u8, u8),
+        color: TrainColor,
+    },
+    NotEnoughTrains {
+        level: String,
+        supplied: usize,
+        demanded: usize,
+    },
+    SolutionDoesNotWin {
+        level: String,
+    },
+    InvalidTrackData {
+        level: String,
+        position: (u8, u8),
+        data: u8,
+    },
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintError::InvalidSize {
+                level,
+                width,
+                height,
+            } => write!(
+                f,
+                "{}: the grid is {}x{}, but it has to be between 1x1 and {}x{}",
+                level, width, height, MAX_YARD_SIZE, MAX_YARD_SIZE
+            ),
+            LintError::OutOfBounds {
+                level,
+                tile,
+                position,
+                width,
+                height,
+            } => write!(
+                f,
+                "{}: {} at {:?} is outside of the {}x{} grid",
+                level, tile, position, width, height
+            ),
+            LintError::OverlappingTiles { level, position } => {
+                write!(f, "{}: more than one tile at {:?}", level, position)
+            }
+            LintError::SinkWithoutEntries { level, position } => {
+                write!(
+                    f,
+                    "{}: sink at {:?} has no entry directions",
+                    level, position
+                )
+            }
+            LintError::ExitFacesEdge {
+                level,
+                tile,
+                position,
+                dir,
+            } => write!(
+                f,
+                "{}: {} at {:?} sends trains {:?}, off the edge of the grid",
+                level, tile, position, dir
+            ),
+            LintError::DuplicateLevelName { level } => {
+                write!(f, "{}: there is more than one level with this name", level)
+            }
+            LintError::UnreachableColor {
+                level,
+                position,
+                color,
+            } => write!(
+                f,
+                "{}: sink at {:?} wants a {} train, which can't be made from this level's trains",
+                level,
+                position,
+                color.to_str()
+            ),
+            LintError::NotEnoughTrains {
+                level,
+                supplied,
+                demanded,
+            } => write!(
+                f,
+                "{}: sinks want {} trains, but sources only supply {} and there are no splitters",
+                level, demanded, supplied
+            ),
+            LintError::SolutionDoesNotWin { level } => {
+                write!(f, "{}: the stored solution doesn't beat this level", level)
+            }
+            LintError::InvalidTrackData {
+                level,
+                position,
+                data,
+            } => write!(
+                f,
+                "{}: the stored solution has tracks that can't be drawn ({:#04x}) at {:?}",
+                level, data, position
+            ),
+        }
+    }
+}
+
+/// How long a stored solution gets to beat its level before it's considered broken.
+const SOLUTION_MAX_TICKS: usize = 200;
+
+fn tile_name(tile: &TileConstructionInfo) -> &'static str {
+    match tile {
+        TileConstructionInfo::DrawableTile => "track",
+        TileConstructionInfo::Rock => "rock",
+        TileConstructionInfo::SourceTile { .. } => "source",
+        TileConstructionInfo::SinkTile { .. } => "sink",
+        TileConstructionInfo::Painter { .. } => "painter",
+        TileConstructionInfo::Splitter { .. } => "splitter",
+    }
+}
+
+fn is_on_grid(level: &LevelLoadInfo, row: u8, col: u8) -> bool {
+    row < level.height && col < level.width
+}
+
+fn faces_edge(level: &LevelLoadInfo, row: u8, col: u8, dir: Dir) -> bool {
+    match dir {
+        Dir::Up => row == level.height - 1,
+        Dir::Right => col == level.width - 1,
+        Dir::Down => row == 0,
+        Dir::Left => col == 0,
+    }
+}
+
+/// Checks every level, along with problems that involve more than one level (such as duplicate names).
+pub fn lint_levels(levels: &[LevelLoadInfo]) -> Vec<LintError> {
+    let mut errors = Vec::new();
+
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for level in levels {
+        *name_counts.entry(level.name.as_str()).or_default() += 1;
+    }
+    let mut reported_names = HashSet::new();
+    for level in levels {
+        if name_counts[level.name.as_str()] > 1 && reported_names.insert(level.name.as_str()) {
+            errors.push(LintError::DuplicateLevelName {
+                level: level.name.clone(),
+            });
+        }
+        errors.extend(lint_level(level));
+    }
+
+    errors
+}
+
+pub fn lint_level(level: &LevelLoadInfo) -> Vec<LintError> {
+    let mut errors = Vec::new();
+    let name = || level.name.clone();
+
+    let size_range = 1..=MAX_YARD_SIZE;
+    if !size_range.contains(&level.width) || !size_range.contains(&level.height) {
+        // every other check depends on the size of the grid
+        return vec![LintError::InvalidSize {
+            level: name(),
+            width: level.width,
+            height: level.height,
+        }];
+    }
+
+    let mut occupied = HashSet::new();
+    let mut reported_overlaps = HashSet::new();
+    for (tile, (row, col)) in level.tiles() {
+        if !is_on_grid(level, row, col) {
+            errors.push(LintError::OutOfBounds {
+                level: name(),
+                tile: tile_name(&tile),
+                position: (row, col),
+                width: level.width,
+                height: level.height,
+            });
+            continue;
+        }
+        if !occupied.insert((row, col)) && reported_overlaps.insert((row, col)) {
+            errors.push(LintError::OverlappingTiles {
+                level: name(),
+                position: (row, col),
+            });
+        }
+
+        let exits = match tile {
+            TileConstructionInfo::SourceTile { out, .. } => vec![out],
+            TileConstructionInfo::Painter { out_dir, .. } => vec![out_dir],
+            TileConstructionInfo::Splitter { in_dir } => {
+                vec![in_dir.rotate_cw(), in_dir.rotate_ccw()]
+            }
+            _ => vec![],
+        };
+        for dir in exits {
+            if faces_edge(level, row, col, dir) {
+                errors.push(LintError::ExitFacesEdge {
+                    level: name(),
+                    tile: tile_name(&tile),
+                    position: (row, col),
+                    dir,
+                });
+            }
+        }
+    }
+
+    for sink in level.sinks.iter() {
+        if sink.in_dirs.is_empty() {
+            errors.push(LintError::SinkWithoutEntries {
+                level: name(),
+                position: sink.position,
+            });
+        }
+    }
+
+    let reachable = reachable_colors(level);
+    for sink in level.sinks.iter() {
+        let mut reported_colors = HashSet::new();
+        for color in sink.trains.iter().copied() {
+            if !reachable.contains(&color) && reported_colors.insert(color) {
+                errors.push(LintError::UnreachableColor {
+                    level: name(),
+                    position: sink.position,
+                    color,
+                });
+            }
+        }
+    }
+
+    // trains can merge together, but only a splitter can turn one train into two.
+    if level.splitters.is_empty() {
+        let supplied: usize = level.sources.iter().map(|s| s.trains.len()).sum();
+        let demanded: usize = level.sinks.iter().map(|s| s.trains.len()).sum();
+        if demanded > supplied {
+            errors.push(LintError::NotEnoughTrains {
+                level: name(),
+                supplied,
+                demanded,
+            });
+        }
+    }
+
+    if let Some(solution) = &level.solution {
+        for (index, data) in solution.iter().enumerate() {
+            if TileConnections::try_from_data(*data).is_none() {
+                let width = level.width as usize;
+                errors.push(LintError::InvalidTrackData {
+                    level: name(),
+                    position: ((index / width) as u8, (index % width) as u8),
+                    data: *data,
+                });
+            }
+        }
+    }
+
+    // only run the solution if the level itself is sound, since a broken level can't be turned into a yard
+    if let Some(solution) = level.solution.as_ref().filter(|_| errors.is_empty()) {
+        if solution.len() != level.num_tiles()
+            || !check_solution(level, solution, SOLUTION_MAX_TICKS)
+        {
+            errors.push(LintError::SolutionDoesNotWin { level: name() });
+        }
+    }
+
+    errors
+}
+
+/// Every color that could possibly arrive at a sink, by mixing, painting and splitting the trains from the sources.
+fn reachable_colors(level: &LevelLoadInfo) -> HashSet<TrainColor> {
+    let mut colors: HashSet<TrainColor> = level
+        .sources
+        .iter()
+        .flat_map(|source| source.trains.iter().copied())
+        .collect();
+    if colors.is_empty() {
+        return colors;
+    }
+    colors.extend(level.painters.iter().map(|painter| painter.color));
+
+    loop {
+        let mut new_colors = colors.clone();
+        for c1 in colors.iter() {
+            for c2 in colors.iter() {
+                new_colors.insert(c1.mix_with(*c2));
+            }
+            if !level.splitters.is_empty() {
+                let (left, right) = c1.split();
+                new_colors.insert(left);
+                new_colors.insert(right);
+            }
+        }
+        if new_colors.len() == colors.len() {
+            return colors;
+        }
+        colors = new_colors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::SplitterInfo;
+    use crate::levels_txt::parse_levels_txt;
+
+    const STOCK_LEVELS: &str = include_str!("../../../assets/levels.txt");
+
+    /// A red train goes from the source at (3, 1) to the sink at (3, 5).
+    fn red_line() -> LevelLoadInfo {
+        let text = "Red Line:1\n+ 1,3 red right\no 5,3 red left\n---\n";
+        parse_levels_txt(text).unwrap().remove(0)
+    }
+
+    #[test]
+    fn the_stock_levels_lint_clean() {
+        let levels = parse_levels_txt(STOCK_LEVELS).unwrap();
+        assert_eq!(lint_levels(&levels), []);
+    }
+
+    #[test]
+    fn tiles_must_be_on_the_grid() {
+        let mut level = red_line();
+        level.rocks.push((7, 2));
+        assert_eq!(
+            lint_level(&level),
+            [LintError::OutOfBounds {
+                level: level.name.clone(),
+                tile: "rock",
+                position: (7, 2),
+                width: 7,
+                height: 7,
+            }]
+        );
+    }
+
+    #[test]
+    fn tiles_must_not_overlap() {
+        let mut level = red_line();
+        level.rocks.push((3, 1));
+        assert_eq!(
+            lint_level(&level),
+            [LintError::OverlappingTiles {
+                level: level.name.clone(),
+                position: (3, 1),
+            }]
+        );
+    }
+
+    #[test]
+    fn sinks_need_a_way_in() {
+        let mut level = red_line();
+        level.sinks[0].in_dirs.clear();
+        assert_eq!(
+            lint_level(&level),
+            [LintError::SinkWithoutEntries {
+                level: level.name.clone(),
+                position: (3, 5),
+            }]
+        );
+    }
+
+    #[test]
+    fn trains_must_not_be_sent_off_the_grid() {
+        let mut level = red_line();
+        level.sources[0].position = (3, 6);
+        assert_eq!(
+            lint_level(&level),
+            [LintError::ExitFacesEdge {
+                level: level.name.clone(),
+                tile: "source",
+                position: (3, 6),
+                dir: Dir::Right,
+            }]
+        );
+    }
+
+    #[test]
+    fn level_names_must_be_unique() {
+        let levels = [red_line(), red_line()];
+        assert_eq!(
+            lint_levels(&levels),
+            [LintError::DuplicateLevelName {
+                level: String::from("Red Line"),
+            }]
+        );
+    }
+
+    #[test]
+    fn sinks_must_want_colors_that_can_be_made() {
+        let mut level = red_line();
+        level.sinks[0].trains = vec![TrainColor::Blue];
+        assert_eq!(
+            lint_level(&level),
+            [LintError::UnreachableColor {
+                level: level.name.clone(),
+                position: (3, 5),
+                color: TrainColor::Blue,
+            }]
+        );
+
+        // a blue painter makes blue trains
+        let painted = "Red Line:1\n+ 1,3 red right\no 5,3 blue left\np 3,3 blue left,right\n---\n";
+        assert_eq!(lint_level(&parse_levels_txt(painted).unwrap()[0]), []);
+    }
+
+    #[test]
+    fn sinks_must_not_want_more_trains_than_the_sources_send() {
+        let mut level = red_line();
+        level.sinks[0].trains = vec![TrainColor::Red, TrainColor::Red];
+        assert_eq!(
+            lint_level(&level),
+            [LintError::NotEnoughTrains {
+                level: level.name.clone(),
+                supplied: 1,
+                demanded: 2,
+            }]
+        );
+
+        // a splitter can turn the one train into two
+        level.splitters.push(SplitterInfo {
+            in_dir: Dir::Up,
+            position: (1, 3),
+        });
+        assert!(!lint_level(&level)
+            .iter()
+            .any(|error| matches!(error, LintError::NotEnoughTrains { .. })));
+    }
+}
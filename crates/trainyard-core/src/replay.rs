@@ -0,0 +1,186 @@
+//! Recordings of the trains running on a level.
+//!
+//! A replay keeps the level and the tracks that were drawn on it, along with everything that happened on every tick.
+//! `Yard::step` is deterministic, so the yard at any tick can be worked out again from the level and the tracks. The
+//! events are kept anyway, so that a replay can be checked against the rules that are being played with now.
+
+use serde::{Deserialize, Serialize};
+
+use crate::level::LevelLoadInfo;
+use crate::yard::{TrainActivityWithLocation, Yard, YardProcessTickResult};
+
+/// Bump this whenever the layout of `Replay` changes in a way that old replays can't be read as-is.
+pub const REPLAY_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub level: LevelLoadInfo,
+    /// The tracks the trains ran on, in the same format as `Yard::get_progress`.
+    pub drawn_tracks: Vec<u8>,
+    /// What `Yard::step` returned on each tick, in order.
+    pub ticks: Vec<YardProcessTickResult>,
+}
+
+impl Replay {
+    /// Runs the trains on `level` with `drawn_tracks` until they win, a train crashes, or `max_ticks` have passed.
+    pub fn record(level: &LevelLoadInfo, drawn_tracks: &[u8], max_ticks: usize) -> Self {
+        let mut replay = Replay {
+            version: REPLAY_VERSION,
+            level: level.clone(),
+            drawn_tracks: drawn_tracks.to_vec(),
+            ticks: Vec::new(),
+        };
+        let mut yard = replay.yard_at(0);
+        while replay.ticks.len() < max_ticks {
+            let result = yard.step();
+            let crashed = result.has_crashed();
+            replay.ticks.push(result);
+            if crashed || yard.has_won() {
+                break;
+            }
+        }
+        replay
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    pub fn has_crashed(&self) -> bool {
+        self.ticks.iter().any(|tick| tick.has_crashed())
+    }
+
+    /// Whether every train was delivered without any of them crashing.
+    pub fn has_won(&self) -> bool {
+        !self.has_crashed() && self.yard_at(self.len()).has_won()
+    }
+
+    /// The yard after the first `tick` ticks of the replay, or after all of them if there aren't that many.
+    pub fn yard_at(&self, tick: usize) -> Yard {
+        let mut yard = self.level.to_yard();
+        yard.apply_progress(&self.drawn_tracks);
+        for _ in 0..tick.min(self.len()) {
+            yard.step();
+        }
+        yard
+    }
+
+    /// Where every train was on each tick, so that the replay can be drawn without running a yard alongside it.
+    pub fn train_activity(&self) -> Vec<Vec<TrainActivityWithLocation>> {
+        let mut yard = self.yard_at(0);
+        self.ticks
+            .iter()
+            .map(|_| {
+                yard.step();
+                yard.train_activity.clone()
+            })
+            .collect()
+    }
+
+    /// Whether running the tracks again gives the same events that were recorded. A replay that doesn't was
+    /// recorded with different rules or was edited by hand, so the yard it shows wouldn't match its events.
+    pub fn verify(&self) -> bool {
+        let mut yard = self.yard_at(0);
+        self.ticks.iter().all(|tick| yard.step() == *tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels_txt::parse_levels_txt;
+
+    const MAX_TICKS: usize = 100;
+
+    /// A red train going straight from its source to its sink, with the track it needs as the level's solution.
+    const RED_LINE: &str = "\
+Red Line:1
++ 1,3 red right
+o 5,3 red left
+= .. .. .. .. .. .. ..
+= .. .. .. .. .. .. ..
+= .. .. .. .. .. .. ..
+= .. .. 0d 0d 0d .. ..
+= .. .. .. .. .. .. ..
+= .. .. .. .. .. .. ..
+= .. .. .. .. .. .. ..
+---
+";
+
+    fn red_line() -> (LevelLoadInfo, Vec<u8>) {
+        let mut level = parse_levels_txt(RED_LINE).unwrap().remove(0);
+        let solution = level.solution.take().unwrap();
+        (level, solution)
+    }
+
+    #[test]
+    fn records_until_the_trains_win() {
+        let (level, solution) = red_line();
+        let replay = Replay::record(&level, &solution, MAX_TICKS);
+        assert!(replay.has_won());
+        assert!(!replay.has_crashed());
+        assert!(replay.len() < MAX_TICKS);
+        assert!(replay.verify());
+        assert_eq!(replay.train_activity().len(), replay.len());
+    }
+
+    #[test]
+    fn records_until_a_train_crashes() {
+        let (level, solution) = red_line();
+        let replay = Replay::record(&level, &vec![0; solution.len()], MAX_TICKS);
+        assert!(replay.has_crashed());
+        assert!(!replay.has_won());
+        assert!(replay.ticks.last().unwrap().has_crashed());
+        assert!(replay.verify());
+    }
+
+    #[test]
+    fn records_no_more_than_max_ticks() {
+        let (level, solution) = red_line();
+        let replay = Replay::record(&level, &solution, 2);
+        assert_eq!(replay.len(), 2);
+        assert!(!replay.has_won());
+        assert!(!replay.has_crashed());
+    }
+
+    #[test]
+    fn yard_at_stops_at_the_end_of_the_replay() {
+        let (level, solution) = red_line();
+        let replay = Replay::record(&level, &solution, MAX_TICKS);
+        assert!(!replay.yard_at(0).has_won());
+        assert!(replay.yard_at(replay.len()).has_won());
+        assert_eq!(
+            replay.yard_at(replay.len() + 10).get_progress(),
+            replay.yard_at(replay.len()).get_progress()
+        );
+    }
+
+    #[test]
+    fn verify_catches_a_replay_that_doesnt_match_its_tracks() {
+        let (level, solution) = red_line();
+        let mut replay = Replay::record(&level, &solution, MAX_TICKS);
+        replay.drawn_tracks[3 * 7 + 3] = 0;
+        assert!(!replay.verify());
+
+        let mut replay = Replay::record(&level, &solution, MAX_TICKS);
+        replay.ticks.swap(0, 1);
+        assert!(!replay.verify());
+    }
+
+    #[test]
+    fn survives_being_saved() {
+        let (level, solution) = red_line();
+        let replay = Replay::record(&level, &solution, MAX_TICKS);
+        let loaded: Replay =
+            serde_json::from_str(&serde_json::to_string(&replay).unwrap()).unwrap();
+        assert_eq!(loaded.version, REPLAY_VERSION);
+        assert_eq!(loaded.ticks, replay.ticks);
+        assert!(loaded.verify());
+        assert!(loaded.has_won());
+    }
+}
@@ -0,0 +1,176 @@
+//! How well a solution solves its level, so that solutions that all win can be compared with each other.
+//!
+//! Every part of a score is better when it's lower.
+
+use serde::{Deserialize, Serialize};
+
+use crate::level::LevelLoadInfo;
+use crate::replay::Replay;
+use crate::tiles::tile::TileEvent;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Score {
+    /// The number of tiles with track drawn on them.
+    pub tracks: u32,
+    /// The number of ticks until the last train was delivered.
+    pub ticks: u32,
+    /// The number of times a train switched the tracks on a tile.
+    pub switches: u32,
+}
+
+/// Which parts of a score beat the best there was before it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NewRecords {
+    pub tracks: bool,
+    pub ticks: bool,
+    pub switches: bool,
+}
+
+impl NewRecords {
+    pub fn any(&self) -> bool {
+        self.tracks || self.ticks || self.switches
+    }
+}
+
+impl Score {
+    /// Scores the run in `replay`, or returns `None` if it didn't win.
+    pub fn of_replay(replay: &Replay) -> Option<Self> {
+        if !replay.has_won() {
+            return None;
+        }
+        let switches = replay
+            .ticks
+            .iter()
+            .flat_map(|tick| tick.all_events())
+            .filter(|e| e.event == TileEvent::SwitchActivePassive)
+            .count();
+        Some(Score {
+            tracks: replay
+                .drawn_tracks
+                .iter()
+                .filter(|data| **data != 0)
+                .count() as u32,
+            ticks: replay.len() as u32,
+            switches: switches as u32,
+        })
+    }
+
+    /// Runs `drawn_tracks` on `level` and scores them, or returns `None` if they don't win within `max_ticks`.
+    pub fn of_solution(
+        level: &LevelLoadInfo,
+        drawn_tracks: &[u8],
+        max_ticks: usize,
+    ) -> Option<Self> {
+        Self::of_replay(&Replay::record(level, drawn_tracks, max_ticks))
+    }
+
+    /// The best of each part of the two scores, which may come from different solutions.
+    pub fn best_of(&self, other: &Score) -> Score {
+        Score {
+            tracks: self.tracks.min(other.tracks),
+            ticks: self.ticks.min(other.ticks),
+            switches: self.switches.min(other.switches),
+        }
+    }
+
+    /// Which parts of this score beat `best`. Nothing is a new record when there's no best yet, since there was
+    /// nothing to beat.
+    pub fn new_records(&self, best: Option<&Score>) -> NewRecords {
+        match best {
+            Some(best) => NewRecords {
+                tracks: self.tracks < best.tracks,
+                ticks: self.ticks < best.ticks,
+                switches: self.switches < best.switches,
+            },
+            None => NewRecords::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels_txt::parse_levels_txt;
+
+    const MAX_TICKS: usize = 100;
+
+    /// Two red trains from one source, which a switch sends to two different sinks.
+    const FORK: &str = "\
+Fork:1
++ 0,3 red,red right
+o 3,3 red left
+o 1,2 red down
+= .. .. .. .. .. .. ..
+= .. .. .. .. .. .. ..
+= .. .. .. .. .. .. ..
+= .. cd 0d .. .. .. ..
+= .. .. .. .. .. .. ..
+= .. .. .. .. .. .. ..
+= .. .. .. .. .. .. ..
+---
+";
+
+    fn fork() -> (LevelLoadInfo, Vec<u8>) {
+        let mut level = parse_levels_txt(FORK).unwrap().remove(0);
+        let solution = level.solution.take().unwrap();
+        (level, solution)
+    }
+
+    fn score(tracks: u32, ticks: u32, switches: u32) -> Score {
+        Score {
+            tracks,
+            ticks,
+            switches,
+        }
+    }
+
+    #[test]
+    fn scores_a_win() {
+        let (level, solution) = fork();
+        let replay = Replay::record(&level, &solution, MAX_TICKS);
+        let score = Score::of_replay(&replay).unwrap();
+        assert_eq!(score.tracks, 2);
+        assert_eq!(score.ticks as usize, replay.len());
+        // once for each train that goes over the switch
+        assert_eq!(score.switches, 2);
+        assert_eq!(
+            Score::of_solution(&level, &solution, MAX_TICKS),
+            Some(score)
+        );
+    }
+
+    #[test]
+    fn losses_have_no_score() {
+        let (level, mut solution) = fork();
+        // without the switch both trains go right, and the second one has no sink to go to
+        solution[3 * 7 + 1] = 0x0d;
+        assert_eq!(Score::of_solution(&level, &solution, MAX_TICKS), None);
+        assert_eq!(
+            Score::of_solution(&level, &vec![0; solution.len()], MAX_TICKS),
+            None
+        );
+        let (level, solution) = fork();
+        assert_eq!(Score::of_solution(&level, &solution, 1), None);
+    }
+
+    #[test]
+    fn best_of_takes_each_part_separately() {
+        assert_eq!(score(3, 20, 0).best_of(&score(5, 10, 1)), score(3, 10, 0));
+        assert_eq!(score(3, 20, 0).best_of(&score(3, 20, 0)), score(3, 20, 0));
+    }
+
+    #[test]
+    fn new_records_have_to_beat_the_best() {
+        assert!(!score(3, 20, 0).new_records(None).any());
+        assert!(!score(3, 20, 0).new_records(Some(&score(3, 20, 0))).any());
+        assert_eq!(
+            score(2, 20, 1).new_records(Some(&score(3, 20, 0))),
+            NewRecords {
+                tracks: true,
+                ticks: false,
+                switches: false,
+            }
+        );
+        assert!(score(3, 19, 0).new_records(Some(&score(3, 20, 0))).any());
+    }
+}
@@ -0,0 +1,1002 @@
+//! Searches for track layouts that beat a level.
+//!
+//! Rather than trying every combination of tracks up front, the solver runs the level and only decides where a train
+//! goes at the moment it reaches a tile with no track for it. Until then, nothing that happened in the yard could have
+//! depended on those tiles, so the simulation never has to be rewound, and tiles that no train can reach are never
+//! considered at all.
+//!
+//! A train that needs track is given a whole route at once: tracks that take it to a sink that wants it, a painter, a
+//! splitter, or onto a track that's already there. Its cheapest routes are tried first, and the search is run
+//! repeatedly with a growing allowance for how much more than that the routes may cost between them, so levels where
+//! trains take the short way are solved without first wandering down every long detour.
+//!
+//! A second track that doesn't touch the first one (a crossing, or two opposite corners) never switches and is never
+//! used by a train on the first track, so those are also only added once a train actually needs them. One that does
+//! touch it makes a switch, which trains on the first track would have flipped, so it's only added to tiles no train
+//! has crossed yet.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use crate::connections::{Connection, TileConnections};
+use crate::direction::Dir;
+use crate::level::LevelLoadInfo;
+use crate::tiles::TileConstructionInfo;
+use crate::trains::TrainColor;
+use crate::yard::Yard;
+
+/// What drawing a single track on a blank tile costs out of the search's budget.
+const SINGLE_TRACK_COST: usize = 1;
+/// What drawing two tracks on a tile costs. This is more than two single tracks, since most tiles in most solutions
+/// only need one, and trying every pair of tracks on every tile is what makes the search slow.
+const DOUBLE_TRACK_COST: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct SolverOptions {
+    /// Stop searching once this many solutions have been found.
+    pub max_solutions: usize,
+    /// Give up after this long, returning whatever was found so far.
+    pub timeout: Duration,
+    /// A layout that hasn't won after this many ticks is treated as a failure.
+    pub max_ticks: usize,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            max_solutions: 1,
+            timeout: Duration::from_secs(10),
+            max_ticks: 200,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SolverResult {
+    /// Each solution is in the same format as `Yard::get_progress`, so it can be passed straight to
+    /// `Yard::apply_progress`.
+    pub solutions: Vec<Vec<u8>>,
+    /// True if the search was cut short by the timeout, in which case there may be solutions that weren't found.
+    pub timed_out: bool,
+    /// The number of yard states the solver looked at.
+    pub states_explored: usize,
+}
+
+impl SolverResult {
+    pub fn is_solved(&self) -> bool {
+        !self.solutions.is_empty()
+    }
+}
+
+/// Finds up to `options.max_solutions` distinct track layouts that win the level without any train crashing.
+pub fn solve(level: &LevelLoadInfo, options: &SolverOptions) -> SolverResult {
+    let yard = level.to_yard();
+    let layout: Layout = yard
+        .tiles
+        .iter()
+        .map(|row| row.iter().map(|tile| tile.construction_info()).collect())
+        .collect();
+    let num_drawable_tiles = all_positions(&yard)
+        .filter(|(row, col)| is_drawable(&layout, *row, *col))
+        .count();
+
+    let mut solver = Solver {
+        options,
+        layout,
+        deadline: Instant::now() + options.timeout,
+        seen_states: HashMap::new(),
+        budget: 0,
+        over_budget: false,
+        result: SolverResult::default(),
+    };
+    let crossed = vec![false; yard.width() * yard.height()];
+    loop {
+        solver.seen_states.clear();
+        solver.over_budget = false;
+        solver.search(yard.clone(), crossed.clone(), &mut Vec::new(), 0, 0);
+        // once nothing was left out for costing too much, a bigger budget won't find anything new
+        if solver.is_done()
+            || !solver.over_budget
+            || solver.budget >= num_drawable_tiles * DOUBLE_TRACK_COST
+        {
+            break;
+        }
+        solver.budget += 1;
+    }
+    solver.result
+}
+
+/// Returns true if the tracks drawn in `drawn_tracks` win the level without any train crashing.
+pub fn check_solution(level: &LevelLoadInfo, drawn_tracks: &[u8], max_ticks: usize) -> bool {
+    let mut yard = level.to_yard();
+    yard.apply_progress(drawn_tracks);
+    for _ in 0..max_ticks {
+        if yard.step().has_crashed() {
+            return false;
+        }
+        if yard.has_won() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Picks the next tile of `solution` to show the player, given the tracks they've already drawn.
+///
+/// Tiles are handed out in the order trains first reach them when the solution is run, so each hint carries on from
+/// the last one. Returns `None` once every tile already matches the solution.
+pub fn next_hint(
+    level: &LevelLoadInfo,
+    solution: &[u8],
+    drawn_tracks: &[u8],
+    max_ticks: usize,
+) -> Option<(usize, usize)> {
+    let mut yard = level.to_yard();
+    yard.apply_progress(solution);
+
+    let mut order: Vec<(usize, usize)> = Vec::new();
+    for _ in 0..max_ticks {
+        yard.step();
+        for activity in yard.train_activity.iter() {
+            if !order.contains(&(activity.row, activity.col)) {
+                order.push((activity.row, activity.col));
+            }
+        }
+        if yard.has_won() {
+            break;
+        }
+    }
+    // in case the solution has tracks no train ever uses
+    for position in all_positions(&yard) {
+        if !order.contains(&position) {
+            order.push(position);
+        }
+    }
+
+    order.into_iter().find(|(row, col)| {
+        let index = row * yard.width() + col;
+        yard.tiles[*row][*col].construction_info() == TileConstructionInfo::DrawableTile
+            && TileConnections::from_data(solution[index])
+                != TileConnections::from_data(drawn_tracks[index])
+    })
+}
+
+struct Solver<'a> {
+    options: &'a SolverOptions,
+    layout: Layout,
+    deadline: Instant,
+    /// The least that has been spent getting to each state of the yard in this pass of the search. Getting there
+    /// again for at least as much can't lead anywhere the first visit didn't.
+    seen_states: HashMap<u64, usize>,
+    /// The most this pass of the search may spend, where each route costs however much more it is than the cheapest
+    /// route the train could have taken.
+    budget: usize,
+    /// Whether this pass of the search left out any routes for going over the budget.
+    over_budget: bool,
+    result: SolverResult,
+}
+
+/// A tile's position, and the tracks that are drawn on it.
+type PlacedTrack = ((usize, usize), TileConnections);
+
+/// A drawable tile that a train is about to enter from a side that has no track yet.
+struct TrackNeeded {
+    row: usize,
+    col: usize,
+    incoming: Vec<Dir>,
+    /// The track that's already on the tile, if the tile isn't blank.
+    existing: Option<Connection>,
+}
+
+impl Solver<'_> {
+    fn is_done(&mut self) -> bool {
+        if self.result.solutions.len() >= self.options.max_solutions {
+            return true;
+        }
+        if Instant::now() >= self.deadline {
+            self.result.timed_out = true;
+            return true;
+        }
+        false
+    }
+
+    /// `placed` holds the tracks chosen so far, as they were when they were first placed
+    /// (the yard's copy may have been switched by trains since). `crossed` has the tiles trains have been on so far,
+    /// by `row * width + col`.
+    fn search(
+        &mut self,
+        mut yard: Yard,
+        mut crossed: Vec<bool>,
+        placed: &mut Vec<PlacedTrack>,
+        spent: usize,
+        mut ticks: usize,
+    ) {
+        loop {
+            if self.is_done() {
+                return;
+            }
+            if ticks > 0 && yard.has_won() {
+                self.record_solution(placed);
+                return;
+            }
+
+            let needed = find_tracks_needed(&self.layout, &yard);
+            if let Some(first) = needed.first() {
+                for (tracks, cost) in self.track_moves(&yard, &crossed, first, self.budget - spent)
+                {
+                    let mut next_yard = yard.clone();
+                    for ((row, col), connections) in tracks.iter() {
+                        next_yard.set_connections(*row, *col, *connections);
+                    }
+                    let num_placed = placed.len();
+                    placed.extend(tracks);
+                    self.search(next_yard, crossed.clone(), placed, spent + cost, ticks);
+                    placed.truncate(num_placed);
+                    if self.is_done() {
+                        return;
+                    }
+                }
+                return;
+            }
+
+            // this catches both trains going round in circles, and layouts that another branch already got to
+            // without spending any more
+            let seen = self
+                .seen_states
+                .entry(state_key(&yard, &crossed))
+                .or_insert(usize::MAX);
+            if *seen <= spent {
+                return;
+            }
+            *seen = spent;
+            self.result.states_explored += 1;
+
+            if ticks >= self.options.max_ticks {
+                return;
+            }
+            if yard.step().has_crashed() {
+                return;
+            }
+            for activity in yard.train_activity.iter() {
+                crossed[activity.row * yard.width() + activity.col] = true;
+            }
+            ticks += 1;
+        }
+    }
+
+    /// Every way of drawing tracks for the trains in `needed` that costs no more than `allowance` over the cheapest
+    /// way, along with how much over it each one is. Cheaper ones come first.
+    fn track_moves(
+        &mut self,
+        yard: &Yard,
+        crossed: &[bool],
+        needed: &TrackNeeded,
+        allowance: usize,
+    ) -> Vec<(Vec<PlacedTrack>, usize)> {
+        if let [incoming] = needed.incoming[..] {
+            let color = yard.borders[needed.row][needed.col]
+                .get_train(incoming)
+                .expect("a train is coming in from this side");
+            let finder = RouteFinder::new(&self.layout, yard, crossed, color);
+            let start = (needed.row, needed.col, incoming);
+            // a train that could go somewhere as it is shouldn't be sent to mix just because that's closer, so routes
+            // are measured against the cheapest one that doesn't need it to
+            let Some(cheapest) = finder
+                .cheapest_route(start, false)
+                .or_else(|| finder.cheapest_route(start, true))
+            else {
+                return Vec::new();
+            };
+            let mut found = FoundRoutes {
+                max_cost: cheapest + allowance,
+                routes: Vec::new(),
+                over_budget: false,
+            };
+            finder.extend(start, 0, &mut Vec::new(), &mut found);
+            self.over_budget |= found.over_budget;
+            let mut moves: Vec<(Vec<PlacedTrack>, usize, bool)> = found
+                .routes
+                .into_iter()
+                .map(|route| {
+                    (
+                        route.tracks,
+                        route.cost.saturating_sub(cheapest),
+                        route.mixes,
+                    )
+                })
+                .collect();
+            moves.sort_by_key(|(_, cost, mixes)| (*cost, *mixes));
+            return moves
+                .into_iter()
+                .map(|(tracks, cost, _)| (tracks, cost))
+                .collect();
+        }
+
+        // trains coming in from more than one side at once are rare enough that this tile is all that's decided
+        let choices = track_choices(&self.layout, yard, needed);
+        let Some(cheapest) = choices.iter().map(|(_, cost)| *cost).min() else {
+            return Vec::new();
+        };
+        let mut moves = Vec::new();
+        for (connections, cost) in choices {
+            if cost - cheapest > allowance {
+                self.over_budget = true;
+                continue;
+            }
+            moves.push((
+                vec![((needed.row, needed.col), connections)],
+                cost - cheapest,
+            ));
+        }
+        moves
+    }
+
+    fn record_solution(&mut self, placed: &[PlacedTrack]) {
+        let width = self.layout.first().map_or(0, |row| row.len());
+        let mut progress = vec![0; self.layout.len() * width];
+        // a tile that got a second track later on appears twice, and the later entry has both tracks
+        for ((row, col), connections) in placed.iter() {
+            progress[row * width + col] = connections.get_data();
+        }
+        if !self.result.solutions.contains(&progress) {
+            self.result.solutions.push(progress);
+        }
+    }
+}
+
+/// What kind of tile is at each position. This never changes while a level is played, so it is worked out up front.
+type Layout = Vec<Vec<TileConstructionInfo>>;
+
+fn all_positions(yard: &Yard) -> impl Iterator<Item = (usize, usize)> {
+    let width = yard.width();
+    (0..yard.height()).flat_map(move |row| (0..width).map(move |col| (row, col)))
+}
+
+fn is_drawable(layout: &Layout, row: usize, col: usize) -> bool {
+    layout[row][col] == TileConstructionInfo::DrawableTile
+}
+
+fn connections_at(yard: &Yard, row: usize, col: usize) -> TileConnections {
+    TileConnections::from_data(yard.tiles[row][col].get_connection_data())
+}
+
+fn find_tracks_needed(layout: &Layout, yard: &Yard) -> Vec<TrackNeeded> {
+    let mut needed = Vec::new();
+    for (row, col) in all_positions(yard) {
+        if !is_drawable(layout, row, col) {
+            continue;
+        }
+        let connections = connections_at(yard, row, col);
+        if !connections.get_passive_conn().is_empty() {
+            // there's no room for another track, so a train coming in from the wrong side will just crash
+            continue;
+        }
+        let active = connections.get_active_conn();
+        let incoming: Vec<Dir> = Dir::all_dirs()
+            .filter(|dir| yard.borders[row][col].get_train(*dir).is_some())
+            .filter(|dir| active.get_other_dir(*dir).is_none())
+            .collect();
+        if !incoming.is_empty() {
+            needed.push(TrackNeeded {
+                row,
+                col,
+                incoming,
+                existing: (!active.is_empty()).then_some(active),
+            });
+        }
+    }
+    needed
+}
+
+fn neighbor(yard: &Yard, row: usize, col: usize, dir: Dir) -> Option<(usize, usize)> {
+    match dir {
+        Dir::Up if row + 1 < yard.height() => Some((row + 1, col)),
+        Dir::Right if col + 1 < yard.width() => Some((row, col + 1)),
+        Dir::Down if row > 0 => Some((row - 1, col)),
+        Dir::Left if col > 0 => Some((row, col - 1)),
+        _ => None,
+    }
+}
+
+/// Every color of train there is.
+const ALL_COLORS: [TrainColor; 7] = [
+    TrainColor::Brown,
+    TrainColor::Red,
+    TrainColor::Blue,
+    TrainColor::Yellow,
+    TrainColor::Purple,
+    TrainColor::Green,
+    TrainColor::Orange,
+];
+
+/// Whether a train of color `from` could turn into `to` by meeting another train, without going through a painter or
+/// a splitter.
+fn can_become(from: TrainColor, to: TrainColor) -> bool {
+    ALL_COLORS.iter().any(|other| from.mix_with(*other) == to)
+}
+
+/// Tracks that take a train from where it needs them to somewhere it can carry on from.
+struct Route {
+    tracks: Vec<PlacedTrack>,
+    cost: usize,
+    /// Whether the route ends somewhere the train only belongs once it meets another train: on track that's already
+    /// been drawn for another one, or at a sink that wants a color the train has to be mixed into.
+    mixes: bool,
+}
+
+/// What a route can do once it gets to a tile, coming in from one side.
+enum Step {
+    /// The train can carry on from here without anything new being drawn, so the route ends. `mixes` is set as it is
+    /// for a `Route`.
+    Arrive { mixes: bool },
+    /// Draw `connections` on the tile, then go on out of the `exit` side. With no exit, the train has joined a track
+    /// that was already on the tile, and the route ends.
+    Draw {
+        connections: TileConnections,
+        cost: usize,
+        exit: Option<Dir>,
+    },
+}
+
+/// The routes found so far for one train, out of those that cost no more than `max_cost`.
+struct FoundRoutes {
+    max_cost: usize,
+    routes: Vec<Route>,
+    /// Whether any routes were left out for costing too much.
+    over_budget: bool,
+}
+
+/// Finds routes for a train of one color through the yard as it is.
+struct RouteFinder<'a> {
+    layout: &'a Layout,
+    yard: &'a Yard,
+    /// The tiles trains have been on so far, as passed to `Solver::search`.
+    crossed: &'a [bool],
+    color: TrainColor,
+    /// For each tile, a lower bound on what a route that comes into the tile still has to spend before it ends.
+    cost_to_finish: Vec<Vec<usize>>,
+}
+
+impl<'a> RouteFinder<'a> {
+    fn new(layout: &'a Layout, yard: &'a Yard, crossed: &'a [bool], color: TrainColor) -> Self {
+        let mut finder = Self {
+            layout,
+            yard,
+            crossed,
+            color,
+            cost_to_finish: vec![vec![usize::MAX; yard.width()]; yard.height()],
+        };
+        finder.cost_to_finish = finder.find_cost_to_finish();
+        finder
+    }
+
+    /// Works out `cost_to_finish` by spreading out from every tile a route could end on, ignoring which sides of the
+    /// tiles the route would have to come in from.
+    fn find_cost_to_finish(&self) -> Vec<Vec<usize>> {
+        let mut best = vec![vec![usize::MAX; self.yard.width()]; self.yard.height()];
+        let mut queue = VecDeque::new();
+        for (row, col) in all_positions(self.yard) {
+            let can_end_here = match &self.layout[row][col] {
+                TileConstructionInfo::DrawableTile => {
+                    !connections_at(self.yard, row, col).is_empty()
+                }
+                TileConstructionInfo::SinkTile { .. } => self.sink_wants_train(row, col),
+                TileConstructionInfo::Painter { .. } | TileConstructionInfo::Splitter { .. } => {
+                    true
+                }
+                TileConstructionInfo::Rock | TileConstructionInfo::SourceTile { .. } => false,
+            };
+            if can_end_here {
+                best[row][col] = 0;
+                queue.push_back((row, col));
+            }
+        }
+        while let Some((row, col)) = queue.pop_front() {
+            for dir in Dir::all_dirs() {
+                let Some((n_row, n_col)) = neighbor(self.yard, row, col, dir) else {
+                    continue;
+                };
+                // only blank tiles are left to fill in, and every one of them costs the same to cross
+                if is_drawable(self.layout, n_row, n_col) && best[n_row][n_col] == usize::MAX {
+                    best[n_row][n_col] = best[row][col] + SINGLE_TRACK_COST;
+                    queue.push_back((n_row, n_col));
+                }
+            }
+        }
+        best
+    }
+
+    fn sink_wants_train(&self, row: usize, col: usize) -> bool {
+        self.yard.tiles[row][col]
+            .remaining_trains()
+            .iter()
+            .any(|color| can_become(self.color, *color))
+    }
+
+    /// What a route could do on coming into the tile at `row`, `col` from the `side` side.
+    fn steps(&self, row: usize, col: usize, side: Dir) -> Vec<Step> {
+        let mut steps = Vec::new();
+        match &self.layout[row][col] {
+            TileConstructionInfo::DrawableTile => {
+                let connections = connections_at(self.yard, row, col);
+                let active = connections.get_active_conn();
+                let passive = connections.get_passive_conn();
+                if active.get_other_dir(side).is_some() || passive.get_other_dir(side).is_some() {
+                    steps.push(Step::Arrive { mixes: true });
+                    return steps;
+                }
+                if !passive.is_empty() {
+                    // there's no room for another track
+                    return steps;
+                }
+                let open_dirs: Vec<Dir> = Dir::all_dirs()
+                    .filter(|dir| {
+                        *dir != side && is_open_edge(self.layout, self.yard, row, col, *dir)
+                    })
+                    .collect();
+                for exit in open_dirs.iter().copied() {
+                    let track = Connection::from_dirs(side, exit);
+                    if !active.is_empty() {
+                        // either crossing the track that's here, or merging into it. merging makes a switch, which
+                        // the trains that already went along the track here would have flipped
+                        let merges = shares_a_dir(active, track);
+                        if merges && self.crossed[row * self.yard.width() + col] {
+                            continue;
+                        }
+                        steps.push(Step::Draw {
+                            connections: TileConnections::from_active_passive(active, track),
+                            cost: DOUBLE_TRACK_COST - SINGLE_TRACK_COST,
+                            exit: (!merges).then_some(exit),
+                        });
+                        continue;
+                    }
+                    steps.push(Step::Draw {
+                        connections: TileConnections::from_active(track),
+                        cost: SINGLE_TRACK_COST,
+                        exit: Some(exit),
+                    });
+                    // a switch, for trains that come by later to go somewhere else
+                    for other in open_dirs.iter().copied().filter(|dir| *dir != exit) {
+                        for passive in [
+                            Connection::from_dirs(side, other),
+                            Connection::from_dirs(exit, other),
+                        ] {
+                            steps.push(Step::Draw {
+                                connections: TileConnections::from_active_passive(track, passive),
+                                cost: DOUBLE_TRACK_COST,
+                                exit: Some(exit),
+                            });
+                        }
+                    }
+                }
+            }
+            TileConstructionInfo::SinkTile { ins, .. } => {
+                if ins[u8::from(side) as usize] && self.sink_wants_train(row, col) {
+                    let remaining = self.yard.tiles[row][col].remaining_trains();
+                    steps.push(Step::Arrive {
+                        mixes: !remaining.contains(&self.color),
+                    });
+                }
+            }
+            TileConstructionInfo::Painter { in_dir, .. } => {
+                if side == *in_dir {
+                    steps.push(Step::Arrive { mixes: false });
+                }
+            }
+            TileConstructionInfo::Splitter { in_dir } => {
+                if side == *in_dir {
+                    steps.push(Step::Arrive { mixes: false });
+                }
+            }
+            TileConstructionInfo::Rock | TileConstructionInfo::SourceTile { .. } => {}
+        }
+        steps
+    }
+
+    /// What the cheapest route starting from `start` (a tile's row and column, and the side the train comes in
+    /// from) costs, or `None` if the train has nowhere to go. Routes to sinks that the train would have to mix on
+    /// the way to are only counted if `allow_mixing` is set.
+    fn cheapest_route(&self, start: (usize, usize, Dir), allow_mixing: bool) -> Option<usize> {
+        let (row, col, side) = start;
+        let mut best: HashMap<(usize, usize, u8), usize> = HashMap::new();
+        // an entry with no tile is a route that has ended
+        let mut queue = BinaryHeap::from([Reverse((0, Some((row, col, u8::from(side)))))]);
+        while let Some(Reverse((cost, at))) = queue.pop() {
+            let Some((row, col, side)) = at else {
+                return Some(cost);
+            };
+            if best.get(&(row, col, side)).is_some_and(|best| *best < cost) {
+                continue;
+            }
+            for step in self.steps(row, col, Dir::from(side)) {
+                let (cost, next) = match step {
+                    Step::Arrive { mixes } if mixes && !allow_mixing => continue,
+                    Step::Arrive { .. } => (cost, None),
+                    Step::Draw { exit: None, .. } if !allow_mixing => continue,
+                    Step::Draw {
+                        cost: step_cost,
+                        exit,
+                        ..
+                    } => (
+                        cost + step_cost,
+                        exit.map(|exit| {
+                            let (n_row, n_col) =
+                                neighbor(self.yard, row, col, exit).expect("exits are open");
+                            (n_row, n_col, u8::from(exit.flip()))
+                        }),
+                    ),
+                };
+                if let Some(next) = next {
+                    if best.get(&next).is_some_and(|best| *best <= cost) {
+                        continue;
+                    }
+                    best.insert(next, cost);
+                }
+                queue.push(Reverse((cost, next)));
+            }
+        }
+        None
+    }
+
+    /// Adds every route that carries on from `tracks` at `at` to `found`, where `tracks` cost `cost` to draw.
+    fn extend(
+        &self,
+        at: (usize, usize, Dir),
+        cost: usize,
+        tracks: &mut Vec<PlacedTrack>,
+        found: &mut FoundRoutes,
+    ) {
+        let (row, col, side) = at;
+        if tracks.iter().any(|(position, _)| *position == (row, col)) {
+            // the route has come back round to a tile it already drew on
+            return;
+        }
+        for step in self.steps(row, col, side) {
+            let (connections, step_cost, exit) = match step {
+                Step::Arrive { mixes } => {
+                    found.routes.push(Route {
+                        tracks: tracks.clone(),
+                        cost,
+                        mixes,
+                    });
+                    continue;
+                }
+                Step::Draw {
+                    connections,
+                    cost,
+                    exit,
+                } => (connections, cost, exit),
+            };
+            let cost = cost + step_cost;
+            let next = exit.map(|exit| {
+                let (n_row, n_col) = neighbor(self.yard, row, col, exit).expect("exits are open");
+                (n_row, n_col, exit.flip())
+            });
+            let still_to_spend =
+                next.map_or(0, |(n_row, n_col, _)| self.cost_to_finish[n_row][n_col]);
+            if still_to_spend == usize::MAX {
+                continue;
+            }
+            if cost + still_to_spend > found.max_cost {
+                found.over_budget = true;
+                continue;
+            }
+            tracks.push(((row, col), connections));
+            match next {
+                Some(next) => self.extend(next, cost, tracks, found),
+                None => found.routes.push(Route {
+                    tracks: tracks.clone(),
+                    cost,
+                    mixes: true,
+                }),
+            }
+            tracks.pop();
+        }
+    }
+}
+
+/// The fewest blank tiles (including this one) that a train on the tile at `row`, `col` has to cross to reach a sink
+/// that's still waiting for trains. Tiles that already have tracks, painters and splitters are assumed to lead
+/// wherever the train needs to go.
+fn blank_tiles_to_sink(layout: &Layout, yard: &Yard, row: usize, col: usize) -> usize {
+    let mut best = vec![vec![usize::MAX; yard.width()]; yard.height()];
+    best[row][col] = 1;
+    let mut queue = VecDeque::from([(row, col)]);
+    while let Some((row, col)) = queue.pop_front() {
+        let distance = best[row][col];
+        for dir in Dir::all_dirs() {
+            let Some((n_row, n_col)) = neighbor(yard, row, col, dir) else {
+                continue;
+            };
+            if !is_open_edge(layout, yard, row, col, dir) {
+                continue;
+            }
+            let step = match &layout[n_row][n_col] {
+                TileConstructionInfo::SinkTile { .. } => {
+                    if !yard.tiles[n_row][n_col].has_no_remaining_trains() {
+                        return distance;
+                    }
+                    continue;
+                }
+                TileConstructionInfo::DrawableTile
+                    if yard.tiles[n_row][n_col].get_connection_data() == 0 =>
+                {
+                    SINGLE_TRACK_COST
+                }
+                _ => 0,
+            };
+            if distance + step < best[n_row][n_col] {
+                best[n_row][n_col] = distance + step;
+                // this is a 0-1 BFS, so tiles that are free to cross go to the front of the queue
+                if step == 0 {
+                    queue.push_front((n_row, n_col));
+                } else {
+                    queue.push_back((n_row, n_col));
+                }
+            }
+        }
+    }
+    usize::MAX
+}
+
+/// Whether a train could ever cross the edge on the `dir` side of the tile at `row`, `col` without crashing.
+fn is_open_edge(layout: &Layout, yard: &Yard, row: usize, col: usize, dir: Dir) -> bool {
+    let Some((n_row, n_col)) = neighbor(yard, row, col, dir) else {
+        return false;
+    };
+    let side = dir.flip();
+    match &layout[n_row][n_col] {
+        TileConstructionInfo::DrawableTile => {
+            let connections = connections_at(yard, n_row, n_col);
+            // a tile with only one track can still get a second one later
+            connections.get_passive_conn().is_empty()
+                || connections.get_active_conn().get_other_dir(side).is_some()
+                || connections.get_passive_conn().get_other_dir(side).is_some()
+        }
+        TileConstructionInfo::Rock => false,
+        TileConstructionInfo::SourceTile { out, .. } => *out == side,
+        TileConstructionInfo::SinkTile { ins, .. } => ins[u8::from(side) as usize],
+        TileConstructionInfo::Painter {
+            in_dir, out_dir, ..
+        } => side == *in_dir || side == *out_dir,
+        TileConstructionInfo::Splitter { in_dir } => {
+            side == *in_dir || side == in_dir.rotate_cw() || side == in_dir.rotate_ccw()
+        }
+    }
+}
+
+fn shares_a_dir(c1: Connection, c2: Connection) -> bool {
+    let (d1, d2) = c1.get_dirs();
+    c2.get_other_dir(d1).is_some() || c2.get_other_dir(d2).is_some()
+}
+
+/// Every way of drawing tracks so that the trains in `needed` can enter the tile, along with what each one costs.
+///
+/// Cheaper choices come first, so the first solutions found tend to be the tidiest.
+fn track_choices(
+    layout: &Layout,
+    yard: &Yard,
+    needed: &TrackNeeded,
+) -> Vec<(TileConnections, usize)> {
+    let open_dirs: Vec<Dir> = Dir::all_dirs()
+        .filter(|dir| {
+            needed.incoming.contains(dir)
+                || is_open_edge(layout, yard, needed.row, needed.col, *dir)
+        })
+        .collect();
+
+    let mut connections = Vec::new();
+    for (i, d1) in open_dirs.iter().enumerate() {
+        for d2 in open_dirs[i + 1..].iter() {
+            connections.push(Connection::from_dirs(*d1, *d2));
+        }
+    }
+    let covers = |conns: &[Connection]| {
+        needed
+            .incoming
+            .iter()
+            .all(|dir| conns.iter().any(|c| c.get_other_dir(*dir).is_some()))
+    };
+    let has_train = |conn: &Connection| {
+        needed
+            .incoming
+            .iter()
+            .any(|dir| conn.get_other_dir(*dir).is_some())
+    };
+
+    let mut choices = Vec::new();
+    if let Some(existing) = needed.existing {
+        for passive in connections.iter() {
+            if !shares_a_dir(existing, *passive) && covers(&[*passive]) {
+                choices.push((
+                    TileConnections::from_active_passive(existing, *passive),
+                    DOUBLE_TRACK_COST - SINGLE_TRACK_COST,
+                ));
+            }
+        }
+        return choices;
+    }
+
+    for active in connections.iter() {
+        if covers(&[*active]) {
+            choices.push((TileConnections::from_active(*active), SINGLE_TRACK_COST));
+        }
+    }
+    for active in connections.iter() {
+        for passive in connections.iter() {
+            if active == passive || !covers(&[*active, *passive]) {
+                continue;
+            }
+            if shares_a_dir(*active, *passive) {
+                // which of these is active matters, since trains switch them
+                choices.push((
+                    TileConnections::from_active_passive(*active, *passive),
+                    DOUBLE_TRACK_COST,
+                ));
+            } else if has_train(active) && has_train(passive) {
+                // otherwise, the second track would have been added when it was first needed.
+                // it also doesn't matter which one is active, so only one order is tried.
+                let choice = TileConnections::from_active_passive(*active, *passive);
+                if !choices.contains(&(choice.switch_active_passive(), DOUBLE_TRACK_COST)) {
+                    choices.push((choice, DOUBLE_TRACK_COST));
+                }
+            }
+        }
+    }
+
+    // of the choices that cost the same, try the ones that head towards a sink first
+    let exit_distance = |connections: &TileConnections| {
+        Dir::all_dirs()
+            .filter(|dir| !needed.incoming.contains(dir))
+            .filter(|dir| {
+                connections.get_active_conn().get_other_dir(*dir).is_some()
+                    || connections.get_passive_conn().get_other_dir(*dir).is_some()
+            })
+            .filter_map(|dir| neighbor(yard, needed.row, needed.col, dir))
+            .map(|(row, col)| blank_tiles_to_sink(layout, yard, row, col))
+            .min()
+            .unwrap_or(usize::MAX)
+    };
+    choices.sort_by_cached_key(|(connections, cost)| (*cost, exit_distance(connections)));
+    choices
+}
+
+/// A hash of everything about the yard that can affect what happens next, including which of the tiles that could
+/// still be made into switches trains have crossed.
+///
+/// The solver can look at millions of states, so only the hash is kept. A collision would at worst make it skip a
+/// state it hasn't really seen.
+fn state_key(yard: &Yard, crossed: &[bool]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (row, col) in all_positions(yard) {
+        let connections = connections_at(yard, row, col);
+        if !connections.get_active_conn().is_empty() && connections.get_passive_conn().is_empty() {
+            crossed[row * yard.width() + col].hash(&mut hasher);
+        }
+        yard.tiles[row][col].get_connection_data().hash(&mut hasher);
+        yard.tiles[row][col].remaining_trains().hash(&mut hasher);
+        yard.borders[row][col].hash(&mut hasher);
+    }
+    yard.pending_switches.hash(&mut hasher);
+    yard.train_activity.is_empty().hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels_txt::parse_levels_txt;
+
+    /// The city of test levels at the end of `levels.txt`, which aren't rated the way the stock levels are.
+    const DEBUG_CITY: &str = "Debug";
+
+    #[test]
+    fn solves_every_one_star_level() {
+        let levels = parse_levels_txt(include_str!("../../../assets/levels.txt")).unwrap();
+        let options = SolverOptions::default();
+        let one_star_levels = levels
+            .iter()
+            .filter(|level| level.stars == 1 && level.city != DEBUG_CITY);
+
+        let mut num_solved = 0;
+        for level in one_star_levels {
+            let result = solve(level, &options);
+            assert!(
+                result.is_solved(),
+                "{} wasn't solved ({} states explored)",
+                level.name,
+                result.states_explored
+            );
+            assert!(check_solution(
+                level,
+                &result.solutions[0],
+                options.max_ticks
+            ));
+            num_solved += 1;
+        }
+        assert_eq!(num_solved, 16);
+    }
+
+    #[test]
+    fn gives_up_on_a_level_with_nowhere_for_trains_to_go() {
+        let level = &parse_levels_txt(
+            "Nowhere:1
++ 1,3 red right
+o 5,3 blue left
+---
+",
+        )
+        .unwrap()[0];
+        let result = solve(level, &SolverOptions::default());
+        assert!(!result.is_solved());
+        assert!(!result.timed_out);
+    }
+
+    const RED_LINE: &str = "Red Line:1
++ 1,3 red right
+o 5,3 red left
+---
+";
+
+    #[test]
+    fn finds_distinct_solutions() {
+        let level = &parse_levels_txt(RED_LINE).unwrap()[0];
+        let options = SolverOptions {
+            max_solutions: 3,
+            ..SolverOptions::default()
+        };
+        let result = solve(level, &options);
+        assert_eq!(result.solutions.len(), 3);
+        for (i, solution) in result.solutions.iter().enumerate() {
+            assert!(check_solution(level, solution, options.max_ticks));
+            assert!(!result.solutions[..i].contains(solution));
+        }
+    }
+
+    #[test]
+    fn stops_at_the_timeout() {
+        let level = &parse_levels_txt(RED_LINE).unwrap()[0];
+        let options = SolverOptions {
+            timeout: Duration::ZERO,
+            ..SolverOptions::default()
+        };
+        let result = solve(level, &options);
+        assert!(result.timed_out);
+        assert!(!result.is_solved());
+    }
+
+    #[test]
+    fn switches_are_only_made_on_tiles_no_train_has_crossed() {
+        let level = &parse_levels_txt(RED_LINE).unwrap()[0];
+        let mut yard = level.to_yard();
+        let layout: Layout = yard
+            .tiles
+            .iter()
+            .map(|row| row.iter().map(|tile| tile.construction_info()).collect())
+            .collect();
+        let left_right = Connection::from_dirs(Dir::Left, Dir::Right);
+        yard.set_connections(3, 3, TileConnections::from_active(left_right));
+
+        // a train coming down onto the Left-Right track can cross it, or join it by turning left or right
+        let exits = |crossed: &[bool]| {
+            let finder = RouteFinder::new(&layout, &yard, crossed, TrainColor::Red);
+            let mut exits: Vec<Option<Dir>> = finder
+                .steps(3, 3, Dir::Up)
+                .into_iter()
+                .map(|step| match step {
+                    Step::Draw { exit, .. } => exit,
+                    Step::Arrive { .. } => panic!("there's no track on the tile's Up side"),
+                })
+                .collect();
+            exits.sort_by_key(|exit| exit.map(u8::from));
+            exits
+        };
+        let mut crossed = vec![false; yard.width() * yard.height()];
+        assert_eq!(exits(&crossed), [None, None, Some(Dir::Down)]);
+        crossed[3 * yard.width() + 3] = true;
+        assert_eq!(exits(&crossed), [Some(Dir::Down)]);
+    }
+}
@@ -0,0 +1,53 @@
+pub mod drawable_tile;
+pub mod painter_tile;
+pub mod rock_tile;
+pub mod sink_tile;
+pub mod source_tile;
+pub mod splitter_tile;
+pub mod tile;
+
+use crate::{direction::Dir, trains::TrainColor};
+use drawable_tile::DrawableTile;
+use painter_tile::PainterTile;
+use rock_tile::RockTile;
+use sink_tile::SinkTile;
+use source_tile::SourceTile;
+use splitter_tile::SplitterTile;
+use tile::Tile;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TileConstructionInfo {
+    DrawableTile,
+    Rock,
+    SourceTile {
+        out: Dir,
+        trains: Vec<TrainColor>,
+    },
+    SinkTile {
+        ins: [bool; 4],
+        trains: Vec<TrainColor>,
+    },
+    Painter {
+        color: TrainColor,
+        in_dir: Dir,
+        out_dir: Dir,
+    },
+    Splitter {
+        in_dir: Dir,
+    },
+}
+
+pub fn construct_new_tile(tile_type: TileConstructionInfo) -> Box<dyn Tile> {
+    match tile_type {
+        TileConstructionInfo::DrawableTile => Box::new(DrawableTile::new()),
+        TileConstructionInfo::Rock => Box::new(RockTile::new()),
+        TileConstructionInfo::SourceTile { out, trains } => Box::new(SourceTile::new(out, trains)),
+        TileConstructionInfo::SinkTile { ins, trains } => Box::new(SinkTile::new(ins, trains)),
+        TileConstructionInfo::Painter {
+            color,
+            in_dir,
+            out_dir,
+        } => Box::new(PainterTile::new(color, in_dir, out_dir)),
+        TileConstructionInfo::Splitter { in_dir } => Box::new(SplitterTile::new(in_dir)),
+    }
+}
@@ -0,0 +1,112 @@
+use super::{
+    tile::{Tile, TileEvent, TileProcessTickResult, TileTrainActivity},
+    TileConstructionInfo,
+};
+use crate::{connections::TileBorderState, direction::Dir, trains::TrainColor};
+
+/// Paints every train that goes through it. Trains only go one way, coming in from `in_dir` and leaving by `out_dir`,
+/// and crash if they come in from any other side.
+#[derive(Clone)]
+pub struct PainterTile {
+    pub color: TrainColor,
+    pub in_dir: Dir,
+    pub out_dir: Dir,
+}
+
+impl PainterTile {
+    pub fn new(color: TrainColor, in_dir: Dir, out_dir: Dir) -> Self {
+        Self {
+            color,
+            in_dir,
+            out_dir,
+        }
+    }
+}
+
+impl Tile for PainterTile {
+    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult {
+        let mut trains = Vec::new();
+        let mut start_tick_events = Vec::new();
+        let mut mid_tick_events = Vec::new();
+
+        for dir in Dir::all_dirs() {
+            if let Some(color) = incoming.get_train(dir) {
+                if dir == self.in_dir {
+                    // the train is painted as it passes under the brush in the middle of the tile
+                    trains.push(TileTrainActivity {
+                        from_dir: Some(self.in_dir),
+                        to_dir: Some(self.out_dir),
+                        start_color: color,
+                        end_color: self.color,
+                    });
+                    mid_tick_events.push(TileEvent::PaintTrain(self.color));
+                } else {
+                    start_tick_events.push(TileEvent::CrashedOnEdge(color, dir));
+                }
+            }
+        }
+
+        TileProcessTickResult {
+            trains,
+            start_tick_events,
+            mid_tick_events,
+            ..Default::default()
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Tile> {
+        Box::new(self.clone())
+    }
+
+    fn construction_info(&self) -> TileConstructionInfo {
+        TileConstructionInfo::Painter {
+            color: self.color,
+            in_dir: self.in_dir,
+            out_dir: self.out_dir,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paint(painter: &mut PainterTile, from: Dir) -> TileProcessTickResult {
+        let mut incoming = TileBorderState::default();
+        incoming.add_train(TrainColor::Blue, from);
+        painter.process_and_output(incoming)
+    }
+
+    #[test]
+    fn trains_are_painted_going_from_in_to_out() {
+        let mut painter = PainterTile::new(TrainColor::Red, Dir::Left, Dir::Up);
+        let result = paint(&mut painter, Dir::Left);
+        assert_eq!(
+            result.trains,
+            vec![TileTrainActivity {
+                from_dir: Some(Dir::Left),
+                to_dir: Some(Dir::Up),
+                start_color: TrainColor::Blue,
+                end_color: TrainColor::Red,
+            }]
+        );
+        assert_eq!(
+            result.mid_tick_events,
+            vec![TileEvent::PaintTrain(TrainColor::Red)]
+        );
+    }
+
+    #[test]
+    fn trains_from_any_other_side_crash() {
+        let mut painter = PainterTile::new(TrainColor::Red, Dir::Left, Dir::Up);
+        // including the way out
+        for from in [Dir::Up, Dir::Right, Dir::Down] {
+            let result = paint(&mut painter, from);
+            assert!(result.trains.is_empty());
+            assert_eq!(
+                result.start_tick_events,
+                vec![TileEvent::CrashedOnEdge(TrainColor::Blue, from)]
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use super::{
+    tile::{Tile, TileEvent, TileProcessTickResult},
+    TileConstructionInfo,
+};
+use crate::{connections::TileBorderState, direction::Dir};
+
+#[derive(Clone, Default)]
+pub struct RockTile;
+
+impl RockTile {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tile for RockTile {
+    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult {
+        let mut start_tick_events = Vec::new();
+
+        for dir_u8 in 0..4 {
+            if let Some(color) = incoming.get_train(Dir::from(dir_u8)) {
+                start_tick_events.push(TileEvent::CrashedOnEdge(color, dir_u8.into()));
+            }
+        }
+
+        TileProcessTickResult {
+            start_tick_events,
+            ..Default::default()
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Tile> {
+        Box::new(self.clone())
+    }
+
+    fn construction_info(&self) -> TileConstructionInfo {
+        TileConstructionInfo::Rock
+    }
+}
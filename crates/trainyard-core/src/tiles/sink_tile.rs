@@ -0,0 +1,71 @@
+use super::{
+    tile::{Tile, TileEvent, TileProcessTickResult, TileTrainActivity},
+    TileConstructionInfo,
+};
+use crate::{connections::TileBorderState, direction::Dir, trains::TrainColor};
+
+#[derive(Clone)]
+pub struct SinkTile {
+    pub in_dirs: [bool; 4],
+    pub trains: Vec<TrainColor>,
+}
+
+impl SinkTile {
+    pub fn new(in_dirs: [bool; 4], trains: Vec<TrainColor>) -> Self {
+        Self { in_dirs, trains }
+    }
+}
+
+impl Tile for SinkTile {
+    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult {
+        let mut train_activity = Vec::new();
+        let mut mid_tick_events = Vec::new();
+        let mut start_tick_events = Vec::new();
+
+        for dir in Dir::all_dirs() {
+            if !self.in_dirs[u8::from(dir) as usize] {
+                if let Some(color) = incoming.get_train(dir) {
+                    start_tick_events.push(TileEvent::CrashedOnEdge(color, dir));
+                    continue;
+                }
+            }
+
+            if let Some(train) = incoming.get_train(dir) {
+                if let Some(index) = self.trains.iter().position(|color| *color == train) {
+                    self.trains.remove(index);
+                    train_activity.push(TileTrainActivity {
+                        from_dir: Some(dir),
+                        to_dir: None,
+                        start_color: train,
+                        end_color: train,
+                    });
+                    mid_tick_events.push(TileEvent::SinkReceivedTrain(train));
+                } else {
+                    start_tick_events.push(TileEvent::CrashedOnEdge(train, dir));
+                }
+            }
+        }
+
+        TileProcessTickResult {
+            trains: train_activity,
+            mid_tick_events,
+            start_tick_events,
+            ..Default::default()
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Tile> {
+        Box::new(self.clone())
+    }
+
+    fn construction_info(&self) -> TileConstructionInfo {
+        TileConstructionInfo::SinkTile {
+            ins: self.in_dirs,
+            trains: self.trains.clone(),
+        }
+    }
+
+    fn remaining_trains(&self) -> &[TrainColor] {
+        &self.trains
+    }
+}
@@ -0,0 +1,64 @@
+use super::{
+    tile::{Tile, TileEvent, TileProcessTickResult, TileTrainActivity},
+    TileConstructionInfo,
+};
+use crate::{connections::TileBorderState, direction::Dir, trains::TrainColor};
+
+#[derive(Clone)]
+pub struct SourceTile {
+    pub out_dir: Dir,
+    pub trains: Vec<TrainColor>,
+}
+
+impl SourceTile {
+    pub fn new(out_dir: Dir, trains: Vec<TrainColor>) -> Self {
+        Self { out_dir, trains }
+    }
+}
+
+impl Tile for SourceTile {
+    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult {
+        let mut start_tick_events = Vec::new();
+
+        for dir_u8 in 0..4 {
+            if let Some(color) = incoming.get_train(Dir::from(dir_u8)) {
+                start_tick_events.push(TileEvent::CrashedOnEdge(color, dir_u8.into()))
+            }
+        }
+
+        if !self.trains.is_empty() {
+            let outgoing_train_color = self.trains.remove(0);
+            let trains = vec![TileTrainActivity {
+                from_dir: None,
+                to_dir: Some(self.out_dir),
+                start_color: outgoing_train_color,
+                end_color: outgoing_train_color,
+            }];
+            return TileProcessTickResult {
+                trains,
+                mid_tick_events: vec![TileEvent::SourceEmittedTrain(outgoing_train_color)],
+                start_tick_events,
+                ..Default::default()
+            };
+        }
+        TileProcessTickResult {
+            start_tick_events,
+            ..Default::default()
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Tile> {
+        Box::new(self.clone())
+    }
+
+    fn construction_info(&self) -> TileConstructionInfo {
+        TileConstructionInfo::SourceTile {
+            out: self.out_dir,
+            trains: self.trains.clone(),
+        }
+    }
+
+    fn remaining_trains(&self) -> &[TrainColor] {
+        &self.trains
+    }
+}
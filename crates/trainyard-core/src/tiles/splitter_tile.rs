@@ -0,0 +1,130 @@
+use super::{
+    tile::{Tile, TileEvent, TileProcessTickResult, TileTrainActivity},
+    TileConstructionInfo,
+};
+use crate::{connections::TileBorderState, direction::Dir};
+
+#[derive(Clone)]
+pub struct SplitterTile {
+    pub in_dir: Dir,
+}
+
+impl SplitterTile {
+    pub fn new(in_dir: Dir) -> Self {
+        Self { in_dir }
+    }
+
+    /// The two edges that split trains leave from, as (left, right) from the point of view of the incoming train.
+    pub fn out_dirs(&self) -> (Dir, Dir) {
+        (self.in_dir.rotate_cw(), self.in_dir.rotate_ccw())
+    }
+}
+
+impl Tile for SplitterTile {
+    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult {
+        let mut trains = Vec::new();
+        let mut start_tick_events = Vec::new();
+        let mut mid_tick_events = Vec::new();
+
+        for dir in Dir::all_dirs() {
+            if let Some(color) = incoming.get_train(dir) {
+                if dir == self.in_dir {
+                    let (left_dir, right_dir) = self.out_dirs();
+                    let (left_color, right_color) = color.split();
+                    trains.push(TileTrainActivity {
+                        from_dir: Some(self.in_dir),
+                        to_dir: Some(left_dir),
+                        start_color: color,
+                        end_color: left_color,
+                    });
+                    trains.push(TileTrainActivity {
+                        from_dir: Some(self.in_dir),
+                        to_dir: Some(right_dir),
+                        start_color: color,
+                        end_color: right_color,
+                    });
+                    mid_tick_events.push(TileEvent::SplitTrain(color));
+                } else {
+                    start_tick_events.push(TileEvent::CrashedOnEdge(color, dir));
+                }
+            }
+        }
+
+        TileProcessTickResult {
+            trains,
+            start_tick_events,
+            mid_tick_events,
+            ..Default::default()
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Tile> {
+        Box::new(self.clone())
+    }
+
+    fn construction_info(&self) -> TileConstructionInfo {
+        TileConstructionInfo::Splitter {
+            in_dir: self.in_dir,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trains::TrainColor;
+
+    /// The color that leaves through each side of a splitter facing `in_dir`, when a `color` train comes in.
+    fn split_at(in_dir: Dir, color: TrainColor) -> Vec<(Dir, TrainColor)> {
+        let mut incoming = TileBorderState::default();
+        incoming.add_train(color, in_dir);
+        SplitterTile::new(in_dir)
+            .process_and_output(incoming)
+            .trains
+            .into_iter()
+            .map(|train| (train.to_dir.unwrap(), train.end_color))
+            .collect()
+    }
+
+    #[test]
+    fn secondary_colors_split_to_their_own_sides() {
+        let cases = [
+            (TrainColor::Purple, TrainColor::Blue, TrainColor::Red),
+            (TrainColor::Orange, TrainColor::Yellow, TrainColor::Red),
+            (TrainColor::Green, TrainColor::Blue, TrainColor::Yellow),
+        ];
+        for (color, left, right) in cases {
+            // a train coming in from the bottom is heading up, so its left is the left side of the tile
+            assert_eq!(
+                split_at(Dir::Down, color),
+                vec![(Dir::Left, left), (Dir::Right, right)],
+                "{:?}",
+                color
+            );
+            for in_dir in Dir::all_dirs() {
+                assert_eq!(
+                    split_at(in_dir, color),
+                    vec![(in_dir.rotate_cw(), left), (in_dir.rotate_ccw(), right)],
+                    "{:?} from {:?}",
+                    color,
+                    in_dir
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn primaries_and_brown_are_copied() {
+        for color in [
+            TrainColor::Red,
+            TrainColor::Yellow,
+            TrainColor::Blue,
+            TrainColor::Brown,
+        ] {
+            assert_eq!(
+                split_at(Dir::Down, color),
+                vec![(Dir::Left, color), (Dir::Right, color)]
+            );
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{connections::TileBorderState, direction::Dir, trains::TrainColor};
+use serde::{Deserialize, Serialize};
+
+use super::TileConstructionInfo;
+
+/// A struct to represent how trains are moving within a tile,
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileTrainActivity {
+    pub from_dir: Option<Dir>,
+    pub to_dir: Option<Dir>,
+    pub start_color: TrainColor,
+    pub end_color: TrainColor,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TileProcessTickResult {
+    pub trains: Vec<TileTrainActivity>,
+    pub start_tick_events: Vec<TileEvent>,
+    pub mid_tick_events: Vec<TileEvent>,
+    pub end_tick_events: Vec<TileEvent>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TileEvent {
+    MixColors(TrainColor, (f32, f32)),
+    CrashedOnEdge(TrainColor, Dir),
+    SourceEmittedTrain(TrainColor),
+    SinkReceivedTrain(TrainColor),
+    SwitchActivePassive,
+    PaintTrain(TrainColor),
+    SplitTrain(TrainColor),
+}
+
+pub trait Tile: Send + Sync {
+    fn add_connection(&mut self, _d1: Dir, _d2: Dir) {}
+
+    fn erase_connections(&mut self) {}
+
+    fn switch_active_passive(&mut self) {}
+
+    // the function argument represents an __incoming__ border state,
+    // while the output represents an __outgoing__ border state.
+    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult;
+
+    fn box_clone(&self) -> Box<dyn Tile>;
+
+    /// Describes the tile in the same terms that were used to build it, so that it can be rebuilt or drawn.
+    fn construction_info(&self) -> TileConstructionInfo;
+
+    /// The trains that are still waiting inside of this tile (in a source or a sink), in order.
+    fn remaining_trains(&self) -> &[TrainColor] {
+        &[]
+    }
+
+    fn has_no_remaining_trains(&self) -> bool {
+        self.remaining_trains().is_empty()
+    }
+    fn get_connection_data(&self) -> u8 {
+        0
+    }
+}
+
+impl Clone for Box<dyn Tile> {
+    fn clone(&self) -> Box<dyn Tile> {
+        self.box_clone()
+    }
+}
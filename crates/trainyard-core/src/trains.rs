@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum TrainColor {
+    Brown,
+    Red,
+    Blue,
+    Yellow,
+    Purple,
+    Green,
+    Orange,
+}
+
+/// A set of colors to draw trains in. The original colors are hard to tell apart with some kinds of color blindness,
+/// so there's a palette for each of the common ones, with the colors that get confused pulled apart in lightness.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Original,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+/// The palette that `Color::from(TrainColor)` uses, as a `Palette` cast to a `u8`.
+#[cfg(feature = "bevy")]
+static ACTIVE_PALETTE: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(0);
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Original,
+        Palette::Protanopia,
+        Palette::Deuteranopia,
+        Palette::Tritanopia,
+    ];
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Palette::Original => "Original",
+            Palette::Protanopia => "Protanopia",
+            Palette::Deuteranopia => "Deuteranopia",
+            Palette::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// The palette after this one in `ALL`, going back to the first after the last.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|palette| palette == self)
+            .unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The red, green and blue of `color` in this palette, in sRGB.
+    pub fn srgb(&self, color: TrainColor) -> (f32, f32, f32) {
+        match (self, color) {
+            (Palette::Original, TrainColor::Brown) => (0.471, 0.333, 0.231),
+            (Palette::Original, TrainColor::Blue) => (0.165, 0.314, 0.773),
+            (Palette::Original, TrainColor::Red) => (0.733, 0.153, 0.122),
+            (Palette::Original, TrainColor::Yellow) => (0.918, 0.918, 0.396),
+            (Palette::Original, TrainColor::Orange) => (0.914, 0.624, 0.220),
+            (Palette::Original, TrainColor::Green) => (0.376, 0.788, 0.231),
+            (Palette::Original, TrainColor::Purple) => (0.631, 0.125, 0.773),
+
+            (Palette::Protanopia, TrainColor::Brown) => (0.583, 0.469, 0.480),
+            (Palette::Protanopia, TrainColor::Blue) => (0.587, 0.599, 1.000),
+            (Palette::Protanopia, TrainColor::Red) => (0.644, 0.000, 0.017),
+            (Palette::Protanopia, TrainColor::Yellow) => (1.000, 1.000, 0.734),
+            (Palette::Protanopia, TrainColor::Orange) => (0.863, 0.552, 0.208),
+            (Palette::Protanopia, TrainColor::Green) => (0.562, 0.953, 0.228),
+            (Palette::Protanopia, TrainColor::Purple) => (0.434, 0.000, 0.726),
+
+            (Palette::Deuteranopia, TrainColor::Brown) => (0.472, 0.337, 0.264),
+            (Palette::Deuteranopia, TrainColor::Blue) => (0.000, 0.311, 0.827),
+            (Palette::Deuteranopia, TrainColor::Red) => (0.698, 0.118, 0.053),
+            (Palette::Deuteranopia, TrainColor::Yellow) => (1.000, 1.000, 0.490),
+            (Palette::Deuteranopia, TrainColor::Orange) => (0.892, 0.540, 0.000),
+            (Palette::Deuteranopia, TrainColor::Green) => (0.302, 0.818, 0.401),
+            (Palette::Deuteranopia, TrainColor::Purple) => (0.683, 0.127, 0.753),
+
+            (Palette::Tritanopia, TrainColor::Brown) => (0.436, 0.338, 0.231),
+            (Palette::Tritanopia, TrainColor::Blue) => (0.165, 0.314, 0.773),
+            (Palette::Tritanopia, TrainColor::Red) => (0.733, 0.153, 0.122),
+            (Palette::Tritanopia, TrainColor::Yellow) => (0.918, 0.918, 0.396),
+            (Palette::Tritanopia, TrainColor::Orange) => (0.934, 0.641, 0.205),
+            (Palette::Tritanopia, TrainColor::Green) => (0.376, 0.788, 0.231),
+            (Palette::Tritanopia, TrainColor::Purple) => (0.647, 0.000, 0.627),
+        }
+    }
+
+    /// The palette that trains are currently drawn in.
+    #[cfg(feature = "bevy")]
+    pub fn active() -> Self {
+        let index = ACTIVE_PALETTE.load(std::sync::atomic::Ordering::Relaxed);
+        Self::ALL[index as usize]
+    }
+
+    /// Draws every train from now on in this palette. Sprites that were already tinted keep their color.
+    #[cfg(feature = "bevy")]
+    pub fn set_active(self) {
+        ACTIVE_PALETTE.store(self as u8, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "bevy")]
+impl From<TrainColor> for bevy_color::Color {
+    fn from(value: TrainColor) -> Self {
+        let (r, g, b) = Palette::active().srgb(value);
+        Self::srgb(r, g, b)
+    }
+}
+
+impl TrainColor {
+    pub fn mix_with(self: TrainColor, other: TrainColor) -> TrainColor {
+        if self == other {
+            return other;
+        }
+
+        if (self == TrainColor::Blue && other == TrainColor::Red)
+            || (self == TrainColor::Red && other == TrainColor::Blue)
+        {
+            return TrainColor::Purple;
+        }
+        if (self == TrainColor::Yellow && other == TrainColor::Red)
+            || (self == TrainColor::Red && other == TrainColor::Yellow)
+        {
+            return TrainColor::Orange;
+        }
+        if (self == TrainColor::Yellow && other == TrainColor::Blue)
+            || (self == TrainColor::Blue && other == TrainColor::Yellow)
+        {
+            return TrainColor::Green;
+        }
+
+        TrainColor::Brown
+    }
+    pub fn mix_many(trains: &[TrainColor]) -> TrainColor {
+        match trains.len() {
+            1 => trains[0],
+            2 => trains[1].mix_with(trains[0]),
+            _ => {
+                for i in 0..(trains.len() - 1) {
+                    if trains[i] != trains[i + 1] {
+                        return TrainColor::Brown;
+                    }
+                }
+                trains[0]
+            }
+        }
+    }
+
+    /// Splits a train into the two trains that leave a splitter, ordered (left, right) as the train sees them.
+    /// Secondary colors split into their primaries, while primaries and brown are duplicated. Which primary goes which
+    /// way is the same as in the original game, whose stored solutions depend on it.
+    pub fn split(self) -> (TrainColor, TrainColor) {
+        match self {
+            TrainColor::Purple => (TrainColor::Blue, TrainColor::Red),
+            TrainColor::Orange => (TrainColor::Yellow, TrainColor::Red),
+            TrainColor::Green => (TrainColor::Blue, TrainColor::Yellow),
+            TrainColor::Brown | TrainColor::Red | TrainColor::Blue | TrainColor::Yellow => {
+                (self, self)
+            }
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            TrainColor::Brown => "Brown",
+            TrainColor::Red => "Red",
+            TrainColor::Blue => "Blue",
+            TrainColor::Yellow => "Yellow",
+            TrainColor::Purple => "Purple",
+            TrainColor::Green => "Green",
+            TrainColor::Orange => "Orange",
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use crate::connections::{TileBorderState, TileConnections};
+use crate::direction::Dir;
+use crate::tiles::tile::{Tile, TileEvent, TileTrainActivity};
+use crate::tiles::{construct_new_tile, TileConstructionInfo};
+use crate::trains::TrainColor;
+use crate::{NUM_COLS, NUM_ROWS};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug)]
+pub struct TrainActivityWithLocation {
+    pub row: usize,
+    pub col: usize,
+    pub activity: TileTrainActivity,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TileEventWithLocation {
+    pub row: usize,
+    pub col: usize,
+    pub event: TileEvent,
+}
+
+/// The events produced by a single call to `Yard::step`, grouped by when they happen within the tick.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct YardProcessTickResult {
+    pub start_tick_events: Vec<TileEventWithLocation>,
+    pub mid_tick_events: Vec<TileEventWithLocation>,
+    pub end_tick_events: Vec<TileEventWithLocation>,
+}
+
+impl YardProcessTickResult {
+    pub fn all_events(&self) -> impl Iterator<Item = &TileEventWithLocation> {
+        self.start_tick_events
+            .iter()
+            .chain(self.mid_tick_events.iter())
+            .chain(self.end_tick_events.iter())
+    }
+
+    pub fn has_crashed(&self) -> bool {
+        self.all_events()
+            .any(|e| matches!(e.event, TileEvent::CrashedOnEdge(..)))
+    }
+}
+
+/// The full state of a puzzle: the grid of tiles, the trains sitting on the borders between tiles,
+/// and what every train did during the last tick.
+#[derive(Clone)]
+pub struct Yard {
+    /// The tiles, as `tiles[row][col]`. Every row has the same number of columns.
+    pub tiles: Vec<Vec<Box<dyn Tile>>>,
+    pub borders: Vec<Vec<TileBorderState>>,
+    pub train_activity: Vec<TrainActivityWithLocation>,
+    /// Tracks that trains toggled during the last tick. These only switch over at the start of the next tick,
+    /// so that the toggle isn't visible while the train that caused it is still on the tile.
+    pub pending_switches: Vec<(usize, usize)>,
+}
+
+impl Default for Yard {
+    fn default() -> Self {
+        Self::new(NUM_COLS as usize, NUM_ROWS as usize)
+    }
+}
+
+impl Yard {
+    /// An empty yard that is `width` tiles across and `height` tiles tall.
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut tiles: Vec<Vec<Box<dyn Tile>>> = Vec::new();
+
+        for _ in 0..height {
+            let mut row_vec: Vec<Box<dyn Tile>> = Vec::new();
+            for _ in 0..width {
+                row_vec.push(construct_new_tile(TileConstructionInfo::DrawableTile));
+            }
+            tiles.push(row_vec);
+        }
+
+        Yard {
+            tiles,
+            borders: vec![vec![TileBorderState::default(); width]; height],
+            train_activity: Vec::new(),
+            pending_switches: Vec::new(),
+        }
+    }
+
+    /// The number of columns in this yard.
+    pub fn width(&self) -> usize {
+        self.tiles.first().map_or(0, |row| row.len())
+    }
+
+    /// The number of rows in this yard.
+    pub fn height(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn replace_tile(&mut self, row: usize, col: usize, tile: Box<dyn Tile>) {
+        self.tiles[row][col] = tile;
+    }
+
+    /// Advances the yard by one tick, and returns everything that happened during that tick.
+    ///
+    /// This is deterministic: the same yard always produces the same events.
+    pub fn step(&mut self) -> YardProcessTickResult {
+        for (row, col) in std::mem::take(&mut self.pending_switches) {
+            self.switch_active_passive(row, col);
+        }
+
+        let result = self.tick();
+
+        for e in result.end_tick_events.iter() {
+            if e.event == TileEvent::SwitchActivePassive {
+                self.pending_switches.push((e.row, e.col));
+            }
+        }
+        result
+    }
+
+    fn tick(&mut self) -> YardProcessTickResult {
+        let mut start_tick_events = Vec::new();
+        let mut mid_tick_events = Vec::new();
+        let mut end_tick_events = Vec::new();
+
+        let (width, height) = (self.width(), self.height());
+        let mut outgoing_border_states = Vec::with_capacity(height);
+        self.train_activity = Vec::new();
+
+        for row in 0..height {
+            let mut outgoing_row = Vec::with_capacity(width);
+            for col in 0..width {
+                let incoming_border_state = &self.borders[row][col];
+                let tile = &mut self.tiles[row][col];
+
+                let tile_process_tick_result =
+                    tile.process_and_output(incoming_border_state.clone());
+                let train_tile_activity = tile_process_tick_result.trains;
+
+                for e in tile_process_tick_result.start_tick_events {
+                    start_tick_events.push(TileEventWithLocation { event: e, row, col });
+                }
+                for e in tile_process_tick_result.mid_tick_events {
+                    mid_tick_events.push(TileEventWithLocation { event: e, row, col });
+                }
+                for e in tile_process_tick_result.end_tick_events {
+                    end_tick_events.push(TileEventWithLocation { event: e, row, col });
+                }
+
+                let mut outgoing_border_state = TileBorderState::new();
+
+                for dir_u8 in 0..4 {
+                    let out_dir = Dir::from(dir_u8);
+                    let mut colors_to_mix: Vec<TrainColor> = Vec::new();
+                    for train_coming_thru in train_tile_activity.iter() {
+                        if train_coming_thru.to_dir == Some(out_dir) {
+                            colors_to_mix.push(train_coming_thru.end_color);
+                        }
+                    }
+                    if !colors_to_mix.is_empty() {
+                        let new_train_color = TrainColor::mix_many(&colors_to_mix);
+                        outgoing_border_state.add_train(new_train_color, out_dir);
+                        if colors_to_mix.len() > 1 {
+                            end_tick_events.push(TileEventWithLocation {
+                                event: TileEvent::MixColors(
+                                    new_train_color,
+                                    out_dir.to_local_coords_of_edge(),
+                                ),
+                                row,
+                                col,
+                            });
+                        }
+                    }
+                }
+                for train_activity in train_tile_activity {
+                    self.train_activity.push(TrainActivityWithLocation {
+                        row,
+                        col,
+                        activity: train_activity,
+                    });
+                }
+                outgoing_row.push(outgoing_border_state);
+            }
+            outgoing_border_states.push(outgoing_row);
+        }
+
+        // swap borders, so outgoing becomes incoming:
+        for row in 0..height.saturating_sub(1) {
+            let (below, above) = outgoing_border_states.split_at_mut(row + 1);
+            for (col, (lower, upper)) in below[row].iter_mut().zip(above[0].iter_mut()).enumerate()
+            {
+                // vertical swaps:
+                let mut t1 = lower.get_train(Dir::Up);
+                let mut t2 = upper.get_train(Dir::Down);
+                if t1.is_some() && t2.is_some() {
+                    let new_color = t1.unwrap().mix_with(t2.unwrap());
+                    t1 = Some(new_color);
+                    t2 = Some(new_color);
+                    end_tick_events.push(TileEventWithLocation {
+                        event: TileEvent::MixColors(new_color, Dir::Up.to_local_coords_of_edge()),
+                        row,
+                        col,
+                    })
+                }
+                lower.set_train(t2, Dir::Up);
+                upper.set_train(t1, Dir::Down);
+            }
+        }
+        for (row, outgoing_row) in outgoing_border_states.iter_mut().enumerate() {
+            for col in 0..width.saturating_sub(1) {
+                // horizontal swaps:
+                let mut t1 = outgoing_row[col].get_train(Dir::Right);
+                let mut t2 = outgoing_row[col + 1].get_train(Dir::Left);
+                if t1.is_some() && t2.is_some() {
+                    let new_color = t1.unwrap().mix_with(t2.unwrap());
+                    t1 = Some(new_color);
+                    t2 = Some(new_color);
+                    end_tick_events.push(TileEventWithLocation {
+                        event: TileEvent::MixColors(
+                            new_color,
+                            Dir::Right.to_local_coords_of_edge(),
+                        ),
+                        row,
+                        col,
+                    })
+                }
+                outgoing_row[col].set_train(t2, Dir::Right);
+                outgoing_row[col + 1].set_train(t1, Dir::Left);
+            }
+        }
+        self.borders = outgoing_border_states;
+
+        YardProcessTickResult {
+            start_tick_events,
+            mid_tick_events,
+            end_tick_events,
+        }
+    }
+
+    // check if all source tiles are empty, all destination tiles are empty, and if all borders are empty.
+    pub fn has_won(&self) -> bool {
+        if !self.train_activity.is_empty() {
+            return false;
+        }
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                if !self.tiles[row][col].has_no_remaining_trains() {
+                    return false;
+                }
+                if !self.borders[row][col].is_empty() {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Draws the tracks that were saved by `get_progress` onto this yard.
+    ///
+    /// Tracks that were saved from a yard of a different size are cut off or padded with empty tiles, and tiles with
+    /// data that drawing can't produce are left empty.
+    pub fn apply_progress(&mut self, drawn_tracks: &[u8]) {
+        let width = self.width();
+        for row in 0..self.height() {
+            for col in 0..width {
+                let data = drawn_tracks.get(row * width + col).copied().unwrap_or(0);
+                let conns = TileConnections::try_from_data(data).unwrap_or_default();
+
+                if !conns.get_active_conn().is_empty() {
+                    let (d1, d2) = conns.get_passive_conn().get_dirs();
+                    self.tiles[row][col].add_connection(d1, d2);
+                }
+                if !conns.get_active_conn().is_empty() {
+                    let (d1, d2) = conns.get_active_conn().get_dirs();
+                    self.tiles[row][col].add_connection(d1, d2);
+                }
+            }
+        }
+    }
+
+    /// Like `apply_progress`, but replaces the tracks that are already drawn instead of adding to them.
+    pub fn set_progress(&mut self, drawn_tracks: &[u8]) {
+        let width = self.width();
+        for row in 0..self.height() {
+            for col in 0..width {
+                let data = drawn_tracks.get(row * width + col).copied().unwrap_or(0);
+                let conns = TileConnections::try_from_data(data).unwrap_or_default();
+                self.set_connections(row, col, conns);
+            }
+        }
+    }
+
+    /// Replaces whatever tracks are on the tile at `row`, `col` with `connections`, keeping which one is active.
+    pub fn set_connections(&mut self, row: usize, col: usize, connections: TileConnections) {
+        let tile = &mut self.tiles[row][col];
+        tile.erase_connections();
+        let passive = connections.get_passive_conn();
+        if !passive.is_empty() {
+            let (d1, d2) = passive.get_dirs();
+            tile.add_connection(d1, d2);
+        }
+        let active = connections.get_active_conn();
+        if !active.is_empty() {
+            let (d1, d2) = active.get_dirs();
+            tile.add_connection(d1, d2);
+        }
+    }
+
+    pub fn get_progress(&self) -> Vec<u8> {
+        self.tiles
+            .iter()
+            .flat_map(|row| row.iter().map(|tile| tile.get_connection_data()))
+            .collect()
+    }
+
+    pub fn switch_active_passive(&mut self, row: usize, col: usize) {
+        self.tiles[row][col].switch_active_passive();
+    }
+}
@@ -0,0 +1,661 @@
+//! The original game's compact codes for yards, like `hh002OjC3GiaC00`, which fit a whole level on one line.
+//!
+//! A code is the size of the yard, then each tile in turn from the top left corner, going across each row:
+//!
+//! ```text
+//! hh          <- 7 wide, 7 tall, with letters counting from a = 0 up to Z = 51
+//! 0 1..9      <- a run of empty tiles, with 0 meaning 10
+//! R           <- rock
+//! O dc c..    <- source: exit direction * 9 + (trains - 1), then the trains' colors, two to a letter
+//! G mn c..    <- sink: entry directions as a bit mask, trains - 1, then the colors
+//! P cd        <- painter: color, then the two directions it connects as one letter
+//! S d         <- splitter: entry direction
+//! ```
+//!
+//! Empty tiles at the end can be left off. Drawn tracks can follow an `_`, written the same way: a run of digits for
+//! tiles without any tracks (including the ones with something else on them), and a letter for the active and
+//! passive tracks on each of the others.
+
+use std::fmt;
+
+use crate::{
+    connections::{Connection, TileConnections},
+    direction::Dir,
+    level::{LevelLoadInfo, PainterInfo, SinkInfo, SourceInfo, SplitterInfo},
+    trains::TrainColor,
+    MAX_YARD_SIZE,
+};
+
+const TRACKS_SEPARATOR: char = '_';
+
+/// The order the original game numbers train colors in.
+const COLORS: [TrainColor; 7] = [
+    TrainColor::Red,
+    TrainColor::Yellow,
+    TrainColor::Blue,
+    TrainColor::Orange,
+    TrainColor::Green,
+    TrainColor::Purple,
+    TrainColor::Brown,
+];
+
+/// The tracks a tile can hold, by the number the original game gives them. 0 means no track.
+const TRACKS: [(Dir, Dir); 6] = [
+    (Dir::Up, Dir::Right),
+    (Dir::Up, Dir::Left),
+    (Dir::Down, Dir::Left),
+    (Dir::Down, Dir::Right),
+    (Dir::Up, Dir::Down),
+    (Dir::Left, Dir::Right),
+];
+
+/// The most trains a source can start with in a code.
+pub const MAX_SOURCE_TRAINS: usize = 9;
+const MAX_SINK_TRAINS: usize = 52;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YardCodeError {
+    /// The code couldn't be read. Characters are counted from 1.
+    Invalid { character: usize, message: String },
+    /// The level has something in it that a code can't hold.
+    Unencodable(String),
+}
+
+impl fmt::Display for YardCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YardCodeError::Invalid { character, message } => {
+                write!(f, "character {}: {}", character, message)
+            }
+            YardCodeError::Unencodable(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for YardCodeError {}
+
+/// Reads a level out of a code, along with the tracks drawn on it if the code has any.
+///
+/// Codes don't hold a level's name, city or stars, so those are left empty.
+pub fn decode_yard_code(code: &str) -> Result<(LevelLoadInfo, Option<Vec<u8>>), YardCodeError> {
+    let code = code.trim();
+    let (level_code, tracks_code) = match code.split_once(TRACKS_SEPARATOR) {
+        Some((level_code, tracks_code)) => (level_code, Some(tracks_code)),
+        None => (code, None),
+    };
+
+    let mut reader = CodeReader::new(level_code, 0);
+    let (width, height) = reader.read_yard_size()?;
+    let mut level = LevelLoadInfo {
+        width,
+        height,
+        ..LevelLoadInfo::default()
+    };
+    while let Some(position) = reader.next_tile()? {
+        let symbol = reader.next_char()?;
+        match symbol {
+            'R' => level.rocks.push(position),
+            'O' => {
+                let value = reader.next_value()?;
+                let dir = reader.dir(value / MAX_SOURCE_TRAINS)?;
+                let trains = reader.read_colors(value % MAX_SOURCE_TRAINS + 1)?;
+                level.sources.push(SourceInfo {
+                    trains,
+                    out_dir: dir,
+                    position,
+                });
+            }
+            'G' => {
+                let mask = reader.next_value()?;
+                if mask == 0 || mask >= 16 {
+                    return Err(reader.error(format!("{} is not a set of directions", mask)));
+                }
+                let dirs = Dir::all_dirs()
+                    .filter(|dir| mask & (1 << u8::from(*dir)) != 0)
+                    .collect();
+                let count = reader.next_value()? + 1;
+                let trains = reader.read_colors(count)?;
+                level.sinks.push(SinkInfo {
+                    trains,
+                    in_dirs: dirs,
+                    position,
+                });
+            }
+            'P' => {
+                let color = reader.next_value()?;
+                let color = reader.color(color)?;
+                let dirs = reader.next_value()?;
+                let in_dir = reader.dir(dirs / 7)?;
+                let out_dir = reader.dir(dirs % 7)?;
+                level.painters.push(PainterInfo {
+                    color,
+                    in_dir,
+                    out_dir,
+                    position,
+                });
+            }
+            'S' => {
+                let dir = reader.next_value()?;
+                let dir = reader.dir(dir)?;
+                level.splitters.push(SplitterInfo {
+                    in_dir: dir,
+                    position,
+                });
+            }
+            _ => return Err(reader.error(format!("{:?} is not a kind of tile", symbol))),
+        }
+    }
+
+    let tracks = tracks_code
+        .map(|tracks_code| {
+            let mut reader = CodeReader::new(tracks_code, level_code.len() + 1);
+            reader.set_yard_size(width, height);
+            read_tracks(&mut reader)
+        })
+        .transpose()?;
+    Ok((level, tracks))
+}
+
+/// Reads tracks on their own for a yard that is `width` by `height`, in the same format as `Yard::get_progress`.
+/// The yard's size can be given in front, like `hh_`, as long as it's the same size.
+pub fn decode_tracks_code(code: &str, width: u8, height: u8) -> Result<Vec<u8>, YardCodeError> {
+    let code = code.trim();
+    let mut reader = match code.split_once(TRACKS_SEPARATOR) {
+        Some((size, tracks_code)) => {
+            let mut size_reader = CodeReader::new(size, 0);
+            let code_size = size_reader.read_yard_size()?;
+            if code_size != (width, height) {
+                return Err(size_reader.error(format!(
+                    "the tracks are for a {}x{} yard, not a {}x{} one",
+                    code_size.0, code_size.1, width, height
+                )));
+            }
+            CodeReader::new(tracks_code, size.len() + 1)
+        }
+        None => CodeReader::new(code, 0),
+    };
+    reader.set_yard_size(width, height);
+    read_tracks(&mut reader)
+}
+
+fn read_tracks(reader: &mut CodeReader) -> Result<Vec<u8>, YardCodeError> {
+    let mut tracks = vec![0; reader.width as usize * reader.height as usize];
+    while let Some((row, col)) = reader.next_tile()? {
+        let value = reader.next_value()?;
+        let active = reader.connection(value / 7)?;
+        let passive = reader.connection(value % 7)?;
+        let connections = TileConnections::try_from_data(
+            TileConnections::from_active_passive(active, passive).get_data(),
+        )
+        .ok_or_else(|| {
+            reader.error(String::from(
+                "a tile can't have a passive track without an active one",
+            ))
+        })?;
+        tracks[row as usize * reader.width as usize + col as usize] = connections.get_data();
+    }
+    Ok(tracks)
+}
+
+/// Writes `level` out as a code, with `tracks` (in the same format as `Yard::get_progress`) after it if given.
+pub fn encode_yard_code(
+    level: &LevelLoadInfo,
+    tracks: Option<&[u8]>,
+) -> Result<String, YardCodeError> {
+    let (width, height) = (level.width, level.height);
+    if !(1..=MAX_YARD_SIZE).contains(&width) || !(1..=MAX_YARD_SIZE).contains(&height) {
+        return Err(YardCodeError::Unencodable(format!(
+            "a {}x{} yard is too big or too small to share",
+            width, height
+        )));
+    }
+    let mut tiles: Vec<Option<String>> = vec![None; level.num_tiles()];
+    let mut place = |position: (u8, u8), tile: String| {
+        let index = tile_index(position, width, height)?;
+        if tiles[index].is_some() {
+            return Err(YardCodeError::Unencodable(format!(
+                "more than one tile at {:?}",
+                position
+            )));
+        }
+        tiles[index] = Some(tile);
+        Ok(())
+    };
+
+    for rock in level.rocks.iter() {
+        place(*rock, String::from("R"))?;
+    }
+    for SourceInfo {
+        trains,
+        out_dir: dir,
+        position,
+    } in level.sources.iter()
+    {
+        if trains.is_empty() || trains.len() > MAX_SOURCE_TRAINS {
+            return Err(YardCodeError::Unencodable(format!(
+                "sources hold 1 to {} trains, the one at {:?} has {}",
+                MAX_SOURCE_TRAINS,
+                position,
+                trains.len()
+            )));
+        }
+        let value = u8::from(*dir) as usize * MAX_SOURCE_TRAINS + trains.len() - 1;
+        place(
+            *position,
+            format!("O{}{}", value_char(value), colors_str(trains)),
+        )?;
+    }
+    for SinkInfo {
+        trains,
+        in_dirs: dirs,
+        position,
+    } in level.sinks.iter()
+    {
+        if trains.is_empty() || trains.len() > MAX_SINK_TRAINS || dirs.is_empty() {
+            return Err(YardCodeError::Unencodable(format!(
+                "sinks hold 1 to {} trains and take them from at least one direction, the one at {:?} doesn't",
+                MAX_SINK_TRAINS, position
+            )));
+        }
+        let mask = dirs
+            .iter()
+            .fold(0, |mask, dir| mask | (1 << u8::from(*dir)));
+        let tile = format!(
+            "G{}{}{}",
+            value_char(mask),
+            value_char(trains.len() - 1),
+            colors_str(trains)
+        );
+        place(*position, tile)?;
+    }
+    for PainterInfo {
+        color,
+        in_dir,
+        out_dir,
+        position,
+    } in level.painters.iter()
+    {
+        let dirs = u8::from(*in_dir) as usize * 7 + u8::from(*out_dir) as usize;
+        let tile = format!("P{}{}", value_char(color_index(*color)), value_char(dirs));
+        place(*position, tile)?;
+    }
+    for SplitterInfo {
+        in_dir: dir,
+        position,
+    } in level.splitters.iter()
+    {
+        place(
+            *position,
+            format!("S{}", value_char(u8::from(*dir) as usize)),
+        )?;
+    }
+
+    let mut code = format!(
+        "{}{}",
+        value_char(width as usize),
+        value_char(height as usize)
+    );
+    write_tiles(&mut code, tiles);
+    if let Some(tracks) = tracks.filter(|tracks| tracks.iter().any(|data| *data != 0)) {
+        code.push(TRACKS_SEPARATOR);
+        code.push_str(&encode_tracks_code(tracks, width, height));
+    }
+    Ok(code)
+}
+
+/// Writes tracks for a yard that is `width` by `height` (in the same format as `Yard::get_progress`) out on their own.
+pub fn encode_tracks_code(tracks: &[u8], width: u8, height: u8) -> String {
+    let mut tiles: Vec<Option<String>> = vec![None; width as usize * height as usize];
+    for row in 0..height {
+        for col in 0..width {
+            let data = tracks
+                .get(row as usize * width as usize + col as usize)
+                .copied()
+                .unwrap_or_default();
+            let connections = TileConnections::from_data(data);
+            if connections.is_empty() {
+                continue;
+            }
+            let active = track_number(connections.get_active_conn());
+            let passive = track_number(connections.get_passive_conn());
+            // the position is always inside the yard here
+            let index = tile_index((row, col), width, height).unwrap();
+            tiles[index] = Some(value_char(active * 7 + passive).to_string());
+        }
+    }
+    let mut code = String::new();
+    write_tiles(&mut code, tiles);
+    code
+}
+
+/// Writes out tiles in code order, with runs of digits for the empty ones.
+fn write_tiles(code: &mut String, tiles: Vec<Option<String>>) {
+    let mut empty_run = 0;
+    for tile in tiles {
+        let Some(tile) = tile else {
+            empty_run += 1;
+            continue;
+        };
+        write_empty_run(code, empty_run);
+        empty_run = 0;
+        code.push_str(&tile);
+    }
+    // empty tiles at the end don't need to be written
+}
+
+fn write_empty_run(code: &mut String, mut count: usize) {
+    while count >= 10 {
+        code.push('0');
+        count -= 10;
+    }
+    if count > 0 {
+        code.push_str(&count.to_string());
+    }
+}
+
+/// Where the tile at (row, col) comes in a code, since codes start at the top left and rows start at the bottom.
+fn tile_index((row, col): (u8, u8), width: u8, height: u8) -> Result<usize, YardCodeError> {
+    if row >= height || col >= width {
+        return Err(YardCodeError::Unencodable(format!(
+            "{:?} is outside of the {}x{} grid",
+            (row, col),
+            width,
+            height
+        )));
+    }
+    Ok((height - 1 - row) as usize * width as usize + col as usize)
+}
+
+fn value_char(value: usize) -> char {
+    match value {
+        0..26 => (b'a' + value as u8) as char,
+        26..52 => (b'A' + (value - 26) as u8) as char,
+        _ => panic!("{} is too big to fit in one letter", value),
+    }
+}
+
+fn color_index(color: TrainColor) -> usize {
+    COLORS.iter().position(|c| *c == color).unwrap()
+}
+
+fn colors_str(trains: &[TrainColor]) -> String {
+    trains
+        .chunks(2)
+        .map(|pair| {
+            // an odd train out is paired with red, which is ignored when reading
+            let second = pair.get(1).map_or(0, |color| color_index(*color));
+            value_char(color_index(pair[0]) * 7 + second)
+        })
+        .collect()
+}
+
+fn track_number(connection: Connection) -> usize {
+    if connection.is_empty() {
+        return 0;
+    }
+    let (d1, d2) = connection.get_dirs();
+    TRACKS
+        .iter()
+        .position(|track| *track == (d1, d2) || *track == (d2, d1))
+        .map_or(0, |index| index + 1)
+}
+
+/// Steps through a code one character at a time, keeping track of which tile it's up to.
+struct CodeReader<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    /// Where this part of the code starts in the whole code, for error messages.
+    offset: usize,
+    /// The character that was read last.
+    character: usize,
+    tile: usize,
+    /// The size of the yard the tiles are in, once it's known.
+    width: u8,
+    height: u8,
+}
+
+impl<'a> CodeReader<'a> {
+    fn new(code: &'a str, offset: usize) -> Self {
+        CodeReader {
+            chars: code.char_indices().peekable(),
+            offset,
+            character: offset,
+            tile: 0,
+            width: 0,
+            height: 0,
+        }
+    }
+
+    fn error(&self, message: String) -> YardCodeError {
+        YardCodeError::Invalid {
+            character: self.character.max(1),
+            message,
+        }
+    }
+
+    fn next_char(&mut self) -> Result<char, YardCodeError> {
+        let (index, char) = self
+            .chars
+            .next()
+            .ok_or_else(|| self.error(String::from("the code ends in the middle of a tile")))?;
+        self.character = self.offset + index + 1;
+        Ok(char)
+    }
+
+    fn next_value(&mut self) -> Result<usize, YardCodeError> {
+        let char = self.next_char()?;
+        match char {
+            'a'..='z' => Ok(char as usize - 'a' as usize),
+            'A'..='Z' => Ok(char as usize - 'A' as usize + 26),
+            _ => Err(self.error(format!("expected a letter, found {:?}", char))),
+        }
+    }
+
+    /// Reads the yard's size as (width, height), which the tiles after it are laid out in.
+    fn read_yard_size(&mut self) -> Result<(u8, u8), YardCodeError> {
+        let width = self.next_value()?;
+        let height = self.next_value()?;
+        let size_range = 1..=MAX_YARD_SIZE as usize;
+        if !size_range.contains(&width) || !size_range.contains(&height) {
+            return Err(self.error(format!(
+                "the yard is {}x{}, only sizes from 1x1 to {}x{} are supported",
+                width, height, MAX_YARD_SIZE, MAX_YARD_SIZE
+            )));
+        }
+        self.set_yard_size(width as u8, height as u8);
+        Ok((width as u8, height as u8))
+    }
+
+    fn set_yard_size(&mut self, width: u8, height: u8) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Skips over any empty tiles, and gives the (row, col) of the next tile, or `None` at the end of the code.
+    fn next_tile(&mut self) -> Result<Option<(u8, u8)>, YardCodeError> {
+        while let Some(digit) = self.chars.peek().and_then(|(_, char)| char.to_digit(10)) {
+            self.next_char()?;
+            self.tile += if digit == 0 { 10 } else { digit as usize };
+        }
+        let Some((index, _)) = self.chars.peek() else {
+            return Ok(None);
+        };
+        let index = *index;
+        let tile = self.tile;
+        self.tile += 1;
+        let width = self.width as usize;
+        if tile >= width * self.height as usize {
+            return Err(YardCodeError::Invalid {
+                character: self.offset + index + 1,
+                message: format!("tile {} is past the end of the yard", tile + 1),
+            });
+        }
+        let row = self.height - 1 - (tile / width) as u8;
+        let col = (tile % width) as u8;
+        Ok(Some((row, col)))
+    }
+
+    fn read_colors(&mut self, count: usize) -> Result<Vec<TrainColor>, YardCodeError> {
+        let mut trains = Vec::new();
+        for _ in 0..count.div_ceil(2) {
+            let value = self.next_value()?;
+            trains.push(self.color(value / 7)?);
+            trains.push(self.color(value % 7)?);
+        }
+        trains.truncate(count);
+        Ok(trains)
+    }
+
+    fn color(&self, value: usize) -> Result<TrainColor, YardCodeError> {
+        COLORS
+            .get(value)
+            .copied()
+            .ok_or_else(|| self.error(format!("{} is not a train color", value)))
+    }
+
+    fn dir(&self, value: usize) -> Result<Dir, YardCodeError> {
+        if value < 4 {
+            Ok(Dir::from(value as u8))
+        } else {
+            Err(self.error(format!("{} is not a direction", value)))
+        }
+    }
+
+    fn connection(&self, value: usize) -> Result<Connection, YardCodeError> {
+        match value {
+            // the same direction twice makes an empty connection
+            0 => Ok(Connection::from_dirs(Dir::Up, Dir::Up)),
+            1..=6 => {
+                let (d1, d2) = TRACKS[value - 1];
+                Ok(Connection::from_dirs(d1, d2))
+            }
+            _ => Err(self.error(format!("{} is not a track", value))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels_txt::parse_levels_txt;
+
+    const LEVEL_DATA: &str = include_str!("../../../assets/levels.txt");
+
+    /// A 3x2 yard with a rock and a source sending a red then a blue train right along the top row, and a sink taking
+    /// a yellow train from above or the left in the bottom left corner. Then a Left-Right track next to the sink.
+    const SMALL_CODE: &str = "dcROkc1Gjah_4Q";
+
+    fn invalid_at(code: &str) -> usize {
+        match decode_yard_code(code) {
+            Err(YardCodeError::Invalid { character, .. }) => character,
+            other => panic!("{:?} should not decode, but gave {:?}", code, other),
+        }
+    }
+
+    #[test]
+    fn decodes_every_kind_of_tile_and_tracks() {
+        let (level, tracks) = decode_yard_code(SMALL_CODE).unwrap();
+        assert_eq!((level.width, level.height), (3, 2));
+        assert_eq!(level.rocks, [(1, 0)]);
+        assert_eq!(
+            level.sources,
+            [SourceInfo {
+                trains: vec![TrainColor::Red, TrainColor::Blue],
+                out_dir: Dir::Right,
+                position: (1, 1),
+            }]
+        );
+        assert_eq!(
+            level.sinks,
+            [SinkInfo {
+                trains: vec![TrainColor::Yellow],
+                in_dirs: vec![Dir::Up, Dir::Left],
+                position: (0, 0),
+            }]
+        );
+        assert_eq!(tracks, Some(vec![0, 0x0d, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn encodes_back_to_the_same_code() {
+        let (level, tracks) = decode_yard_code(SMALL_CODE).unwrap();
+        assert_eq!(
+            encode_yard_code(&level, tracks.as_deref()).unwrap(),
+            SMALL_CODE
+        );
+        // tracks that are all empty are left off
+        assert_eq!(
+            encode_yard_code(&level, Some(&[0; 6])).unwrap(),
+            "dcROkc1Gjah"
+        );
+    }
+
+    #[test]
+    fn stock_levels_round_trip() {
+        for level in parse_levels_txt(LEVEL_DATA).unwrap() {
+            let code = encode_yard_code(&level, level.solution.as_deref())
+                .unwrap_or_else(|e| panic!("{}: {}", level.name, e));
+            let (decoded, tracks) = decode_yard_code(&code).unwrap();
+            assert_eq!((decoded.width, decoded.height), (level.width, level.height));
+            let mut tiles = level.tiles();
+            let mut decoded_tiles = decoded.tiles();
+            tiles.sort_by_key(|(_, position)| *position);
+            decoded_tiles.sort_by_key(|(_, position)| *position);
+            assert_eq!(decoded_tiles.len(), tiles.len(), "{}", level.name);
+            assert_eq!(
+                encode_yard_code(&decoded, tracks.as_deref()).unwrap(),
+                code,
+                "{}",
+                level.name
+            );
+            if let Some(solution) = &level.solution {
+                assert_eq!(tracks.as_ref(), Some(solution), "{}", level.name);
+            }
+        }
+    }
+
+    #[test]
+    fn tracks_can_have_the_size_in_front() {
+        let tracks = vec![0, 0x0d, 0, 0, 0, 0];
+        assert_eq!(decode_tracks_code("4Q", 3, 2), Ok(tracks.clone()));
+        assert_eq!(decode_tracks_code("dc_4Q", 3, 2), Ok(tracks.clone()));
+        assert_eq!(encode_tracks_code(&tracks, 3, 2), "4Q");
+        assert!(decode_tracks_code("hh_4Q", 3, 2).is_err());
+    }
+
+    #[test]
+    fn malformed_codes_are_rejected() {
+        // the size is missing, or out of range
+        assert_eq!(invalid_at(""), 1);
+        assert_eq!(invalid_at("d"), 1);
+        assert_eq!(invalid_at("ac"), 2);
+        assert_eq!(invalid_at("2c"), 1);
+        // a tile that isn't one, or is cut off
+        assert_eq!(invalid_at("dcX"), 3);
+        assert_eq!(invalid_at("dcOk"), 4);
+        // a tile past the end of the yard
+        assert_eq!(invalid_at("dc6R"), 4);
+        // a sink that takes trains from nowhere, and a painter of a color that doesn't exist
+        assert_eq!(invalid_at("dcGaaa"), 4);
+        assert_eq!(invalid_at("dcPZb"), 4);
+        // a track that doesn't exist, and a passive track without an active one
+        assert_eq!(invalid_at("dc_4Z"), 5);
+        assert_eq!(invalid_at("dc_4b"), 5);
+    }
+
+    #[test]
+    fn levels_a_code_cant_hold_are_not_encoded() {
+        let (mut level, _) = decode_yard_code(SMALL_CODE).unwrap();
+        level.sources[0].trains = vec![TrainColor::Red; MAX_SOURCE_TRAINS + 1];
+        assert!(matches!(
+            encode_yard_code(&level, None),
+            Err(YardCodeError::Unencodable(_))
+        ));
+
+        let (mut level, _) = decode_yard_code(SMALL_CODE).unwrap();
+        level.rocks.push((0, 0));
+        assert!(matches!(
+            encode_yard_code(&level, None),
+            Err(YardCodeError::Unencodable(_))
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+//! Golden tests that run the known solutions of the game's levels and check that the trains still do exactly what they
+//! did when the golden files were written.
+//!
+//! The solutions are the ones stored with the levels, in `assets/levels/original_levels.json` for the original game's
+//! levels and in `assets/levels.txt` for the stock ones. Every event of every tick is written out to
+//! `tests/golden/original_levels.txt` and `tests/golden/stock_levels.txt`, so a change to how tiles move, mix or crash
+//! trains shows up as a diff of those files. Once a change in behavior is intended, the file is rewritten by running the tests with
+//! `UPDATE_GOLDEN=1` set, and the diff is reviewed like any other.
+//!
+//! The original game's solutions page, saved in `assets/assets_raw/puzzles`, is checked against the levels too.
+
+use std::{collections::HashMap, fmt::Write, fs, path::PathBuf};
+
+use trainyard_core::{
+    level::LevelLoadInfo, levels_txt::parse_levels_txt, yard::TileEventWithLocation,
+};
+
+/// More ticks than any of the levels take to win.
+const MAX_TICKS: usize = 1000;
+
+/// Levels that are spelled differently on the solutions page than in the level data, as (page, level data).
+const SOLUTIONS_PAGE_ALIASES: &[(&str, &str)] = &[("Ackee Tree", "Akee Tree")];
+
+fn workspace_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .join(path)
+}
+
+fn original_levels() -> Vec<LevelLoadInfo> {
+    let path = workspace_path("assets/levels/original_levels.json");
+    let contents =
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {:?}: {}", path, e));
+    serde_json::from_str(&contents).unwrap()
+}
+
+fn stock_levels() -> Vec<LevelLoadInfo> {
+    let path = workspace_path("assets/levels.txt");
+    let contents =
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {:?}: {}", path, e));
+    parse_levels_txt(&contents).unwrap()
+}
+
+/// How a run of a level's solution went.
+struct SolutionRun {
+    won: bool,
+    /// One line for every event, in the order `Yard::step` returned them.
+    events: Vec<String>,
+}
+
+/// Runs `drawn_tracks` on `level` until the trains win, one of them crashes, or `MAX_TICKS` have passed.
+fn run_solution(level: &LevelLoadInfo, drawn_tracks: &[u8]) -> SolutionRun {
+    let mut yard = level.to_yard();
+    yard.apply_progress(drawn_tracks);
+
+    let mut events = Vec::new();
+    let mut event_lines = |tick: usize, when: &str, tick_events: &[TileEventWithLocation]| {
+        for event in tick_events {
+            events.push(format!(
+                "{} {} ({}, {}) {:?}",
+                tick, when, event.row, event.col, event.event
+            ));
+        }
+    };
+    for tick in 1..=MAX_TICKS {
+        let result = yard.step();
+        event_lines(tick, "start", &result.start_tick_events);
+        event_lines(tick, "mid", &result.mid_tick_events);
+        event_lines(tick, "end", &result.end_tick_events);
+        if result.has_crashed() {
+            break;
+        }
+        if yard.has_won() {
+            return SolutionRun { won: true, events };
+        }
+    }
+    SolutionRun { won: false, events }
+}
+
+/// The names of the levels in `levels` whose stored solution doesn't win.
+fn losing_solutions(levels: &[LevelLoadInfo]) -> Vec<&str> {
+    levels
+        .iter()
+        .filter(|level| {
+            level
+                .solution
+                .as_ref()
+                .is_some_and(|solution| !run_solution(level, solution).won)
+        })
+        .map(|level| level.name.as_str())
+        .collect()
+}
+
+/// Every event of every level's stored solution. Levels without one are listed too, so that a solution going missing
+/// shows up in the diff.
+fn golden_events(levels: &[LevelLoadInfo]) -> String {
+    let mut golden = String::new();
+    for level in levels {
+        let Some(solution) = &level.solution else {
+            let _ = writeln!(golden, "== {} (no solution)", level.name);
+            continue;
+        };
+        let run = run_solution(level, solution);
+        let _ = writeln!(
+            golden,
+            "== {} ({})",
+            level.name,
+            if run.won { "won" } else { "lost" }
+        );
+        for line in run.events {
+            let _ = writeln!(golden, "{}", line);
+        }
+    }
+    golden
+}
+
+/// Checks `golden` against the golden file at `path`, or rewrites the file if `UPDATE_GOLDEN` is set.
+fn assert_matches_golden_file(golden: &str, path: &str) {
+    let path = workspace_path(path);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, golden).unwrap();
+        return;
+    }
+    let expected =
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {:?}: {}", path, e));
+    if golden != expected {
+        // pointing at the first difference is more useful than printing two files this long
+        let (line_number, (actual_line, expected_line)) = golden
+            .lines()
+            .chain(std::iter::repeat("<end of file>"))
+            .zip(expected.lines().chain(std::iter::repeat("<end of file>")))
+            .enumerate()
+            .find(|(_, (actual, expected))| actual != expected)
+            .unwrap();
+        panic!(
+            "the events differ from {:?} at line {}:\n  expected: {}\n    actual: {}\n\
+             rerun with UPDATE_GOLDEN=1 set if the change is intended",
+            path,
+            line_number + 1,
+            expected_line,
+            actual_line
+        );
+    }
+}
+
+#[test]
+fn stored_solutions_win() {
+    let levels = original_levels();
+    let failures = losing_solutions(&levels);
+    assert!(
+        failures.is_empty(),
+        "these levels' solutions don't win: {}",
+        failures.join(", ")
+    );
+}
+
+#[test]
+fn stored_solutions_match_golden_events() {
+    assert_matches_golden_file(
+        &golden_events(&original_levels()),
+        "crates/trainyard-core/tests/golden/original_levels.txt",
+    );
+}
+
+#[test]
+fn stock_solutions_win() {
+    let levels = stock_levels();
+    let failures = losing_solutions(&levels);
+    assert!(
+        failures.is_empty(),
+        "these stock levels' solutions don't win: {}",
+        failures.join(", ")
+    );
+}
+
+#[test]
+fn stock_solutions_match_golden_events() {
+    assert_matches_golden_file(
+        &golden_events(&stock_levels()),
+        "crates/trainyard-core/tests/golden/stock_levels.txt",
+    );
+}
+
+/// A level as it's listed on the original game's solutions page.
+struct SolutionsPageEntry {
+    name: String,
+    stars: u8,
+    solution_count: u32,
+}
+
+/// The text between the first `start` and the `end` after it, along with everything after that.
+fn text_between<'a>(html: &'a str, start: &str, end: &str) -> Option<(&'a str, &'a str)> {
+    let (_, rest) = html.split_once(start)?;
+    rest.split_once(end)
+}
+
+fn parse_solutions_page(html: &str) -> Vec<SolutionsPageEntry> {
+    let mut entries = Vec::new();
+    let mut rest = html;
+    while let Some((name, after_name)) = text_between(rest, "<div class=\"name\">", "<img") {
+        let (stars, after_stars) = text_between(after_name, "starLabel_", ".png").unwrap();
+        let (solution_count, after_count) =
+            text_between(after_stars, "<div class=\"numSolutions\">(", " solutions)").unwrap();
+        entries.push(SolutionsPageEntry {
+            name: name.trim().replace("&amp;", "&").replace("&#39;", "'"),
+            stars: stars.parse().unwrap(),
+            solution_count: solution_count.parse().unwrap(),
+        });
+        rest = after_count;
+    }
+    entries
+}
+
+#[test]
+fn solutions_page_matches_levels() {
+    let path = workspace_path("assets/assets_raw/puzzles/Trainyard - Solutions.html");
+    let html = fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {:?}: {}", path, e));
+    let entries = parse_solutions_page(&html);
+    assert!(!entries.is_empty(), "no levels found in {:?}", path);
+
+    let levels = original_levels();
+    let levels_by_name: HashMap<&str, &LevelLoadInfo> = levels
+        .iter()
+        .map(|level| (level.name.as_str(), level))
+        .collect();
+    let aliases: HashMap<&str, &str> = SOLUTIONS_PAGE_ALIASES.iter().copied().collect();
+
+    for entry in entries {
+        let name = aliases
+            .get(entry.name.as_str())
+            .copied()
+            .unwrap_or(&entry.name);
+        let level = levels_by_name
+            .get(name)
+            .unwrap_or_else(|| panic!("{} is on the solutions page but not in the levels", name));
+        assert_eq!(level.stars, entry.stars, "stars of {}", name);
+        assert!(entry.solution_count > 0, "{} has no solutions", name);
+    }
+}
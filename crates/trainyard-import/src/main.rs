@@ -0,0 +1,421 @@
+//! Reads the puzzles out of the original game's SQLite databases, and prints them as a JSON array of levels.
+//!
+//! Usage: `trainyard-import [path/to/assets_raw] > assets/levels/original_levels.json`
+//!
+//! Puzzles and solutions are written in the original game's compact codes, which `trainyard_core::yard_code` reads,
+//! the same as codes that players share in the game.
+//!
+//! Anything that can't be mapped onto our levels (unknown tile codes, tiles past the edge of the yard...) is skipped and
+//! reported on stderr. So are solutions that don't win here, see `check_solution`.
+//!
+//! The dev databases have some levels with the same name as a different, released level. Those are imported as
+//! "Name (City)", since the game looks levels up by name.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use rusqlite::Connection;
+use trainyard_core::connections::TileConnections;
+use trainyard_core::direction::Dir;
+use trainyard_core::level::{LevelLoadInfo, PainterInfo};
+use trainyard_core::solver::{self, SolverOptions};
+use trainyard_core::yard_code::{decode_tracks_code, decode_yard_code};
+
+const DEFAULT_RAW_DIR: &str = "assets/assets_raw";
+const USAGE: &str =
+    "usage: trainyard-import [path/to/assets_raw] > assets/levels/original_levels.json";
+
+/// (database with the levels, database with the players' solutions for them)
+const LEVEL_DATABASES: [(&str, &str); 4] = [
+    ("trainyardStatic.db", "trainyardLive.db"),
+    ("trainyardStatic_dev.db", "trainyardLive_dev.db"),
+    ("trainyardExpressStatic.db", "trainyardExpressLive.db"),
+    (
+        "trainyardExpressStatic_dev.db",
+        "trainyardExpressLive_dev.db",
+    ),
+];
+/// User-made puzzles, which store their own solutions and have no worlds.
+const PUZZLE_DATABASES: [&str; 2] = ["trainyardEngineer.db", "trainyardEngineer_dev.db"];
+const PUZZLE_CITY: &str = "Engineer";
+
+fn main() -> ExitCode {
+    let mut raw_dir = PathBuf::from(DEFAULT_RAW_DIR);
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => raw_dir = PathBuf::from(arg),
+        }
+    }
+
+    let mut importer = Importer::default();
+    if let Err(e) = importer
+        .import_levels(&raw_dir)
+        .and_then(|()| importer.import_puzzles(&raw_dir))
+    {
+        eprintln!(
+            "could not read the databases in {}: {}",
+            raw_dir.display(),
+            e
+        );
+        return ExitCode::from(2);
+    }
+    eprintln!("imported {} levels", importer.levels.len());
+    println!("{}", serde_json::to_string(&importer.levels).unwrap());
+    ExitCode::SUCCESS
+}
+
+#[derive(Default)]
+struct Importer {
+    levels: Vec<LevelLoadInfo>,
+    /// Where each name in `levels` is.
+    seen_names: HashMap<String, usize>,
+}
+
+impl Importer {
+    fn import_levels(&mut self, raw_dir: &Path) -> rusqlite::Result<()> {
+        for (db_name, live_db_name) in LEVEL_DATABASES {
+            let db = Connection::open(raw_dir.join(db_name))?;
+            let solutions = load_solutions(&raw_dir.join(live_db_name))?;
+            let cities: HashMap<String, String> = db
+                .prepare("SELECT slug, name FROM worlds")?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<_>>()?;
+            let mut statement = db.prepare(
+                "SELECT levels.slug, levels.worldSlug, levels.name, levels.stars, levels.puzzle FROM levels \
+                 JOIN worlds ON levels.worldSlug = worlds.slug ORDER BY worlds.ordinal, levels.ordinal",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, u8>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?;
+            for row in rows {
+                let (slug, world, name, stars, puzzle) = row?;
+                let city = cities.get(&world).cloned().unwrap_or_default();
+                let solution = solutions.get(&slug).map(String::as_str);
+                self.add_level(db_name, &name, &city, stars, &puzzle, solution);
+            }
+        }
+        Ok(())
+    }
+
+    fn import_puzzles(&mut self, raw_dir: &Path) -> rusqlite::Result<()> {
+        for db_name in PUZZLE_DATABASES {
+            let db = Connection::open(raw_dir.join(db_name))?;
+            let mut statement = db.prepare(
+                "SELECT name, puzzleString, solutionString FROM puzzles ORDER BY userOrdinal",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?;
+            for row in rows {
+                let (name, puzzle, solution) = row?;
+                let solution = solution.as_deref().filter(|solution| !solution.is_empty());
+                self.add_level(db_name, &name, PUZZLE_CITY, 0, &puzzle, solution);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a level from one of the databases, unless it's a copy of one that's already there.
+    ///
+    /// `solution` is the level's solution as the database stores it.
+    fn add_level(
+        &mut self,
+        source: &str,
+        name: &str,
+        city: &str,
+        stars: u8,
+        puzzle: &str,
+        solution: Option<&str>,
+    ) {
+        let mut level = match decode_yard_code(puzzle) {
+            Ok((level, _)) => level,
+            Err(e) => return report(source, name, &format!("skipping level, {}", e)),
+        };
+        level.name = name.to_string();
+        level.city = city.to_string();
+        level.stars = stars;
+        if let Some(solution) = solution {
+            match decode_tracks_code(solution, level.width, level.height)
+                .map_err(|e| e.to_string())
+                .and_then(|tracks| check_solution(&mut level, &tracks).map(|()| tracks))
+            {
+                Ok(tracks) => level.solution = Some(tracks),
+                Err(e) => report(source, name, &format!("skipping solution, {}", e)),
+            }
+        }
+
+        let mut name = name.to_string();
+        if self
+            .seen_names
+            .get(&name)
+            .is_some_and(|index| !same_puzzle(&self.levels[*index], &level))
+        {
+            // a different level with the same name, which the dev databases have a few of
+            name = format!("{} ({})", name, city);
+            level.name = name.clone();
+        }
+        if let Some(index) = self.seen_names.get(&name) {
+            let earlier = &mut self.levels[*index];
+            if !same_puzzle(earlier, &level) {
+                report(
+                    source,
+                    &name,
+                    "skipping level, a different level already has this name",
+                );
+            } else if earlier.solution.is_none() && level.solution.is_some() {
+                // the dev databases repeat a lot of the released levels
+                earlier.painters = level.painters;
+                earlier.solution = level.solution;
+            }
+            return;
+        }
+
+        self.seen_names.insert(name, self.levels.len());
+        self.levels.push(level);
+    }
+}
+
+fn report(source: &str, name: &str, message: &str) {
+    eprintln!("{}: {}: {}", source, name, message);
+}
+
+/// The first solved blueprint of each level in a live database, by level slug, still encoded.
+fn load_solutions(db_path: &Path) -> rusqlite::Result<HashMap<String, String>> {
+    let mut solutions = HashMap::new();
+    if !db_path.exists() {
+        return Ok(solutions);
+    }
+    let db = Connection::open(db_path)?;
+    let mut statement =
+        db.prepare("SELECT levelSlug, solution FROM blueprints WHERE isSolved ORDER BY ordinal")?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (slug, solution) = row?;
+        solutions.entry(slug).or_insert(solution);
+    }
+    Ok(solutions)
+}
+
+/// Whether two levels have the same yard, whatever they're called and whichever way their painters point.
+fn same_puzzle(level: &LevelLoadInfo, other: &LevelLoadInfo) -> bool {
+    let yard = |level: &LevelLoadInfo| LevelLoadInfo {
+        name: String::new(),
+        city: String::new(),
+        painters: level
+            .painters
+            .iter()
+            .map(|painter| {
+                let mut ends = [painter.in_dir, painter.out_dir];
+                ends.sort_by_key(|dir| u8::from(*dir));
+                PainterInfo {
+                    in_dir: ends[0],
+                    out_dir: ends[1],
+                    ..painter.clone()
+                }
+            })
+            .collect(),
+        solution: None,
+        ..level.clone()
+    };
+    yard(level) == yard(other)
+}
+
+/// Rejects solutions that don't beat their level here.
+///
+/// Some of the dev databases' blueprints are left over from before their level was changed, so they put track where
+/// there is now a fixed tile, or have track running off into a tile that is now empty. Those are pointed out, since
+/// they're the most likely reason.
+///
+/// The original game's painters let trains through either way, while ours only go from `in_dir` to `out_dir`, so the
+/// ends the databases give them are in no particular order. The level's painters are turned around to go the way the
+/// solution uses them.
+fn check_solution(level: &mut LevelLoadInfo, tracks: &[u8]) -> Result<(), String> {
+    check_solution_fits(level, tracks)?;
+    let max_ticks = SolverOptions::default().max_ticks;
+    let painters = level.painters.clone();
+    for flipped in 0..1 << painters.len() {
+        for (i, (painter, original)) in level.painters.iter_mut().zip(painters.iter()).enumerate() {
+            let (in_dir, out_dir) = if flipped & (1 << i) == 0 {
+                (original.in_dir, original.out_dir)
+            } else {
+                (original.out_dir, original.in_dir)
+            };
+            painter.in_dir = in_dir;
+            painter.out_dir = out_dir;
+        }
+        if solver::check_solution(level, tracks, max_ticks) {
+            return Ok(());
+        }
+    }
+    level.painters = painters;
+    Err(String::from("it doesn't win"))
+}
+
+/// Rejects solutions that put track on a fixed tile, or that run track into a tile that has none.
+fn check_solution_fits(level: &LevelLoadInfo, tracks: &[u8]) -> Result<(), String> {
+    let fixed: Vec<(u8, u8)> = level
+        .tiles()
+        .into_iter()
+        .map(|(_, position)| position)
+        .collect();
+    let width = level.width as usize;
+    for (index, data) in tracks.iter().enumerate() {
+        if *data == 0 {
+            continue;
+        }
+        let position = ((index / width) as u8, (index % width) as u8);
+        if fixed.contains(&position) {
+            return Err(format!("it has track on the fixed tile at {:?}", position));
+        }
+        let connections = TileConnections::from_data(*data);
+        for connection in [
+            connections.get_active_conn(),
+            connections.get_passive_conn(),
+        ] {
+            if connection.is_empty() {
+                continue;
+            }
+            let (d1, d2) = connection.get_dirs();
+            for dir in [d1, d2] {
+                let Some(next) = step(position, dir, level) else {
+                    continue;
+                };
+                let next_index = next.0 as usize * width + next.1 as usize;
+                if !fixed.contains(&next) && tracks[next_index] == 0 {
+                    return Err(format!(
+                        "the track at {:?} runs {:?} into an empty tile",
+                        position, dir
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The tile next to `(row, col)` in direction `dir`, if it's inside the yard. Row 0 is at the bottom.
+fn step((row, col): (u8, u8), dir: Dir, level: &LevelLoadInfo) -> Option<(u8, u8)> {
+    let (row, col) = match dir {
+        Dir::Up => (row.checked_add(1)?, col),
+        Dir::Right => (row, col.checked_add(1)?),
+        Dir::Down => (row.checked_sub(1)?, col),
+        Dir::Left => (row, col.checked_sub(1)?),
+    };
+    (row < level.height && col < level.width).then_some((row, col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 5 wide, 4 tall yard, with a rock and a sink expecting one red train from below in its top row, and a source
+    /// sending one red train up from the left end of the row under that.
+    const SMALL_PUZZLE: &str = "feRGeaa3Oaa";
+    /// A 3 wide, 1 tall yard, with a source sending a red train right into a sink on the other end.
+    const LINE_PUZZLE: &str = "dbOja1Giaa";
+
+    fn small_level() -> LevelLoadInfo {
+        decode_yard_code(SMALL_PUZZLE).unwrap().0
+    }
+
+    fn add(importer: &mut Importer, name: &str, city: &str, puzzle: &str, solution: Option<&str>) {
+        importer.add_level("test.db", name, city, 1, puzzle, solution);
+    }
+
+    #[test]
+    fn track_leading_into_an_empty_tile() {
+        let level = small_level();
+        let mut tracks = vec![0; 20];
+        tracks[5] = 0b1100; // Up-Left, leading to the source and off the yard
+        assert_eq!(check_solution_fits(&level, &tracks), Ok(()));
+        tracks[5] = 0b0100; // Up-Right
+        assert_eq!(
+            check_solution_fits(&level, &tracks),
+            Err(String::from(
+                "the track at (1, 0) runs Right into an empty tile"
+            ))
+        );
+        tracks[6..10].copy_from_slice(&[0b1101; 4]); // Left-Right, all the way to the edge
+        assert_eq!(check_solution_fits(&level, &tracks), Ok(()));
+        tracks[6] = 0b1110_1101; // Left-Right, switching to Down-Left
+        assert_eq!(
+            check_solution_fits(&level, &tracks),
+            Err(String::from(
+                "the track at (1, 1) runs Down into an empty tile"
+            ))
+        );
+    }
+
+    #[test]
+    fn track_on_a_fixed_tile() {
+        let level = small_level();
+        let mut tracks = vec![0; 20];
+        tracks[15] = 0b1000;
+        assert_eq!(
+            check_solution_fits(&level, &tracks),
+            Err(String::from("it has track on the fixed tile at (3, 0)"))
+        );
+    }
+
+    #[test]
+    fn solutions_have_to_win() {
+        let mut level = decode_yard_code(LINE_PUZZLE).unwrap().0;
+        assert_eq!(check_solution(&mut level, &[0, 0b1101, 0]), Ok(()));
+        assert_eq!(
+            check_solution(&mut level, &[0, 0b1000, 0]),
+            Err(String::from("it doesn't win"))
+        );
+    }
+
+    #[test]
+    fn painters_are_turned_to_fit_the_solution() {
+        // the painter's ends are given the wrong way around for the red train to be painted blue on its way right
+        let mut level = decode_yard_code("dbOjaPckGiao").unwrap().0;
+        assert_eq!(check_solution(&mut level, &[0; 3]), Ok(()));
+        assert_eq!(
+            (level.painters[0].in_dir, level.painters[0].out_dir),
+            (Dir::Left, Dir::Right)
+        );
+    }
+
+    #[test]
+    fn copies_are_merged() {
+        let mut importer = Importer::default();
+        add(&mut importer, "Line", "Town", LINE_PUZZLE, None);
+        add(&mut importer, "Line", "Dev town", LINE_PUZZLE, Some("1Q"));
+        assert_eq!(importer.levels.len(), 1);
+        assert_eq!(importer.levels[0].city, "Town");
+        assert_eq!(importer.levels[0].solution, Some(vec![0, 0b1101, 0]));
+    }
+
+    #[test]
+    fn different_levels_with_the_same_name_are_both_kept() {
+        let mut importer = Importer::default();
+        add(&mut importer, "Rock", "Town", "hhR", None);
+        add(&mut importer, "Rock", "Dev town", "hh1R", None);
+        add(&mut importer, "Rock", "Dev town", "hh1R", None);
+        let names: Vec<&str> = importer
+            .levels
+            .iter()
+            .map(|level| level.name.as_str())
+            .collect();
+        assert_eq!(names, ["Rock", "Rock (Dev town)"]);
+    }
+}
@@ -0,0 +1,75 @@
+//! Checks a levels file for mistakes that would crash the game on startup, or make a level impossible to beat.
+//!
+//! Usage: `trainyard-lint [--json] [path/to/levels.txt]`
+//!
+//! Reads the `levels.txt` format that the game ships, or a JSON array of levels if the path ends in `.json`.
+//! Prints one line per problem (or a JSON array of problems with `--json`), and exits with a non-zero status if
+//! anything was found.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use trainyard_core::level::LevelLoadInfo;
+use trainyard_core::levels_txt::parse_levels_txt;
+use trainyard_core::lint::lint_levels;
+
+const DEFAULT_LEVELS_PATH: &str = "assets/levels.txt";
+
+fn main() -> ExitCode {
+    let mut json_output = false;
+    let mut path = PathBuf::from(DEFAULT_LEVELS_PATH);
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json_output = true,
+            "-h" | "--help" => {
+                println!("usage: trainyard-lint [--json] [path/to/levels.txt]");
+                return ExitCode::SUCCESS;
+            }
+            _ => path = PathBuf::from(arg),
+        }
+    }
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("could not read {}: {}", path.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+    let levels = match parse_levels(&path, &contents) {
+        Ok(levels) => levels,
+        Err(e) => {
+            // both parsers' errors already include the line.
+            eprintln!("could not parse {}: {}", path.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let errors = lint_levels(&levels);
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&errors).unwrap());
+    } else {
+        for error in errors.iter() {
+            println!("{}", error);
+        }
+        eprintln!(
+            "checked {} levels, found {} problems",
+            levels.len(),
+            errors.len()
+        );
+    }
+
+    if errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_levels(path: &Path, contents: &str) -> Result<Vec<LevelLoadInfo>, String> {
+    if path.extension().is_some_and(|extension| extension == "json") {
+        serde_json::from_str(contents).map_err(|e| e.to_string())
+    } else {
+        parse_levels_txt(contents).map_err(|e| e.to_string())
+    }
+}
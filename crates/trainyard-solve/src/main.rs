@@ -0,0 +1,211 @@
+//! Searches for track layouts that beat the levels in a levels file.
+//!
+//! Usage: `trainyard-solve [--level NAME] [--solutions N] [--timeout SECS] [--write] [path/to/levels.txt]`
+//!
+//! Like `trainyard-lint`, this reads the `levels.txt` format, or a JSON array of levels if the path ends in `.json`.
+//!
+//! Without `--level`, every level is solved, and the command lists the ones it couldn't solve and exits with a non-zero
+//! status if there are any.
+//! Solutions are printed as a grid of track bytes (top row first), in the same format the save file uses.
+//!
+//! With `--write`, levels that already have a solution are only checked, and the first solution found for each of
+//! the others is written into the levels file, so the game can give hints from it. This only works on `levels.txt`.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use trainyard_core::level::LevelLoadInfo;
+use trainyard_core::levels_txt::{parse_levels_txt, replace_solutions};
+use trainyard_core::solver::{check_solution, solve, SolverOptions};
+use trainyard_core::tiles::TileConstructionInfo;
+
+const DEFAULT_LEVELS_PATH: &str = "assets/levels.txt";
+const USAGE: &str = "usage: trainyard-solve [--level NAME] [--solutions N] [--timeout SECS] [--write] [path/to/levels.txt]";
+
+fn main() -> ExitCode {
+    let mut path = PathBuf::from(DEFAULT_LEVELS_PATH);
+    let mut level_name: Option<String> = None;
+    let mut options = SolverOptions::default();
+    let mut write = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let parsed = match arg.as_str() {
+            "--level" => args.next().map(|name| level_name = Some(name)),
+            "--solutions" => args
+                .next()
+                .and_then(|n| n.parse().ok())
+                .map(|n| options.max_solutions = n),
+            "--timeout" => args
+                .next()
+                .and_then(|secs| secs.parse().ok())
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .map(|timeout| options.timeout = timeout),
+            "--write" => {
+                write = true;
+                Some(())
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => {
+                path = PathBuf::from(arg);
+                Some(())
+            }
+        };
+        if parsed.is_none() {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    }
+
+    if write && is_json(&path) {
+        eprintln!("--write only works on the levels.txt format");
+        return ExitCode::from(2);
+    }
+    let (contents, levels) = match std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| parse_levels(&path, &contents).map(|levels| (contents, levels)))
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("could not load {}: {}", path.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let levels: Vec<&LevelLoadInfo> = match &level_name {
+        Some(name) => levels.iter().filter(|level| &level.name == name).collect(),
+        None => levels.iter().collect(),
+    };
+    if levels.is_empty() {
+        eprintln!("no level named {:?}", level_name.unwrap_or_default());
+        return ExitCode::from(2);
+    }
+
+    let mut unsolved: Vec<&str> = Vec::new();
+    let mut new_solutions: Vec<(&str, Vec<u8>)> = Vec::new();
+    for level in levels.iter() {
+        if let (true, Some(solution)) = (write, &level.solution) {
+            let wins = check_solution(level, solution, options.max_ticks);
+            println!(
+                "{}: {}",
+                level.name,
+                if wins {
+                    "already solved"
+                } else {
+                    "stored solution doesn't win"
+                }
+            );
+            if !wins {
+                unsolved.push(&level.name);
+            }
+            continue;
+        }
+        let result = solve(level, &options);
+        let status = match (result.is_solved(), result.timed_out) {
+            (true, _) => format!("{} solution(s)", result.solutions.len()),
+            (false, true) => "timed out".to_string(),
+            (false, false) => "no solution".to_string(),
+        };
+        println!(
+            "{}: {} ({} states explored)",
+            level.name, status, result.states_explored
+        );
+        // the solver builds its solutions up piece by piece, so make sure they also work when drawn all at once
+        let all_replay = result.solutions.iter().all(|solution| {
+            let wins = check_solution(level, solution, options.max_ticks);
+            if !wins {
+                eprintln!("{}: a solution didn't win when replayed", level.name);
+            }
+            wins
+        });
+        if !result.is_solved() || !all_replay {
+            unsolved.push(&level.name);
+        } else if write {
+            new_solutions.push((&level.name, result.solutions[0].clone()));
+        }
+        if level_name.is_some() {
+            for solution in result.solutions.iter() {
+                print_solution(level, solution);
+            }
+        }
+    }
+
+    if !new_solutions.is_empty() {
+        let solution_for = |name: &str| {
+            let (_, solution) = new_solutions.iter().find(|(solved, _)| *solved == name)?;
+            Some(solution.as_slice())
+        };
+        let written = replace_solutions(&contents, solution_for)
+            .map_err(|e| e.to_string())
+            .and_then(|text| std::fs::write(&path, text).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            eprintln!("could not write {}: {}", path.display(), e);
+            return ExitCode::from(2);
+        }
+        eprintln!(
+            "wrote {} solution(s) to {}",
+            new_solutions.len(),
+            path.display()
+        );
+    }
+
+    if levels.len() > 1 {
+        eprintln!(
+            "solved {} of {} levels",
+            levels.len() - unsolved.len(),
+            levels.len()
+        );
+        if !unsolved.is_empty() {
+            eprintln!("unsolved: {}", unsolved.join(", "));
+        }
+    }
+    if unsolved.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+fn parse_levels(path: &Path, contents: &str) -> Result<Vec<LevelLoadInfo>, String> {
+    if is_json(path) {
+        serde_json::from_str(contents).map_err(|e| e.to_string())
+    } else {
+        parse_levels_txt(contents).map_err(|e| e.to_string())
+    }
+}
+
+fn print_solution(level: &LevelLoadInfo, solution: &[u8]) {
+    let fixed_tiles = level.tiles();
+    println!();
+    for row in (0..level.height).rev() {
+        let cells: Vec<String> = (0..level.width)
+            .map(|col| {
+                let fixed = fixed_tiles
+                    .iter()
+                    .find(|(_, position)| *position == (row, col));
+                match fixed.map(|(info, _)| info) {
+                    Some(TileConstructionInfo::Rock) => "##".to_string(),
+                    Some(TileConstructionInfo::SourceTile { .. }) => "So".to_string(),
+                    Some(TileConstructionInfo::SinkTile { .. }) => "Si".to_string(),
+                    Some(TileConstructionInfo::Painter { .. }) => "Pa".to_string(),
+                    Some(TileConstructionInfo::Splitter { .. }) => "Sp".to_string(),
+                    _ => match solution[row as usize * level.width as usize + col as usize] {
+                        0 => "..".to_string(),
+                        data => format!("{:02x}", data),
+                    },
+                }
+            })
+            .collect();
+        println!("{}", cells.join(" "));
+    }
+    println!();
+}
@@ -0,0 +1,277 @@
+//! The actions the player takes with the keyboard, and the keys that are bound to each of them.
+//!
+//! Systems read the actions from `ButtonInput<InputAction>` instead of reading keys, so that which keys do what is
+//! only decided here and in the player's `KeyBindings`, which are saved along with the rest of the settings.
+
+use std::collections::BTreeMap;
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+const CTRL_KEYS: [KeyCode; 2] = [KeyCode::ControlLeft, KeyCode::ControlRight];
+const SHIFT_KEYS: [KeyCode; 2] = [KeyCode::ShiftLeft, KeyCode::ShiftRight];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    DrawToggle,
+    EraseToggle,
+    RunToggle,
+    Undo,
+    Redo,
+    DebuggerPlayPause,
+    DebuggerStep,
+    DebuggerHalfStep,
+    DebuggerStepBack,
+    EditorTurn,
+    InspectorToggle,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 15] = [
+        InputAction::MoveUp,
+        InputAction::MoveDown,
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::DrawToggle,
+        InputAction::EraseToggle,
+        InputAction::RunToggle,
+        InputAction::Undo,
+        InputAction::Redo,
+        InputAction::DebuggerPlayPause,
+        InputAction::DebuggerStep,
+        InputAction::DebuggerHalfStep,
+        InputAction::DebuggerStepBack,
+        InputAction::EditorTurn,
+        InputAction::InspectorToggle,
+    ];
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            InputAction::MoveUp => "Move Up",
+            InputAction::MoveDown => "Move Down",
+            InputAction::MoveLeft => "Move Left",
+            InputAction::MoveRight => "Move Right",
+            InputAction::DrawToggle => "Draw",
+            InputAction::EraseToggle => "Erase",
+            InputAction::RunToggle => "Start / Stop Trains",
+            InputAction::Undo => "Undo",
+            InputAction::Redo => "Redo",
+            InputAction::DebuggerPlayPause => "Debugger Play / Pause",
+            InputAction::DebuggerStep => "Debugger Tick",
+            InputAction::DebuggerHalfStep => "Debugger Half Tick",
+            InputAction::DebuggerStepBack => "Debugger Back",
+            InputAction::EditorTurn => "Turn (Editor)",
+            InputAction::InspectorToggle => "Inspector",
+        }
+    }
+
+    /// The keys bound to the action until the player binds others. The arrow keys move the cursor as well as WASD,
+    /// so the debugger steps with the comma and period keys.
+    pub fn default_bindings(self) -> Vec<KeyBinding> {
+        match self {
+            InputAction::MoveUp => vec![
+                KeyBinding::new(KeyCode::KeyW),
+                KeyBinding::new(KeyCode::ArrowUp),
+            ],
+            InputAction::MoveDown => vec![
+                KeyBinding::new(KeyCode::KeyS),
+                KeyBinding::new(KeyCode::ArrowDown),
+            ],
+            InputAction::MoveLeft => vec![
+                KeyBinding::new(KeyCode::KeyA),
+                KeyBinding::new(KeyCode::ArrowLeft),
+            ],
+            InputAction::MoveRight => vec![
+                KeyBinding::new(KeyCode::KeyD),
+                KeyBinding::new(KeyCode::ArrowRight),
+            ],
+            InputAction::DrawToggle => vec![KeyBinding::new(KeyCode::KeyE)],
+            InputAction::EraseToggle => vec![KeyBinding::new(KeyCode::KeyQ)],
+            InputAction::RunToggle => vec![KeyBinding::new(KeyCode::Space)],
+            InputAction::Undo => vec![KeyBinding::new(KeyCode::KeyZ).with_ctrl()],
+            InputAction::Redo => vec![KeyBinding::new(KeyCode::KeyZ).with_ctrl().with_shift()],
+            InputAction::DebuggerPlayPause => vec![KeyBinding::new(KeyCode::KeyB)],
+            InputAction::DebuggerStep => vec![KeyBinding::new(KeyCode::Period)],
+            InputAction::DebuggerHalfStep => vec![KeyBinding::new(KeyCode::Period).with_shift()],
+            InputAction::DebuggerStepBack => vec![KeyBinding::new(KeyCode::Comma)],
+            InputAction::EditorTurn => vec![KeyBinding::new(KeyCode::KeyR)],
+            InputAction::InspectorToggle => vec![KeyBinding::new(KeyCode::Escape)],
+        }
+    }
+}
+
+/// A key, along with the modifier keys that have to be held down for it to count. The modifiers have to match
+/// exactly, so that Ctrl+Z and Ctrl+Shift+Z can be bound to different actions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+}
+
+impl KeyBinding {
+    pub fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+        }
+    }
+
+    pub fn with_ctrl(self) -> Self {
+        Self { ctrl: true, ..self }
+    }
+
+    pub fn with_shift(self) -> Self {
+        Self {
+            shift: true,
+            ..self
+        }
+    }
+
+    /// The binding of `key`, with whichever modifiers are held down along with it.
+    pub fn from_keys(key: KeyCode, keys: &ButtonInput<KeyCode>) -> Self {
+        Self {
+            key,
+            ctrl: keys.any_pressed(CTRL_KEYS),
+            shift: keys.any_pressed(SHIFT_KEYS),
+        }
+    }
+
+    /// Whether `key` is only ever held down along with other keys, and can't be bound on its own.
+    pub fn is_modifier(key: KeyCode) -> bool {
+        CTRL_KEYS.contains(&key) || SHIFT_KEYS.contains(&key)
+    }
+
+    fn modifiers_match(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        keys.any_pressed(CTRL_KEYS) == self.ctrl && keys.any_pressed(SHIFT_KEYS) == self.shift
+    }
+
+    pub fn just_pressed(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        keys.just_pressed(self.key) && self.modifiers_match(keys)
+    }
+
+    pub fn pressed(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        keys.pressed(self.key) && self.modifiers_match(keys)
+    }
+
+    /// How the binding is written out for the player, like "Ctrl+Shift+Z" or "Left".
+    pub fn label(&self) -> String {
+        let key = match self.key {
+            KeyCode::Comma => String::from(","),
+            KeyCode::Period => String::from("."),
+            key => {
+                let name = format!("{:?}", key);
+                ["Key", "Digit", "Arrow"]
+                    .iter()
+                    .find_map(|prefix| name.strip_prefix(prefix))
+                    .map_or(name.clone(), String::from)
+            }
+        };
+        format!(
+            "{}{}{}",
+            if self.ctrl { "Ctrl+" } else { "" },
+            if self.shift { "Shift+" } else { "" },
+            key
+        )
+    }
+}
+
+/// The keys bound to each action. An action can have any number of keys, and has none once they've all been cleared.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct KeyBindings(BTreeMap<InputAction, Vec<KeyBinding>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(
+            InputAction::ALL
+                .iter()
+                .map(|action| (*action, action.default_bindings()))
+                .collect(),
+        )
+    }
+}
+
+impl KeyBindings {
+    pub fn get(&self, action: InputAction) -> &[KeyBinding] {
+        self.0.get(&action).map_or(&[], |bindings| &bindings[..])
+    }
+
+    /// Adds `binding` to `action`, taking it away from any other action it was bound to.
+    pub fn bind(&mut self, action: InputAction, binding: KeyBinding) {
+        for bindings in self.0.values_mut() {
+            bindings.retain(|other| *other != binding);
+        }
+        self.0.entry(action).or_default().push(binding);
+    }
+
+    pub fn clear(&mut self, action: InputAction) {
+        self.0.insert(action, Vec::new());
+    }
+
+    /// Gives the actions that were added since the bindings were saved their default keys.
+    pub fn add_missing_defaults(&mut self) {
+        for action in InputAction::ALL {
+            self.0
+                .entry(action)
+                .or_insert_with(|| action.default_bindings());
+        }
+    }
+
+    /// Every key bound to `action`, like "W / Up".
+    pub fn label(&self, action: InputAction) -> String {
+        let bindings = self.get(action);
+        if bindings.is_empty() {
+            return String::from("None");
+        }
+        bindings
+            .iter()
+            .map(KeyBinding::label)
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+/// Set while the player is typing into a text field, so that the keys they type don't also do what they're bound to.
+#[derive(Resource, Default)]
+pub struct TypingText(pub bool);
+
+pub struct InputActionPlugin;
+
+impl Plugin for InputActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonInput<InputAction>>()
+            .init_resource::<TypingText>()
+            .add_systems(PreUpdate, update_input_actions.after(InputSystem));
+    }
+}
+
+/// Presses the actions whose keys were just pressed, and lets go of the ones none of whose keys are held anymore.
+fn update_input_actions(
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    typing: Res<TypingText>,
+    mut actions: ResMut<ButtonInput<InputAction>>,
+) {
+    actions.clear();
+    if typing.0 {
+        actions.release_all();
+        return;
+    }
+    for action in InputAction::ALL {
+        let bindings = settings.key_bindings.get(action);
+        if bindings.iter().any(|binding| binding.just_pressed(&keys)) {
+            actions.press(action);
+        } else if !bindings.iter().any(|binding| binding.pressed(&keys)) {
+            actions.release(action);
+        }
+    }
+}
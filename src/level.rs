@@ -1,27 +1,40 @@
+pub mod color_glyphs;
 pub mod cursor;
-pub mod direction;
+pub mod debugger;
+pub mod ghost;
+pub mod hints;
+pub mod history;
 pub mod loader;
 pub mod persistence;
+pub mod replays;
+pub mod scoring;
 pub mod tiles;
-pub mod trains;
 pub mod yard;
 
 use bevy::{audio::Volume, prelude::*};
-use persistence::{GameLevelProgress, LevelProgress};
-use trains::TrainColor;
+use persistence::GameLevelProgress;
+use trainyard_core::{tiles::tile::TileEvent, trains::TrainColor, yard::TileEventWithLocation};
 
 use crate::{
-    ui::{level::speed_slider::TrainSpeed, level_picker::StartLevelEvent},
+    input::InputAction,
+    level_editor::player_can_draw_track,
+    tutorial::player_can_touch_yard,
+    ui::{
+        level::{
+            share_dialog::ShareDialogState, solutions_dialog::SolutionsDialogState,
+            speed_slider::TrainSpeed,
+        },
+        level_picker::StartLevelEvent,
+        UIState,
+    },
     TILE_SIZE_PX,
 };
+use debugger::{DebuggerState, TrainDebugger};
 use loader::StockLevelInfos;
+use replays::ReplayState;
 use std::time::Duration;
-use tiles::{
-    tile::TileEvent,
-    tile_animations::{FloatingFadingAnimationComponent, SrinkToNoneAnimationComponent},
-    YardComponent,
-};
-use yard::{TileEventWithLocation, Yard, YardEditedState, YardMidTickEvent, YardTickedEvent};
+use tiles::{tile_animations::FloatingFadingAnimationComponent, YardComponent};
+use yard::{Yard, YardEditedState, YardMidTickEvent, YardTickedEvent};
 
 #[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LevelState {
@@ -52,12 +65,49 @@ impl ComputedStates for LevelStateIsRunning {
 #[derive(Default, Resource)]
 pub struct CurrentLevelName(pub Option<String>);
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct YardTickTimer {
     timer: Timer,
     half_timer: Timer,
 }
 
+/// A timer that's just about to finish, so that the trains move as soon as they're started.
+impl Default for YardTickTimer {
+    fn default() -> Self {
+        let mut timer: Timer = Timer::new(Duration::from_secs(1), TimerMode::Repeating);
+        let half_timer = Timer::new(Duration::from_millis(500), TimerMode::Once);
+        timer.tick(Duration::from_micros(999999)); // make the timer just about to expire
+        YardTickTimer { timer, half_timer }
+    }
+}
+
+impl YardTickTimer {
+    /// Puts the timer back to the start of a tick that has just been stepped.
+    pub fn restart_tick(&mut self) {
+        self.timer.reset();
+        self.half_timer.reset();
+    }
+
+    /// How far the timer has to go for the trains to reach the next middle or end of a tick.
+    pub fn until_next_half_tick(&self) -> Duration {
+        if self.half_timer.finished() {
+            self.timer.remaining()
+        } else {
+            self.half_timer.remaining().min(self.timer.remaining())
+        }
+    }
+
+    /// Whether the trains reached the end of a tick on the last tick of the timer.
+    pub fn just_ticked(&self) -> bool {
+        self.timer.just_finished()
+    }
+
+    /// Whether the trains reached the middle of a tick on the last tick of the timer.
+    pub fn just_reached_mid_tick(&self) -> bool {
+        self.half_timer.just_finished()
+    }
+}
+
 #[derive(Event, Default)]
 pub struct WinLevelEvent;
 
@@ -68,11 +118,13 @@ pub struct LevelEditingSet;
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct LevelRunningSet;
 
+/// An event that a tile produced during the last tick, waiting to be handled at the end of the tick.
 #[derive(Component)]
-pub struct EndTickEvent;
+pub struct EndTickEvent(pub TileEventWithLocation);
 
+/// An event that a tile produced during the current tick, waiting to be handled halfway through the tick.
 #[derive(Component)]
-pub struct MidTickEvent;
+pub struct MidTickEvent(pub TileEventWithLocation);
 
 #[derive(Component)]
 pub struct StartTickEvent;
@@ -82,17 +134,29 @@ pub struct LevelPlugin;
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
+            color_glyphs::ColorGlyphPlugin,
             cursor::CursorPlugin,
+            debugger::DebuggerPlugin,
+            ghost::GhostPlugin,
+            hints::HintPlugin,
+            history::HistoryPlugin,
             tiles::TilePlugin,
             persistence::PersistencePlugin,
+            replays::ReplayPlugin,
+            scoring::ScoringPlugin,
         ))
         .add_event::<WinLevelEvent>()
-        .add_event::<TileEventWithLocation>()
         .configure_sets(
             Update,
             (
                 LevelSet.run_if(not(in_state(LevelState::None))),
-                LevelEditingSet.run_if(in_state(LevelState::Editing)),
+                LevelEditingSet.run_if(
+                    in_state(LevelState::Editing)
+                        .and(in_state(ShareDialogState::Closed))
+                        .and(in_state(SolutionsDialogState::Closed))
+                        .and(player_can_touch_yard)
+                        .and(player_can_draw_track),
+                ),
                 LevelRunningSet.run_if(in_state(LevelStateIsRunning::Running)),
             ),
         )
@@ -106,9 +170,15 @@ impl Plugin for LevelPlugin {
         .add_systems(
             Update,
             (
-                update_level_state_from_keypress,
+                update_level_state_from_keypress.run_if(player_can_touch_yard),
                 tick_yard_tick_timer.in_set(LevelRunningSet),
-                win_event_handler.run_if(on_event::<WinLevelEvent>),
+                // tutorials and the level editor run trains without there being a level to win,
+                // and a replay that's being watched was already won when it was recorded
+                win_event_handler.run_if(
+                    on_event::<WinLevelEvent>
+                        .and(in_state(UIState::Level))
+                        .and(in_state(ReplayState::Off)),
+                ),
             )
                 .in_set(LevelSet),
         )
@@ -123,11 +193,11 @@ impl Plugin for LevelPlugin {
 }
 
 pub fn update_level_state_from_keypress(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<InputAction>>,
     state: Res<State<LevelState>>,
     mut next_state: ResMut<NextState<LevelState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
+    if actions.just_pressed(InputAction::RunToggle) {
         toggle_level_state(&state, &mut next_state);
     }
 }
@@ -179,10 +249,7 @@ pub fn spawn_timer(mut commands: Commands, timer_query: Query<Entity, With<YardT
         commands.entity(entity).despawn();
     }
 
-    let mut timer: Timer = Timer::new(Duration::from_secs(1), TimerMode::Repeating);
-    let half_timer = Timer::new(Duration::from_millis(500), TimerMode::Once);
-    timer.tick(Duration::from_micros(999999)); // make the timer just about to expire
-    commands.spawn(YardTickTimer { timer, half_timer });
+    commands.spawn(YardTickTimer::default());
 }
 
 pub fn despawn_timer(
@@ -203,6 +270,7 @@ pub fn handle_tile_event(
     has_crashed: &mut bool,
 ) {
     info!("handling event {:?}", event);
+    yard.animate_event(event, commands);
     match event.event {
         TileEvent::SinkReceivedTrain(train_color) => {
             play_color_sound(commands, asset_server, train_color);
@@ -235,17 +303,23 @@ pub fn handle_tile_event(
                 train_color,
             );
         }
-        TileEvent::ShrinkAwayInnerEntity(entity) => {
-            commands
-                .entity(entity)
-                .insert(SrinkToNoneAnimationComponent(1.0));
-        }
+        TileEvent::SourceEmittedTrain(_) => {}
         TileEvent::SwitchActivePassive => {
-            yard.switch_active_passive(event.row, event.col);
+            // the simulation switches the tracks itself at the start of the next step
             commands.spawn(AudioPlayer::<AudioSource>(
                 asset_server.load("audio/switch_track.ogg"),
             ));
         }
+        TileEvent::PaintTrain(_) => {
+            commands.spawn(AudioPlayer::<AudioSource>(
+                asset_server.load("audio/painter.ogg"),
+            ));
+        }
+        TileEvent::SplitTrain(_) => {
+            commands.spawn(AudioPlayer::<AudioSource>(
+                asset_server.load("audio/splitter.ogg"),
+            ));
+        }
     };
 }
 
@@ -353,28 +427,35 @@ pub fn tick_yard_tick_timer(
     mut event_yard_mid_tick: EventWriter<YardMidTickEvent>,
     mut win_event: EventWriter<WinLevelEvent>,
 
-    mid_tick_events_q: Query<(Entity, &TileEventWithLocation), With<MidTickEvent>>,
-    end_tick_events_q: Query<(Entity, &TileEventWithLocation), With<EndTickEvent>>,
+    mid_tick_events_q: Query<(Entity, &MidTickEvent)>,
+    end_tick_events_q: Query<(Entity, &EndTickEvent)>,
 
     train_speed: Res<TrainSpeed>,
+    debugger_state: Res<State<DebuggerState>>,
+    mut debugger: ResMut<TrainDebugger>,
 ) {
     let yard_tick_timer = q.single_mut().into_inner();
 
-    let delta_ns = time.delta().as_nanos();
-    let delta_ns_for_tick = (delta_ns as f32 * 10.0 * train_speed.0) as u64;
-    yard_tick_timer
-        .timer
-        .tick(Duration::from_nanos(delta_ns_for_tick));
-    yard_tick_timer
-        .half_timer
-        .tick(Duration::from_nanos(delta_ns_for_tick));
+    // a paused debugger only moves the trains when it's stepped, and then exactly to the next half-tick
+    let delta_for_tick = if *debugger_state.get() == DebuggerState::Paused {
+        if debugger.take_queued_half_tick() {
+            yard_tick_timer.until_next_half_tick()
+        } else {
+            Duration::ZERO
+        }
+    } else {
+        let delta_ns = time.delta().as_nanos();
+        Duration::from_nanos((delta_ns as f32 * 10.0 * train_speed.0) as u64)
+    };
+    yard_tick_timer.timer.tick(delta_for_tick);
+    yard_tick_timer.half_timer.tick(delta_for_tick);
 
     let mut has_crashed = false;
 
     if yard_tick_timer.timer.just_finished() {
         let yard = yard_query.single_mut().into_inner();
 
-        for (entity, ev) in end_tick_events_q.iter() {
+        for (entity, EndTickEvent(ev)) in end_tick_events_q.iter() {
             commands.entity(entity).despawn();
             handle_tile_event(
                 &mut commands,
@@ -386,7 +467,7 @@ pub fn tick_yard_tick_timer(
             );
         }
 
-        let process_tick_results = yard.tick();
+        let process_tick_results = yard.sim.step();
 
         for e in process_tick_results.start_tick_events {
             handle_tile_event(
@@ -400,21 +481,22 @@ pub fn tick_yard_tick_timer(
         }
 
         for e in process_tick_results.mid_tick_events {
-            commands.spawn((MidTickEvent, e));
+            commands.spawn(MidTickEvent(e));
         }
         for e in process_tick_results.end_tick_events {
-            commands.spawn((EndTickEvent, e));
+            commands.spawn(EndTickEvent(e));
         }
 
         event_yard_ticked.send_default();
 
-        if !has_crashed && *level_state.get() == LevelState::RunningNotCrashed && yard.has_won() {
+        if !has_crashed && *level_state.get() == LevelState::RunningNotCrashed && yard.sim.has_won()
+        {
             win_event.send_default();
         }
         yard_tick_timer.half_timer.reset();
     } else if yard_tick_timer.half_timer.just_finished() {
         let yard = yard_query.single_mut().into_inner();
-        for (entity, ev) in mid_tick_events_q.iter() {
+        for (entity, MidTickEvent(ev)) in mid_tick_events_q.iter() {
             commands.entity(entity).despawn();
             handle_tile_event(
                 &mut commands,
@@ -453,16 +535,9 @@ pub fn win_event_handler(
 
         // persist current level progress
         if let Some(name) = curr_lvl_name.0.as_ref() {
-            let name = name.to_string();
-            let has_won = true;
-
-            let drawn_tracks = yard.0.get_progress();
-
-            let progress = LevelProgress {
-                has_won,
-                drawn_tracks,
-            };
-            persistence.0.insert(name, progress);
+            let progress = persistence.0.entry(name.to_string()).or_default();
+            progress.has_won = true;
+            progress.drawn_tracks = yard.0.sim.get_progress();
         }
     }
 }
@@ -489,14 +564,11 @@ pub fn level_start_event_handler(
         let mut found_level = false;
         for level in levels.0.iter() {
             if level.name == start_event.level_name {
-                let yard_entity = level.to_yard(
-                    &mut commands,
-                    &asset_server,
-                    persistence.0.get(&start_event.level_name),
-                );
-
-                let yard_bundle = (YardComponent, Name::new("The Yard"));
-                commands.entity(yard_entity).insert(yard_bundle);
+                let mut sim = level.to_yard();
+                if let Some(progress) = persistence.0.get(&start_event.level_name) {
+                    sim.apply_progress(&progress.drawn_tracks);
+                }
+                spawn_yard(&mut commands, &asset_server, sim);
                 found_level = true;
                 break;
             }
@@ -511,3 +583,37 @@ pub fn level_start_event_handler(
         level_name.0 = Some(start_event.level_name.clone());
     }
 }
+
+/// Spawns the entities for a yard that plays `sim`, returning the yard's entity.
+pub fn spawn_yard(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    sim: trainyard_core::yard::Yard,
+) -> Entity {
+    let yard = Yard::new(sim, commands, asset_server);
+    let yard_entity = commands.entity(yard.base_entity).insert(yard).id();
+
+    let yard_bundle = (YardComponent, Name::new("The Yard"));
+    commands.entity(yard_entity).insert(yard_bundle);
+    yard_entity
+}
+
+/// Swaps the yard at `old_yard_entity` for a new one that plays `sim`, returning the new yard's entity.
+/// The cursor is a child of the yard, so it's moved over to the new one instead of going away with the old one.
+pub fn replace_yard(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    old_yard_entity: Entity,
+    cursor: Option<Entity>,
+    sim: trainyard_core::yard::Yard,
+) -> Entity {
+    if let Some(cursor) = cursor {
+        commands.entity(cursor).remove_parent();
+    }
+    commands.entity(old_yard_entity).despawn_recursive();
+    let yard_entity = spawn_yard(commands, asset_server, sim);
+    if let Some(cursor) = cursor {
+        commands.entity(yard_entity).add_children(&[cursor]);
+    }
+    yard_entity
+}
@@ -0,0 +1,144 @@
+//! Letters drawn over trains and the trains waiting in stations, so that train colors can be told apart without
+//! relying on the colors themselves. Along with the palette trains are drawn in, these make up the settings for
+//! players with color blindness.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use trainyard_core::trains::{Palette, TrainColor};
+
+pub const GLYPH_FONT_SIZE: f32 = 30.0;
+/// Glyphs on colors brighter than this are drawn in black, and in white on the rest.
+pub const DARK_GLYPH_LUMINANCE: f32 = 0.3;
+
+#[derive(Resource, Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorblindSettings {
+    /// Whether each color's glyph is drawn over it.
+    pub glyphs: bool,
+    pub palette: Palette,
+}
+
+/// Marks a sprite that's tinted with a train's color, to have that color's glyph drawn over it while glyphs are on.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub struct ColorGlyph(pub TrainColor);
+
+/// The text drawing a `ColorGlyph`, as a child of the sprite it's drawn over.
+#[derive(Component)]
+pub struct ColorGlyphText;
+
+pub struct ColorGlyphPlugin;
+
+impl Plugin for ColorGlyphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ColorblindSettings>().add_systems(
+            Update,
+            (
+                apply_palette.run_if(resource_changed::<ColorblindSettings>),
+                despawn_color_glyphs.run_if(
+                    resource_changed::<ColorblindSettings>
+                        .and(|settings: Res<ColorblindSettings>| !settings.glyphs),
+                ),
+                (update_color_glyphs, keep_color_glyphs_upright)
+                    .chain()
+                    .run_if(|settings: Res<ColorblindSettings>| settings.glyphs),
+            ),
+        );
+    }
+}
+
+/// The letter drawn over `color`. Brown gets the last letter of its name, since blue already has the B.
+pub fn glyph(color: TrainColor) -> &'static str {
+    match color {
+        TrainColor::Brown => "N",
+        TrainColor::Red => "R",
+        TrainColor::Blue => "B",
+        TrainColor::Yellow => "Y",
+        TrainColor::Purple => "P",
+        TrainColor::Green => "G",
+        TrainColor::Orange => "O",
+    }
+}
+
+/// The color that the glyph for `color` stands out best in, on top of `color` itself.
+fn glyph_color(color: TrainColor) -> Color {
+    if Color::from(color).luminance() > DARK_GLYPH_LUMINANCE {
+        Color::BLACK
+    } else {
+        Color::WHITE
+    }
+}
+
+fn apply_palette(settings: Res<ColorblindSettings>) {
+    settings.palette.set_active();
+}
+
+/// Spawns the glyphs for sprites that don't have one yet, and changes the ones whose train changed color.
+fn update_color_glyphs(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<ColorblindSettings>,
+    glyph_query: Query<(Entity, Ref<ColorGlyph>, Option<&Children>)>,
+    mut text_query: Query<(&mut Text2d, &mut TextColor), With<ColorGlyphText>>,
+) {
+    for (entity, color_glyph, children) in glyph_query.iter() {
+        // every sprite needs its glyph when glyphs have just been turned on
+        if !color_glyph.is_changed() && !settings.is_changed() {
+            continue;
+        }
+        let color = color_glyph.0;
+        let existing_text = children
+            .into_iter()
+            .flatten()
+            .find(|child| text_query.contains(**child));
+        match existing_text {
+            Some(text_entity) => {
+                let (mut text, mut text_color) = text_query.get_mut(*text_entity).unwrap();
+                if text.0 != glyph(color) {
+                    text.0 = String::from(glyph(color));
+                }
+                if text_color.0 != glyph_color(color) {
+                    text_color.0 = glyph_color(color);
+                }
+            }
+            None => {
+                let text_entity = commands
+                    .spawn((
+                        Text2d::new(glyph(color)),
+                        TextFont {
+                            font: asset_server.load("fonts/kenyan_coffee_bd.otf"),
+                            font_size: GLYPH_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(glyph_color(color)),
+                        Transform::from_xyz(0.0, 0.0, 0.1),
+                        ColorGlyphText,
+                        Name::new("color glyph"),
+                    ))
+                    .id();
+                commands.entity(entity).add_children(&[text_entity]);
+            }
+        }
+    }
+}
+
+/// Turns glyphs back the other way from the trains they're on, so that they can always be read.
+fn keep_color_glyphs_upright(
+    mut text_query: Query<(&Parent, &mut Transform), With<ColorGlyphText>>,
+    parent_query: Query<&Transform, Without<ColorGlyphText>>,
+) {
+    for (parent, mut transform) in text_query.iter_mut() {
+        let Ok(parent_transform) = parent_query.get(parent.get()) else {
+            continue;
+        };
+        let rotation = parent_transform.rotation.inverse();
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+    }
+}
+
+fn despawn_color_glyphs(mut commands: Commands, text_query: Query<Entity, With<ColorGlyphText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).remove_parent().despawn();
+    }
+}
@@ -2,11 +2,12 @@ use bevy::input::common_conditions::input_just_pressed;
 use bevy::prelude::*;
 use bevy::{input::common_conditions::input_pressed, window::PrimaryWindow};
 
+use trainyard_core::direction::Dir;
+
+use crate::input::InputAction;
 use crate::level::yard::Yard;
 use crate::level::{LevelEditingSet, LevelState};
-use crate::{NUM_COLS, NUM_ROWS, TILE_SIZE_PX};
-
-use super::direction::Dir;
+use crate::TILE_SIZE_PX;
 
 pub const NEUTRAL_CURSOR_COLOR: Color = Color::WHITE;
 pub const DRAWING_CURSOR_COLOR: Color = Color::srgb(0.0, 0.0, 1.0);
@@ -30,6 +31,7 @@ impl Plugin for CursorPlugin {
                 Update,
                 (
                     (
+                        keep_cursor_in_yard,
                         draw_cursor_position,
                         move_cursor,
                         toggle_cursor_drawing,
@@ -105,18 +107,25 @@ fn spawn_cursor(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     cursor_state: Res<State<CursorState>>,
-    yard_entity_query: Query<Entity, With<Yard>>,
+    yard_entity_query: Query<(Entity, &Yard)>,
 ) {
-    let yard_entity = yard_entity_query.single();
+    let (yard_entity, yard) = yard_entity_query.single();
 
     let cursor_color = cursor_state.get().get_color();
 
+    // the cursor starts in the middle of the yard
+    let r = (yard.sim.height() / 2) as u8;
+    let c = (yard.sim.width() / 2) as u8;
     let cursor = commands
         .spawn((
-            TilePosition { r: 3, c: 3 },
+            TilePosition { r, c },
             CursorComponent,
             OldCursorMovementDir { dir: None },
-            Transform::from_xyz(TILE_SIZE_PX * 3.5, TILE_SIZE_PX * 3.5, 1.0),
+            Transform::from_xyz(
+                (c as f32 + 0.5) * TILE_SIZE_PX,
+                (r as f32 + 0.5) * TILE_SIZE_PX,
+                1.0,
+            ),
             Sprite {
                 image: asset_server.load("sprites/Cursor.png"),
                 color: Color::from(cursor_color),
@@ -138,6 +147,23 @@ fn despawn_cursor(
     next_cursor_state.set(CursorState::NotDrawing);
 }
 
+/// Moves the cursor back inside the yard if the yard has shrunk out from under it, like in the level editor.
+fn keep_cursor_in_yard(
+    yard_query: Query<&Yard, Changed<Yard>>,
+    mut cursor_query: Query<&mut TilePosition, With<CursorComponent>>,
+) {
+    let (Ok(yard), Ok(mut position)) = (yard_query.get_single(), cursor_query.get_single_mut())
+    else {
+        return;
+    };
+    let max_r = yard.sim.height().saturating_sub(1) as u8;
+    let max_c = yard.sim.width().saturating_sub(1) as u8;
+    if position.r > max_r || position.c > max_c {
+        position.r = position.r.min(max_r);
+        position.c = position.c.min(max_c);
+    }
+}
+
 fn draw_cursor_position(
     mut query: Query<
         (&mut Transform, &TilePosition),
@@ -166,29 +192,35 @@ fn update_cursor_color(
 }
 
 fn toggle_cursor_drawing(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<InputAction>>,
     state: Res<State<CursorState>>,
     mut next_state: ResMut<NextState<CursorState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::KeyE) {
+    if actions.just_pressed(InputAction::DrawToggle) {
         next_state.set(state.get().toggle_draw())
     }
-    if keyboard_input.just_pressed(KeyCode::KeyQ) {
+    if actions.just_pressed(InputAction::EraseToggle) {
         next_state.set(state.get().toggle_erase())
     }
 }
 
 fn move_cursor(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<InputAction>>,
     mut cursor_query: Query<&mut TilePosition, With<CursorComponent>>,
     mut moved_events: EventWriter<CursorMovedEvent>,
+    yard_query: Query<&Yard>,
 ) {
+    let Ok(yard) = yard_query.get_single() else {
+        return;
+    };
+    let num_rows = yard.sim.height() as u8;
+    let num_cols = yard.sim.width() as u8;
     if let Ok(tile_pos) = cursor_query.get_single_mut() {
         let tile_pos = tile_pos.into_inner();
         let c = &mut tile_pos.c;
         let r = &mut tile_pos.r;
 
-        if keyboard_input.just_pressed(KeyCode::KeyA) {
+        if actions.just_pressed(InputAction::MoveLeft) {
             if *c > 0 {
                 moved_events.send(CursorMovedEvent {
                     dir: Dir::Left,
@@ -198,8 +230,8 @@ fn move_cursor(
                 *c -= 1;
             }
         }
-        if keyboard_input.just_pressed(KeyCode::KeyD) {
-            if *c < NUM_COLS - 1 {
+        if actions.just_pressed(InputAction::MoveRight) {
+            if *c + 1 < num_cols {
                 moved_events.send(CursorMovedEvent {
                     dir: Dir::Right,
                     old_r: *r,
@@ -208,7 +240,7 @@ fn move_cursor(
                 *c += 1;
             }
         }
-        if keyboard_input.just_pressed(KeyCode::KeyS) {
+        if actions.just_pressed(InputAction::MoveDown) {
             if *r > 0 {
                 moved_events.send(CursorMovedEvent {
                     dir: Dir::Down,
@@ -218,8 +250,8 @@ fn move_cursor(
                 *r -= 1;
             }
         }
-        if keyboard_input.just_pressed(KeyCode::KeyW) {
-            if *r < NUM_ROWS - 1 {
+        if actions.just_pressed(InputAction::MoveUp) {
+            if *r + 1 < num_rows {
                 moved_events.send(CursorMovedEvent {
                     dir: Dir::Up,
                     old_r: *r,
@@ -239,12 +271,11 @@ fn move_cursor_by_mouse(
     mut q_old_movement_dir: Query<&mut OldCursorMovementDir>,
     mut moved_events: EventWriter<CursorMovedEvent>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
-    yard_global_transform_query: Query<&GlobalTransform, With<Yard>>,
+    yard_query: Query<(&GlobalTransform, &Yard)>,
 ) {
     let window = q_windows.single();
     let (camera, camera_transform) = camera_query.single();
-    let yard_transform = yard_global_transform_query.single();
-    let yard_transform = yard_transform.translation().truncate();
+    let (yard_transform, yard) = yard_query.single();
 
     let mut position = q_position.single_mut();
     let mut old_movement_dir = q_old_movement_dir.single_mut();
@@ -256,7 +287,12 @@ fn move_cursor_by_mouse(
             .viewport_to_world_2d(camera_transform, cursor_position)
             .unwrap();
 
-        let yard_local_coords = world_coordinates_of_mouse - yard_transform;
+        // the yard is scaled down when it's too big to fit on screen
+        let yard_local_coords = yard_transform
+            .affine()
+            .inverse()
+            .transform_point3(world_coordinates_of_mouse.extend(0.0))
+            .truncate();
 
         let x = yard_local_coords.x;
         let y = yard_local_coords.y;
@@ -264,7 +300,7 @@ fn move_cursor_by_mouse(
         let c = (x / TILE_SIZE_PX) as i32;
         let r = (y / TILE_SIZE_PX) as i32;
 
-        if c < 0 || c >= NUM_COLS as i32 || r < 0 || r >= NUM_ROWS as i32 {
+        if c < 0 || c >= yard.sim.width() as i32 || r < 0 || r >= yard.sim.height() as i32 {
             return;
         }
 
@@ -325,7 +361,7 @@ fn play_cursor_sounds(
     }
 }
 
-fn add_connections_from_cursor_movement(
+pub fn add_connections_from_cursor_movement(
     mut moved_events: EventReader<CursorMovedEvent>,
     mut old_movement_dir_query: Query<&mut OldCursorMovementDir>,
     mut yard_query: Query<&mut Yard>,
@@ -343,7 +379,8 @@ fn add_connections_from_cursor_movement(
                 let r = e.old_r;
                 let c = e.old_c;
 
-                yard.tiles
+                yard.sim
+                    .tiles
                     .get_mut(r as usize)
                     .unwrap()
                     .get_mut(c as usize)
@@ -356,7 +393,7 @@ fn add_connections_from_cursor_movement(
     }
 }
 
-fn destroy_connections_under_cursor(
+pub fn destroy_connections_under_cursor(
     cursor_query: Query<&TilePosition, With<CursorComponent>>,
     mut yard_query: Query<&mut Yard>,
 ) {
@@ -364,6 +401,7 @@ fn destroy_connections_under_cursor(
     let yard = yard_query.single_mut().into_inner();
 
     let tile = yard
+        .sim
         .tiles
         .get_mut(cursor.r as usize)
         .unwrap()
@@ -0,0 +1,317 @@
+//! Running the trains one step at a time, to see why a solution doesn't work.
+//!
+//! The debugger runs the trains like the start button does, but they can be paused, moved forward by half a tick or a
+//! whole tick, and moved back through the states the yard was in at each half-tick.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use trainyard_core::{connections::TileBorderState, direction::Dir, yard as sim};
+
+use crate::{
+    input::InputAction,
+    level::{
+        tick_yard_tick_timer, yard::Yard, EndTickEvent, LevelSet, LevelState, LevelStateIsRunning,
+        MidTickEvent, YardTickTimer,
+    },
+    tutorial::player_can_touch_yard,
+    ui::level::{share_dialog::ShareDialogState, solutions_dialog::SolutionsDialogState},
+    TILE_SIZE_PX,
+};
+
+/// The most half-ticks that can be stepped back through.
+pub const MAX_DEBUGGER_SNAPSHOTS: usize = 1000;
+pub const BORDER_MARKER_SIZE_PX: f32 = 14.0;
+
+/// Whether the trains are running in the debugger, and if so whether they're paused.
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DebuggerState {
+    #[default]
+    Off,
+    Playing,
+    Paused,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebuggerEvent {
+    /// Starts the trains in the debugger, paused, or pauses and unpauses them if they're already in it.
+    PlayPause,
+    StepHalfTick,
+    StepTick,
+    StepBack,
+}
+
+/// Everything about a running yard at one half-tick, so that it can be put back the way it was.
+#[derive(Clone)]
+pub struct DebuggerSnapshot {
+    pub sim: sim::Yard,
+    timer: YardTickTimer,
+    pub mid_tick_events: Vec<sim::TileEventWithLocation>,
+    pub end_tick_events: Vec<sim::TileEventWithLocation>,
+    pub crashed: bool,
+    /// How many ticks the yard has run for.
+    pub ticks: usize,
+    /// Whether this is halfway through a tick, rather than the start of one.
+    pub halfway: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct TrainDebugger {
+    /// Half-ticks that are waiting to be stepped through while paused. The yard moves one half-tick per frame.
+    queued_half_ticks: u32,
+    /// The yard at every half-tick since the debugger started, with the latest at the back.
+    snapshots: VecDeque<DebuggerSnapshot>,
+}
+
+impl TrainDebugger {
+    /// The state the yard was left in at the last half-tick.
+    pub fn latest(&self) -> Option<&DebuggerSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Takes one of the queued half-ticks, returning whether there was one.
+    pub fn take_queued_half_tick(&mut self) -> bool {
+        if self.queued_half_ticks == 0 {
+            return false;
+        }
+        self.queued_half_ticks -= 1;
+        true
+    }
+}
+
+/// A marker over the edge of a tile, showing a train that's waiting to come into the tile from that side.
+#[derive(Component)]
+pub struct BorderMarker;
+
+pub struct DebuggerPlugin;
+
+impl Plugin for DebuggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<DebuggerState>()
+            .add_event::<DebuggerEvent>()
+            .init_resource::<TrainDebugger>()
+            .add_systems(OnExit(LevelStateIsRunning::Running), stop_debugging)
+            .add_systems(OnEnter(DebuggerState::Off), despawn_border_markers)
+            .add_systems(
+                Update,
+                (
+                    debugger_keys.run_if(
+                        player_can_touch_yard
+                            .and(in_state(ShareDialogState::Closed))
+                            .and(in_state(SolutionsDialogState::Closed)),
+                    ),
+                    (handle_debugger_events, step_back)
+                        .run_if(on_event::<DebuggerEvent>)
+                        .before(tick_yard_tick_timer),
+                    (record_snapshot, update_border_markers)
+                        .chain()
+                        .after(tick_yard_tick_timer)
+                        .run_if(
+                            in_state(LevelStateIsRunning::Running)
+                                .and(not(in_state(DebuggerState::Off))),
+                        ),
+                )
+                    .in_set(LevelSet),
+            );
+    }
+}
+
+fn debugger_keys(
+    actions: Res<ButtonInput<InputAction>>,
+    mut debugger_ev_writer: EventWriter<DebuggerEvent>,
+) {
+    let action_events = [
+        (InputAction::DebuggerPlayPause, DebuggerEvent::PlayPause),
+        (InputAction::DebuggerStep, DebuggerEvent::StepTick),
+        (InputAction::DebuggerHalfStep, DebuggerEvent::StepHalfTick),
+        (InputAction::DebuggerStepBack, DebuggerEvent::StepBack),
+    ];
+    for (action, event) in action_events {
+        if actions.just_pressed(action) {
+            debugger_ev_writer.send(event);
+        }
+    }
+}
+
+fn handle_debugger_events(
+    mut events: EventReader<DebuggerEvent>,
+    level_state: Res<State<LevelState>>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+    debugger_state: Res<State<DebuggerState>>,
+    mut next_debugger_state: ResMut<NextState<DebuggerState>>,
+    mut debugger: ResMut<TrainDebugger>,
+) {
+    for event in events.read() {
+        match (event, debugger_state.get()) {
+            (DebuggerEvent::PlayPause, DebuggerState::Off) => {
+                if *level_state.get() == LevelState::Editing {
+                    // the first half-tick puts the trains in their stations, like starting them normally does
+                    debugger.queued_half_ticks = 1;
+                    next_level_state.set(LevelState::RunningNotCrashed);
+                    next_debugger_state.set(DebuggerState::Paused);
+                }
+            }
+            (DebuggerEvent::PlayPause, DebuggerState::Playing) => {
+                next_debugger_state.set(DebuggerState::Paused);
+            }
+            (DebuggerEvent::PlayPause, DebuggerState::Paused) => {
+                debugger.queued_half_ticks = 0;
+                next_debugger_state.set(DebuggerState::Playing);
+            }
+            // stepping while the trains are playing just stops them where they are
+            (_, DebuggerState::Playing) => next_debugger_state.set(DebuggerState::Paused),
+            (DebuggerEvent::StepHalfTick, DebuggerState::Paused) => debugger.queued_half_ticks += 1,
+            (DebuggerEvent::StepTick, DebuggerState::Paused) => debugger.queued_half_ticks += 2,
+            (DebuggerEvent::StepBack, DebuggerState::Paused) | (_, DebuggerState::Off) => {}
+        }
+    }
+}
+
+/// Puts the yard back the way it was at the half-tick before the one it's at now.
+fn step_back(
+    mut commands: Commands,
+    mut events: EventReader<DebuggerEvent>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+    mut debugger: ResMut<TrainDebugger>,
+    mut running_query: Query<(&mut Yard, &mut YardTickTimer)>,
+    mid_tick_events_query: Query<Entity, With<MidTickEvent>>,
+    end_tick_events_query: Query<Entity, With<EndTickEvent>>,
+) {
+    let steps = events
+        .read()
+        .filter(|event| **event == DebuggerEvent::StepBack)
+        .count();
+    let Ok((mut yard, mut timer)) = running_query.get_single_mut() else {
+        return;
+    };
+    // the latest snapshot is where the yard is now, so the one before it is where it goes back to
+    let steps = steps.min(debugger.snapshots.len().saturating_sub(1));
+    if steps == 0 {
+        return;
+    }
+    let remaining = debugger.snapshots.len() - steps;
+    debugger.snapshots.truncate(remaining);
+    debugger.queued_half_ticks = 0;
+    let snapshot = debugger.snapshots.back().unwrap().clone();
+
+    yard.sim = snapshot.sim;
+    // the sources and sinks only take trains away as they're handled, so they're drawn again from the restored yard
+    yard.reset_tile_inner_entities_and_train_entities(&mut commands);
+    *timer = snapshot.timer;
+    for entity in mid_tick_events_query
+        .iter()
+        .chain(end_tick_events_query.iter())
+    {
+        commands.entity(entity).despawn();
+    }
+    for event in snapshot.mid_tick_events {
+        commands.spawn(MidTickEvent(event));
+    }
+    for event in snapshot.end_tick_events {
+        commands.spawn(EndTickEvent(event));
+    }
+    next_level_state.set(if snapshot.crashed {
+        LevelState::RunningCrashed
+    } else {
+        LevelState::RunningNotCrashed
+    });
+}
+
+/// Keeps a copy of the yard every time it reaches a half-tick.
+fn record_snapshot(
+    mut debugger: ResMut<TrainDebugger>,
+    running_query: Query<(&Yard, &YardTickTimer)>,
+    mid_tick_events_query: Query<&MidTickEvent>,
+    end_tick_events_query: Query<&EndTickEvent>,
+    level_state: Res<State<LevelState>>,
+    next_level_state: Res<NextState<LevelState>>,
+) {
+    let Ok((yard, timer)) = running_query.get_single() else {
+        return;
+    };
+    let ticked = timer.just_ticked();
+    if !ticked && !timer.just_reached_mid_tick() {
+        return;
+    }
+
+    let previous_ticks = debugger.latest().map_or(0, |snapshot| snapshot.ticks);
+    // a crash only shows up in the level's state on the next frame
+    let crashed = *level_state.get() == LevelState::RunningCrashed
+        || matches!(
+            *next_level_state,
+            NextState::Pending(LevelState::RunningCrashed)
+        );
+    debugger.snapshots.push_back(DebuggerSnapshot {
+        sim: yard.sim.clone(),
+        timer: timer.clone(),
+        mid_tick_events: mid_tick_events_query.iter().map(|e| e.0.clone()).collect(),
+        end_tick_events: end_tick_events_query.iter().map(|e| e.0.clone()).collect(),
+        crashed,
+        ticks: previous_ticks + ticked as usize,
+        halfway: !ticked,
+    });
+    if debugger.snapshots.len() > MAX_DEBUGGER_SNAPSHOTS {
+        debugger.snapshots.pop_front();
+    }
+}
+
+/// Shows the trains that are waiting on the edges of tiles, which the yard doesn't draw on its own.
+fn update_border_markers(
+    mut commands: Commands,
+    yard_query: Query<&Yard>,
+    marker_query: Query<Entity, With<BorderMarker>>,
+    mut shown_borders: Local<Vec<Vec<TileBorderState>>>,
+) {
+    let Ok(yard) = yard_query.get_single() else {
+        return;
+    };
+    if *shown_borders == yard.sim.borders && !marker_query.is_empty() {
+        return;
+    }
+    *shown_borders = yard.sim.borders.clone();
+
+    for entity in marker_query.iter() {
+        commands.entity(entity).remove_parent().despawn();
+    }
+    for (row, borders_row) in yard.sim.borders.iter().enumerate() {
+        for (col, border) in borders_row.iter().enumerate() {
+            for dir in Dir::all_dirs() {
+                let Some(color) = border.get_train(dir) else {
+                    continue;
+                };
+                // markers sit just inside the tile that the train is about to come into
+                let (dx, dy) = dir.to_local_coords_of_edge();
+                let inset = BORDER_MARKER_SIZE_PX / TILE_SIZE_PX;
+                let x = (col as f32 + dx.clamp(inset, 1.0 - inset)) * TILE_SIZE_PX;
+                let y = (row as f32 + dy.clamp(inset, 1.0 - inset)) * TILE_SIZE_PX;
+                let marker = commands
+                    .spawn((
+                        Transform::from_xyz(x, y, 3.0),
+                        Sprite {
+                            color: Color::from(color),
+                            custom_size: Some(Vec2::splat(BORDER_MARKER_SIZE_PX)),
+                            ..default()
+                        },
+                        BorderMarker,
+                        Name::new("border marker"),
+                    ))
+                    .id();
+                commands.entity(yard.base_entity).add_children(&[marker]);
+            }
+        }
+    }
+}
+
+fn stop_debugging(
+    mut debugger: ResMut<TrainDebugger>,
+    mut next_debugger_state: ResMut<NextState<DebuggerState>>,
+) {
+    *debugger = TrainDebugger::default();
+    next_debugger_state.set(DebuggerState::Off);
+}
+
+fn despawn_border_markers(mut commands: Commands, marker_query: Query<Entity, With<BorderMarker>>) {
+    for entity in marker_query.iter() {
+        commands.entity(entity).remove_parent().despawn();
+    }
+}
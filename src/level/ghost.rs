@@ -0,0 +1,142 @@
+//! See-through trains from the level's saved replay, running over the yard while the player draws, so that a new
+//! attempt can be compared with the solution that won before.
+
+use bevy::prelude::*;
+use trainyard_core::yard::TrainActivityWithLocation;
+
+use crate::{
+    level::{
+        replays::{ReplayEvent, ReplayPlayer},
+        yard::{train_transform, Yard},
+        LevelSet, LevelState,
+    },
+    ui::level::speed_slider::TrainSpeed,
+};
+
+pub const GHOST_TRAIN_ALPHA: f32 = 0.35;
+/// How many ticks the ghost waits once its trains are all delivered, before it starts over.
+pub const GHOST_REST_TICKS: usize = 2;
+
+#[derive(Resource, Default)]
+pub struct Ghost {
+    pub shown: bool,
+    /// Where the replay's trains are on each tick.
+    frames: Vec<Vec<TrainActivityWithLocation>>,
+    /// How far into the replay the ghost is, in ticks.
+    elapsed: f32,
+}
+
+#[derive(Component)]
+pub struct GhostTrain;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ghost>()
+            .add_systems(OnExit(LevelState::Editing), despawn_ghost_trains)
+            .add_systems(
+                Update,
+                (
+                    toggle_ghost.run_if(on_event::<ReplayEvent>),
+                    update_ghost_frames.run_if(resource_changed::<ReplayPlayer>),
+                    animate_ghost.run_if(|ghost: Res<Ghost>| ghost.shown),
+                )
+                    .chain()
+                    .run_if(in_state(LevelState::Editing))
+                    .in_set(LevelSet),
+            );
+    }
+}
+
+fn toggle_ghost(
+    mut commands: Commands,
+    mut events: EventReader<ReplayEvent>,
+    mut ghost: ResMut<Ghost>,
+    ghost_train_query: Query<Entity, With<GhostTrain>>,
+) {
+    for event in events.read() {
+        if *event == ReplayEvent::ToggleGhost {
+            ghost.shown = !ghost.shown;
+            ghost.elapsed = 0.0;
+        }
+    }
+    if !ghost.shown {
+        for entity in ghost_train_query.iter() {
+            commands.entity(entity).remove_parent().despawn();
+        }
+    }
+}
+
+fn update_ghost_frames(player: Res<ReplayPlayer>, mut ghost: ResMut<Ghost>) {
+    ghost.frames = player
+        .replay
+        .as_ref()
+        .map(|replay| replay.train_activity())
+        .unwrap_or_default();
+    ghost.elapsed = 0.0;
+}
+
+/// Moves the ghost's trains along at the same speed as the player's trains would go.
+fn animate_ghost(
+    mut commands: Commands,
+    mut ghost: ResMut<Ghost>,
+    time: Res<Time>,
+    train_speed: Res<TrainSpeed>,
+    asset_server: Res<AssetServer>,
+    yard_query: Query<&Yard>,
+    mut ghost_train_query: Query<(Entity, &mut Transform, &mut Sprite), With<GhostTrain>>,
+) {
+    let Ok(yard) = yard_query.get_single() else {
+        return;
+    };
+    let cycle_length = (ghost.frames.len() + GHOST_REST_TICKS) as f32;
+    ghost.elapsed = (ghost.elapsed + time.delta_secs() * 10.0 * train_speed.0) % cycle_length;
+    let trains = ghost
+        .frames
+        .get(ghost.elapsed as usize)
+        .map_or(&[][..], |trains| &trains[..]);
+    let time_within_tick = ghost.elapsed.fract();
+
+    if ghost_train_query.iter().count() != trains.len() {
+        for (entity, _, _) in ghost_train_query.iter() {
+            commands.entity(entity).remove_parent().despawn();
+        }
+        for _ in trains {
+            let ghost_train = commands
+                .spawn((
+                    Transform::default(),
+                    Visibility::Hidden,
+                    Sprite::from_image(asset_server.load("sprites/Train.png")),
+                    GhostTrain,
+                    Name::new("ghost train"),
+                ))
+                .id();
+            commands
+                .entity(yard.base_entity)
+                .add_children(&[ghost_train]);
+        }
+        // the new trains are placed on the next frame, once they've been spawned
+        return;
+    }
+
+    for ((entity, mut transform, mut sprite), activity) in ghost_train_query.iter_mut().zip(trains)
+    {
+        let Some((train_transform, color)) = train_transform(activity, time_within_tick) else {
+            commands.entity(entity).insert(Visibility::Hidden);
+            continue;
+        };
+        *transform = train_transform;
+        sprite.color = Color::from(color).with_alpha(GHOST_TRAIN_ALPHA);
+        commands.entity(entity).insert(Visibility::Inherited);
+    }
+}
+
+fn despawn_ghost_trains(
+    mut commands: Commands,
+    ghost_train_query: Query<Entity, With<GhostTrain>>,
+) {
+    for entity in ghost_train_query.iter() {
+        commands.entity(entity).remove_parent().despawn();
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+use trainyard_core::{
+    connections::TileConnections,
+    solver::{next_hint, solve, SolverOptions},
+};
+
+use crate::{
+    level::{
+        history::EditHistory, loader::StockLevelInfos, persistence::GameLevelProgress, yard::Yard,
+        CurrentLevelName, LevelEditingSet, LevelSet,
+    },
+    ui::buttons::TrainyardButton,
+    TILE_SIZE_PX,
+};
+
+pub const HINT_BUTTON_TEXT: &str = "Hint";
+pub const HINT_BUTTON_SOLVING_TEXT: &str = "Finding a hint...";
+pub const HINT_BUTTON_NO_SOLUTION_TEXT: &str = "No hint found";
+pub const HINT_SOLVER_TIMEOUT: Duration = Duration::from_secs(20);
+pub const HINT_HIGHLIGHT_SECS: f32 = 2.0;
+pub const HINT_HIGHLIGHT_COLOR: Color = Color::srgba(1.0, 0.9, 0.2, 0.6);
+
+#[derive(Event, Default)]
+pub struct HintRequestedEvent;
+
+/// Where hints for the current level come from.
+/// Levels that don't ship with a solution have one worked out by the solver the first time a hint is asked for.
+#[derive(Resource, Default)]
+pub struct LevelHintState {
+    level_name: Option<String>,
+    solution: Option<Vec<u8>>,
+    solver_task: Option<Task<Option<Vec<u8>>>>,
+    /// Set when the player has asked for a hint that hasn't been shown yet, because the solver is still running.
+    hint_wanted: bool,
+}
+
+/// A highlight over a tile that a hint just drew tracks on, which fades away.
+#[derive(Component)]
+pub struct HintHighlight {
+    ttl: f32,
+}
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HintRequestedEvent>()
+            .init_resource::<LevelHintState>()
+            .add_systems(
+                Update,
+                (
+                    start_hint.run_if(on_event::<HintRequestedEvent>),
+                    reveal_hint.in_set(LevelEditingSet),
+                )
+                    .chain()
+                    .in_set(LevelSet),
+            )
+            .add_systems(Update, fade_hint_highlights);
+    }
+}
+
+fn start_hint(
+    mut events: EventReader<HintRequestedEvent>,
+    mut hint_state: ResMut<LevelHintState>,
+    curr_lvl_name: Res<CurrentLevelName>,
+    levels: Res<StockLevelInfos>,
+    mut button_text_query: Query<(&mut Text, &TrainyardButton)>,
+) {
+    events.clear();
+    let Some(name) = curr_lvl_name.0.as_ref() else {
+        return;
+    };
+    let Some(level) = levels.0.iter().find(|level| &level.name == name) else {
+        return;
+    };
+
+    if hint_state.level_name.as_ref() != Some(name) {
+        *hint_state = LevelHintState {
+            level_name: Some(name.clone()),
+            solution: level.solution.clone(),
+            ..default()
+        };
+    }
+    hint_state.hint_wanted = true;
+
+    if hint_state.solution.is_none() && hint_state.solver_task.is_none() {
+        let level = level.clone();
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let options = SolverOptions {
+                timeout: HINT_SOLVER_TIMEOUT,
+                ..default()
+            };
+            solve(&level, &options).solutions.into_iter().next()
+        });
+        hint_state.solver_task = Some(task);
+        set_hint_button_text(&mut button_text_query, HINT_BUTTON_SOLVING_TEXT);
+    }
+}
+
+fn reveal_hint(
+    mut commands: Commands,
+    mut hint_state: ResMut<LevelHintState>,
+    mut yard_query: Query<&mut Yard>,
+    mut progress: ResMut<GameLevelProgress>,
+    mut history: ResMut<EditHistory>,
+    levels: Res<StockLevelInfos>,
+    mut button_text_query: Query<(&mut Text, &TrainyardButton)>,
+) {
+    if let Some(task) = hint_state.solver_task.as_mut() {
+        let Some(solution) = block_on(future::poll_once(task)) else {
+            return;
+        };
+        hint_state.solver_task = None;
+        if solution.is_none() {
+            warn!("the solver couldn't find a solution to give hints from");
+            hint_state.hint_wanted = false;
+            set_hint_button_text(&mut button_text_query, HINT_BUTTON_NO_SOLUTION_TEXT);
+            return;
+        }
+        hint_state.solution = solution;
+        set_hint_button_text(&mut button_text_query, HINT_BUTTON_TEXT);
+    }
+
+    if !hint_state.hint_wanted {
+        return;
+    }
+    let (Some(name), Some(solution)) = (&hint_state.level_name, &hint_state.solution) else {
+        return;
+    };
+    let Some(level) = levels.0.iter().find(|level| &level.name == name) else {
+        return;
+    };
+    let Ok(yard) = yard_query.get_single_mut() else {
+        return;
+    };
+    let yard = yard.into_inner();
+
+    let drawn_tracks = yard.sim.get_progress();
+    let max_ticks = SolverOptions::default().max_ticks;
+    if let Some((row, col)) = next_hint(level, solution, &drawn_tracks, max_ticks) {
+        let connections = TileConnections::from_data(solution[row * yard.sim.width() + col]);
+        yard.sim.set_connections(row, col, connections);
+        history.record_separate_edit(name, &drawn_tracks, &yard.sim.get_progress());
+        spawn_hint_highlight(&mut commands, yard.base_entity, row, col);
+
+        progress.0.entry(name.clone()).or_default().hints_used += 1;
+    }
+    hint_state.hint_wanted = false;
+}
+
+fn set_hint_button_text(query: &mut Query<(&mut Text, &TrainyardButton)>, new_text: &str) {
+    for (mut text, button_type) in query.iter_mut() {
+        if *button_type == TrainyardButton::LevelHintButton {
+            text.0 = String::from(new_text);
+        }
+    }
+}
+
+fn spawn_hint_highlight(commands: &mut Commands, yard_entity: Entity, row: usize, col: usize) {
+    let x = col as f32 * TILE_SIZE_PX + TILE_SIZE_PX / 2.0;
+    let y = row as f32 * TILE_SIZE_PX + TILE_SIZE_PX / 2.0;
+
+    let id = commands
+        .spawn((
+            Transform::from_xyz(x, y, 2.0),
+            Sprite {
+                color: HINT_HIGHLIGHT_COLOR,
+                custom_size: Some(Vec2::splat(TILE_SIZE_PX)),
+                ..default()
+            },
+            HintHighlight {
+                ttl: HINT_HIGHLIGHT_SECS,
+            },
+            Name::new("hint highlight"),
+        ))
+        .id();
+    commands.entity(yard_entity).add_children(&[id]);
+}
+
+fn fade_hint_highlights(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Sprite, &mut HintHighlight)>,
+    time: Res<Time>,
+) {
+    for (entity, mut sprite, mut highlight) in query.iter_mut() {
+        highlight.ttl -= time.delta_secs();
+        if highlight.ttl <= 0.0 {
+            commands.entity(entity).remove_parent().despawn();
+            continue;
+        }
+        let alpha = HINT_HIGHLIGHT_COLOR.alpha() * highlight.ttl / HINT_HIGHLIGHT_SECS;
+        sprite.color.set_alpha(alpha);
+    }
+}
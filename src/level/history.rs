@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::mem;
+
+use bevy::prelude::*;
+
+use crate::input::InputAction;
+use crate::level::{
+    cursor::{add_connections_from_cursor_movement, destroy_connections_under_cursor, CursorState},
+    yard::Yard,
+    CurrentLevelName, LevelEditingSet, LevelSet,
+};
+
+pub const UNDO_BUTTON_TEXT: &str = "Undo";
+pub const REDO_BUTTON_TEXT: &str = "Redo";
+
+#[derive(Event, Clone, Copy, PartialEq, Eq)]
+pub enum EditHistoryEvent {
+    Undo,
+    Redo,
+}
+
+/// The tracks that were drawn on one level before each of the player's strokes.
+///
+/// A stroke is everything drawn or erased between pressing and releasing the mouse, or between toggling the cursor
+/// into and out of drawing/erasing with the keyboard, so it can be undone in one go.
+struct LevelEditHistory {
+    undo_stack: Vec<Vec<u8>>,
+    redo_stack: Vec<Vec<u8>>,
+    /// The tracks as of the last time they were recorded.
+    current: Vec<u8>,
+    /// The tracks from before the stroke in progress, once it has changed something.
+    stroke_start: Option<Vec<u8>>,
+}
+
+impl LevelEditHistory {
+    fn new(current: Vec<u8>) -> Self {
+        LevelEditHistory {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            current,
+            stroke_start: None,
+        }
+    }
+
+    fn record(&mut self, tracks: Vec<u8>) {
+        if tracks == self.current {
+            return;
+        }
+        let before = mem::replace(&mut self.current, tracks);
+        if self.stroke_start.is_none() {
+            self.stroke_start = Some(before);
+        }
+    }
+
+    fn end_stroke(&mut self) {
+        if let Some(before) = self.stroke_start.take() {
+            self.undo_stack.push(before);
+            self.redo_stack.clear();
+        }
+    }
+
+    fn undo(&mut self) -> Option<Vec<u8>> {
+        self.end_stroke();
+        let tracks = self.undo_stack.pop()?;
+        self.redo_stack
+            .push(mem::replace(&mut self.current, tracks.clone()));
+        Some(tracks)
+    }
+
+    fn redo(&mut self) -> Option<Vec<u8>> {
+        self.end_stroke();
+        let tracks = self.redo_stack.pop()?;
+        self.undo_stack
+            .push(mem::replace(&mut self.current, tracks.clone()));
+        Some(tracks)
+    }
+}
+
+/// Undo/redo history for every level that has been edited since the game started, by level name.
+#[derive(Resource, Default)]
+pub struct EditHistory(HashMap<String, LevelEditHistory>);
+
+impl EditHistory {
+    /// Records a change to the tracks that didn't come from the cursor (like a hint) as a stroke of its own.
+    pub fn record_separate_edit(&mut self, level_name: &str, before: &[u8], after: &[u8]) {
+        let history = self
+            .0
+            .entry(level_name.to_string())
+            .or_insert_with(|| LevelEditHistory::new(before.to_vec()));
+        history.record(before.to_vec());
+        history.end_stroke();
+        history.record(after.to_vec());
+        history.end_stroke();
+    }
+}
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EditHistoryEvent>()
+            .init_resource::<EditHistory>()
+            .add_systems(
+                Update,
+                (
+                    send_history_events_from_keypress,
+                    apply_history_events.run_if(on_event::<EditHistoryEvent>),
+                    record_edits
+                        .after(add_connections_from_cursor_movement)
+                        .after(destroy_connections_under_cursor),
+                )
+                    .chain()
+                    .in_set(LevelEditingSet)
+                    .in_set(LevelSet),
+            );
+    }
+}
+
+fn send_history_events_from_keypress(
+    actions: Res<ButtonInput<InputAction>>,
+    mut history_events: EventWriter<EditHistoryEvent>,
+) {
+    if actions.just_pressed(InputAction::Undo) {
+        history_events.send(EditHistoryEvent::Undo);
+    }
+    if actions.just_pressed(InputAction::Redo) {
+        history_events.send(EditHistoryEvent::Redo);
+    }
+}
+
+fn apply_history_events(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut history_events: EventReader<EditHistoryEvent>,
+    mut history: ResMut<EditHistory>,
+    curr_lvl_name: Res<CurrentLevelName>,
+    mut yard_query: Query<&mut Yard>,
+) {
+    let (Some(name), Ok(mut yard)) = (curr_lvl_name.0.as_ref(), yard_query.get_single_mut()) else {
+        history_events.clear();
+        return;
+    };
+    let Some(level_history) = history.0.get_mut(name) else {
+        history_events.clear();
+        return;
+    };
+
+    for event in history_events.read() {
+        let (tracks, sound) = match event {
+            EditHistoryEvent::Undo => (level_history.undo(), "audio/undo.ogg"),
+            EditHistoryEvent::Redo => (level_history.redo(), "audio/redo.ogg"),
+        };
+        if let Some(tracks) = tracks {
+            yard.sim.set_progress(&tracks);
+            commands.spawn(AudioPlayer::<AudioSource>(asset_server.load(sound)));
+        }
+    }
+}
+
+fn record_edits(
+    mut history: ResMut<EditHistory>,
+    curr_lvl_name: Res<CurrentLevelName>,
+    yard_query: Query<&Yard>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut cursor_state_events: EventReader<StateTransitionEvent<CursorState>>,
+) {
+    let (Some(name), Ok(yard)) = (curr_lvl_name.0.as_ref(), yard_query.get_single()) else {
+        return;
+    };
+    let tracks = yard.sim.get_progress();
+    let level_history = history
+        .0
+        .entry(name.clone())
+        .or_insert_with(|| LevelEditHistory::new(tracks.clone()));
+
+    // a new click, or switching between drawing and erasing, starts a new stroke
+    if cursor_state_events.read().count() > 0 || mouse_input.just_pressed(MouseButton::Left) {
+        level_history.end_stroke();
+    }
+    level_history.record(tracks);
+    if mouse_input.just_released(MouseButton::Left) {
+        level_history.end_stroke();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_until_the_end_of_a_stroke_are_undone_together() {
+        let mut history = LevelEditHistory::new(vec![0, 0]);
+        history.record(vec![1, 0]);
+        history.record(vec![1, 1]);
+        history.end_stroke();
+        history.record(vec![2, 1]);
+        history.end_stroke();
+
+        assert_eq!(history.undo(), Some(vec![1, 1]));
+        assert_eq!(history.undo(), Some(vec![0, 0]));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn strokes_only_count_once_they_change_the_tracks() {
+        let mut history = LevelEditHistory::new(vec![0]);
+        history.record(vec![0]);
+        history.end_stroke();
+        history.record(vec![1]);
+        history.record(vec![0]);
+        history.end_stroke();
+
+        // the second stroke drew and erased the same track, which still counts as a change
+        assert_eq!(history.undo(), Some(vec![0]));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let mut history = LevelEditHistory::new(vec![0]);
+        for tracks in 1..=3 {
+            history.record(vec![tracks]);
+            history.end_stroke();
+        }
+
+        assert_eq!(history.undo(), Some(vec![2]));
+        assert_eq!(history.undo(), Some(vec![1]));
+        assert_eq!(history.redo(), Some(vec![2]));
+        assert_eq!(history.redo(), Some(vec![3]));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(vec![2]));
+        assert_eq!(history.current, vec![2]);
+    }
+
+    #[test]
+    fn a_new_stroke_clears_redo() {
+        let mut history = LevelEditHistory::new(vec![0]);
+        history.record(vec![1]);
+        history.end_stroke();
+        assert_eq!(history.undo(), Some(vec![0]));
+
+        // the redo is kept until the new stroke has actually changed something
+        history.record(vec![0]);
+        history.end_stroke();
+        history.record(vec![2]);
+        history.end_stroke();
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(vec![0]));
+    }
+
+    #[test]
+    fn separate_edits_are_strokes_of_their_own() {
+        let mut history = EditHistory::default();
+        history.record_separate_edit("Level", &[0, 0], &[1, 1]);
+        let level_history = history.0.get_mut("Level").unwrap();
+        assert_eq!(level_history.undo(), Some(vec![0, 0]));
+        assert_eq!(level_history.undo(), None);
+
+        // tracks drawn since the last recording aren't lost in the separate edit's stroke
+        let mut history = EditHistory::default();
+        history.record_separate_edit("Level", &[0, 0], &[1, 0]);
+        history.record_separate_edit("Level", &[1, 1], &[2, 2]);
+        let level_history = history.0.get_mut("Level").unwrap();
+        assert_eq!(level_history.undo(), Some(vec![1, 1]));
+        assert_eq!(level_history.undo(), Some(vec![1, 0]));
+        assert_eq!(level_history.undo(), Some(vec![0, 0]));
+        assert_eq!(level_history.undo(), None);
+    }
+}
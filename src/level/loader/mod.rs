@@ -1,18 +1,39 @@
-pub mod level_load_info;
 
 use bevy::prelude::*;
-use level_load_info::LevelLoadInfo;
 use serde::{Deserialize, Serialize};
+use trainyard_core::{level::LevelLoadInfo, levels_txt};
 
 #[derive(Resource, Serialize, Deserialize, Debug)]
 pub struct StockLevelInfos(pub Vec<LevelLoadInfo>);
 
-pub const LEVEL_DATA: &str = include_str!("../../../assets/levels/levels.json");
+impl StockLevelInfos {
+    /// The name of every city, in the order their levels appear.
+    pub fn cities(&self) -> Vec<&str> {
+        let mut cities: Vec<&str> = Vec::new();
+        for level in self.0.iter() {
+            if !cities.contains(&level.city.as_str()) {
+                cities.push(&level.city);
+            }
+        }
+        cities
+    }
+
+    pub fn levels_in_city<'a>(&'a self, city: &'a str) -> impl Iterator<Item = &'a LevelLoadInfo> {
+        self.0.iter().filter(move |level| level.city == city)
+    }
+
+    pub fn find_level(&self, name: &str) -> Option<&LevelLoadInfo> {
+        self.0.iter().find(|level| level.name == name)
+    }
+}
+
+pub const LEVEL_DATA: &str = include_str!("../../../assets/levels.txt");
 
 pub struct LevelLoaderPlugin;
 impl Plugin for LevelLoaderPlugin {
     fn build(&self, app: &mut App) {
-        let my_levels: StockLevelInfos = serde_json::from_str(LEVEL_DATA).unwrap();
-        app.insert_resource(my_levels);
+        let levels = levels_txt::parse_levels_txt(LEVEL_DATA)
+            .unwrap_or_else(|e| panic!("could not parse levels.txt: {}", e));
+        app.insert_resource(StockLevelInfos(levels));
     }
 }
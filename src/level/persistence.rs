@@ -1,17 +1,425 @@
 use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+use trainyard_core::{connections::TileConnections, level::LevelLoadInfo, score::Score};
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{level::loader::StockLevelInfos, ui::level_picker::IMPORTED_CITY};
+
+/// Bump this whenever the layout of `SaveFile` changes in a way that old saves can't be read as-is.
+pub const SAVE_FILE_VERSION: u32 = 1;
+pub const SAVE_DIR_NAME: &str = "trainyard";
+pub const SAVE_FILE_NAME: &str = "progress.json";
 
 #[derive(Resource, Default)]
 pub struct GameLevelProgress(pub HashMap<String, LevelProgress>);
 
-#[derive(Default)]
+impl GameLevelProgress {
+    /// The total star rating of every level in `levels` that has been won.
+    pub fn stars_earned<'a>(&self, levels: impl IntoIterator<Item = &'a LevelLoadInfo>) -> u32 {
+        levels
+            .into_iter()
+            .filter(|level| self.0.get(&level.name).is_some_and(|p| p.has_won))
+            .map(|level| level.stars as u32)
+            .sum()
+    }
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct LevelProgress {
     pub has_won: bool,
     pub drawn_tracks: Vec<u8>,
+    /// How many track pieces the player has been shown with the hint button.
+    #[serde(default)]
+    pub hints_used: u32,
+    /// The best of each part of the score over every win, which may each come from a different solution.
+    #[serde(default)]
+    pub best_score: Option<Score>,
+    /// Tracks the player has saved under a name, besides `drawn_tracks`, which is saved automatically.
+    #[serde(default)]
+    pub saved_solutions: BTreeMap<String, Vec<u8>>,
+}
+
+/// One of the places a level's tracks can be saved in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SolutionSlot {
+    /// `LevelProgress::drawn_tracks`, which is saved whenever the player leaves the level or wins it.
+    #[default]
+    Autosave,
+    Named(String),
+}
+
+impl SolutionSlot {
+    pub fn label(&self) -> &str {
+        match self {
+            SolutionSlot::Autosave => "Autosave",
+            SolutionSlot::Named(name) => name,
+        }
+    }
+}
+
+impl LevelProgress {
+    /// Every slot that has tracks in it, starting with the autosave.
+    pub fn solution_slots(&self) -> impl Iterator<Item = SolutionSlot> + '_ {
+        std::iter::once(SolutionSlot::Autosave).chain(
+            self.saved_solutions
+                .keys()
+                .map(|name| SolutionSlot::Named(name.clone())),
+        )
+    }
+
+    pub fn solution(&self, slot: &SolutionSlot) -> Option<&Vec<u8>> {
+        match slot {
+            SolutionSlot::Autosave => Some(&self.drawn_tracks),
+            SolutionSlot::Named(name) => self.saved_solutions.get(name),
+        }
+    }
+
+    /// Saves `tracks` in a new slot called `name`, or in one called "Solution N" if `name` is blank.
+    pub fn save_new_solution(
+        &mut self,
+        name: &str,
+        tracks: Vec<u8>,
+    ) -> Result<SolutionSlot, String> {
+        let name = match name.trim() {
+            "" => (1..)
+                .map(|n| format!("Solution {}", n))
+                .find(|name| !self.saved_solutions.contains_key(name))
+                .unwrap(),
+            name => self.check_new_solution_name(name)?,
+        };
+        self.saved_solutions.insert(name.clone(), tracks);
+        Ok(SolutionSlot::Named(name))
+    }
+
+    /// Moves the solution called `old_name` to a slot called `new_name`.
+    pub fn rename_solution(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<SolutionSlot, String> {
+        let new_name = self.check_new_solution_name(new_name.trim())?;
+        let tracks = self
+            .saved_solutions
+            .remove(old_name)
+            .ok_or("that solution isn't there anymore")?;
+        self.saved_solutions.insert(new_name.clone(), tracks);
+        Ok(SolutionSlot::Named(new_name))
+    }
+
+    fn check_new_solution_name(&self, name: &str) -> Result<String, String> {
+        if name.is_empty() {
+            return Err(String::from("type a name for the solution first"));
+        }
+        // a solution called "Autosave" couldn't be told apart from the autosave in the list
+        if name == SolutionSlot::Autosave.label() || self.saved_solutions.contains_key(name) {
+            return Err(format!("there's already a solution called \"{}\"", name));
+        }
+        Ok(name.to_string())
+    }
+}
+
+/// The on-disk representation of `GameLevelProgress`.
+/// A `BTreeMap` is used so that the file contents don't change order between saves.
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    levels: BTreeMap<String, LevelProgress>,
 }
 
 pub struct PersistencePlugin;
 impl Plugin for PersistencePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<GameLevelProgress>();
+        let (progress, save_file_is_newer) = match save_file_path() {
+            Some(path) => load_progress(&path),
+            None => {
+                warn!("could not find a data directory, level progress will not be loaded");
+                (GameLevelProgress::default(), SaveFileIsNewer(false))
+            }
+        };
+        app.insert_resource(progress)
+            .insert_resource(save_file_is_newer)
+            .add_systems(
+                Last,
+                save_progress.run_if(
+                    resource_changed::<GameLevelProgress>
+                        .and(not(resource_added::<GameLevelProgress>))
+                        .and(|newer: Res<SaveFileIsNewer>| !newer.0),
+                ),
+            );
+    }
+}
+
+/// Set when the save file was written by a newer version of the game. This version can't read it, but it mustn't
+/// overwrite it either, so progress isn't saved until the game is updated.
+#[derive(Resource, Default)]
+pub struct SaveFileIsNewer(pub bool);
+
+/// Just enough of a `SaveFile` to tell which version wrote it, whatever the rest of it looks like.
+#[derive(Deserialize)]
+struct SaveFileVersion {
+    version: u32,
+}
+
+pub fn save_file_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(SAVE_DIR_NAME).join(SAVE_FILE_NAME))
+}
+
+fn load_progress(path: &Path) -> (GameLevelProgress, SaveFileIsNewer) {
+    if !path.exists() {
+        return (GameLevelProgress::default(), SaveFileIsNewer(false));
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string());
+    let version = contents
+        .as_deref()
+        .ok()
+        .and_then(|contents| serde_json::from_str::<SaveFileVersion>(contents).ok());
+    if let Some(SaveFileVersion { version }) = version {
+        if version > SAVE_FILE_VERSION {
+            warn!(
+                "{:?} was saved by a newer version of the game (save file version {}, this version reads up to {}), \
+                 so it will be left as it is and level progress will not be saved",
+                path, version, SAVE_FILE_VERSION
+            );
+            return (GameLevelProgress::default(), SaveFileIsNewer(true));
+        }
+    }
+
+    let save_file = contents.and_then(|contents| {
+        serde_json::from_str::<SaveFile>(&contents).map_err(|e| e.to_string())
+    });
+    match save_file {
+        Ok(mut save_file) => {
+            info!("loaded level progress from {:?}", path);
+            for (name, progress) in save_file.levels.iter_mut() {
+                discard_invalid_tracks(name, progress);
+            }
+            (
+                GameLevelProgress(save_file.levels.into_iter().collect()),
+                SaveFileIsNewer(false),
+            )
+        }
+        Err(e) => {
+            // keep the unreadable file around so that it can be recovered by hand,
+            // and start over with empty progress so that the game stays playable.
+            let backup_path = backup_path(path);
+            warn!(
+                "could not read level progress from {:?} ({}), moving it to {:?}",
+                path, e, backup_path
+            );
+            if let Err(e) = fs::rename(path, &backup_path) {
+                error!("could not back up unreadable save file: {}", e);
+            }
+            (GameLevelProgress::default(), SaveFileIsNewer(false))
+        }
+    }
+}
+
+/// Where to move the unreadable file at `path`. That's `<name>.json.bak` next to it, unless an earlier backup is
+/// already there: then the time is added to the name, so that backups are never overwritten.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let backup_path = path.with_extension("json.bak");
+    if !backup_path.exists() {
+        return backup_path;
+    }
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    (0..)
+        .map(|n| match n {
+            0 => path.with_extension(format!("{}.json.bak", secs)),
+            n => path.with_extension(format!("{}-{}.json.bak", secs, n)),
+        })
+        .find(|backup_path| !backup_path.exists())
+        .unwrap()
+}
+
+/// Whether every tile of `tracks` holds something that could have been drawn. Only a damaged or hand-edited file
+/// has tracks that couldn't.
+pub fn tracks_are_valid(tracks: &[u8]) -> bool {
+    tracks
+        .iter()
+        .all(|data| TileConnections::try_from_data(*data).is_some())
+}
+
+/// Throws away the tracks saved for `level_name` that couldn't have been drawn, keeping the rest of its progress.
+fn discard_invalid_tracks(level_name: &str, progress: &mut LevelProgress) {
+    if !tracks_are_valid(&progress.drawn_tracks) {
+        warn!(
+            "discarding the saved tracks of {}, which are not valid",
+            level_name
+        );
+        progress.drawn_tracks.clear();
+    }
+    progress.saved_solutions.retain(|solution_name, tracks| {
+        let valid = tracks_are_valid(tracks);
+        if !valid {
+            warn!(
+                "discarding the solution {:?} of {}, which is not valid",
+                solution_name, level_name
+            );
+        }
+        valid
+    });
+}
+
+fn save_progress(progress: Res<GameLevelProgress>, levels: Res<StockLevelInfos>) {
+    let Some(path) = save_file_path() else {
+        return;
+    };
+    // imported levels are named in the order they're pasted in, so next time the same name could be another level
+    let is_imported = |name: &str| {
+        levels
+            .find_level(name)
+            .is_some_and(|level| level.city == IMPORTED_CITY)
+    };
+    let save_file = SaveFile {
+        version: SAVE_FILE_VERSION,
+        levels: progress
+            .0
+            .iter()
+            .filter(|(name, _)| !is_imported(name))
+            .map(|(name, progress)| (name.clone(), progress.clone()))
+            .collect(),
+    };
+
+    match write_atomically(&path, &serde_json::to_vec_pretty(&save_file).unwrap()) {
+        Ok(()) => info!("saved level progress to {:?}", path),
+        Err(e) => error!("could not save level progress to {:?}: {}", path, e),
+    }
+}
+
+/// Writes to a temporary file next to `path` and then renames it over `path`,
+/// so that a crash halfway through never leaves a partially written save behind.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> SolutionSlot {
+        SolutionSlot::Named(String::from(name))
+    }
+
+    #[test]
+    fn blank_names_are_filled_in() {
+        let mut progress = LevelProgress::default();
+        assert_eq!(
+            progress.save_new_solution("", vec![1]),
+            Ok(named("Solution 1"))
+        );
+        assert_eq!(
+            progress.save_new_solution("  ", vec![2]),
+            Ok(named("Solution 2"))
+        );
+        assert_eq!(progress.solution(&named("Solution 2")), Some(&vec![2]));
+    }
+
+    #[test]
+    fn solutions_are_saved_under_the_name_given() {
+        let mut progress = LevelProgress::default();
+        assert_eq!(
+            progress.save_new_solution(" Short ", vec![1]),
+            Ok(named("Short"))
+        );
+        assert!(progress.save_new_solution("Short", vec![2]).is_err());
+        assert!(progress.save_new_solution("Autosave", vec![2]).is_err());
+        assert_eq!(progress.solution(&named("Short")), Some(&vec![1]));
+    }
+
+    #[test]
+    fn renaming_keeps_the_tracks() {
+        let mut progress = LevelProgress::default();
+        progress.save_new_solution("First", vec![1]).unwrap();
+        progress.save_new_solution("Second", vec![2]).unwrap();
+        assert!(progress.rename_solution("First", "Second").is_err());
+        assert!(progress.rename_solution("First", "").is_err());
+        assert!(progress.rename_solution("Third", "Fourth").is_err());
+
+        assert_eq!(progress.rename_solution("First", "Best"), Ok(named("Best")));
+        assert_eq!(progress.solution(&named("Best")), Some(&vec![1]));
+        assert_eq!(progress.solution(&named("First")), None);
+        assert_eq!(
+            progress.solution_slots().collect::<Vec<_>>(),
+            [SolutionSlot::Autosave, named("Best"), named("Second")]
+        );
+    }
+
+    /// An empty directory of its own for each test, so that tests running at the same time don't see each other's
+    /// files.
+    fn empty_dir(test_name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("trainyard-{}-{}", test_name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn saves_from_newer_versions_are_left_alone() {
+        let dir = empty_dir("newer-save");
+        let path = dir.join(SAVE_FILE_NAME);
+        let contents = format!(
+            r#"{{"version": {}, "levels": {{}}, "something new": []}}"#,
+            SAVE_FILE_VERSION + 1
+        );
+        fs::write(&path, &contents).unwrap();
+
+        let (progress, newer) = load_progress(&path);
+        assert!(newer.0);
+        assert!(progress.0.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+        assert!(!path.with_extension("json.bak").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_saves_are_backed_up_without_overwriting_older_backups() {
+        let dir = empty_dir("unreadable-save");
+        let path = dir.join(SAVE_FILE_NAME);
+        let current = format!(r#"{{"version": {}, "levels": {{}}}}"#, SAVE_FILE_VERSION);
+        fs::write(&path, &current).unwrap();
+        let (_, newer) = load_progress(&path);
+        assert!(!newer.0);
+        assert!(path.exists());
+
+        fs::write(&path, "first").unwrap();
+        load_progress(&path);
+        assert!(!path.exists());
+        let first_backup = path.with_extension("json.bak");
+        assert_eq!(fs::read_to_string(&first_backup).unwrap(), "first");
+
+        for contents in ["second", "third"] {
+            fs::write(&path, contents).unwrap();
+            let (progress, newer) = load_progress(&path);
+            assert!(progress.0.is_empty());
+            assert!(!newer.0);
+        }
+        let mut backups: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        backups.sort();
+        assert_eq!(backups, ["first", "second", "third"]);
+        assert_eq!(fs::read_to_string(&first_backup).unwrap(), "first");
+        fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,282 @@
+//! Saving the latest winning run of each level, and watching it again later.
+//!
+//! Watching a replay runs the replay's tracks on the level's yard, just like the start button does with the player's
+//! own tracks, which are put back once the replay is stopped. Since the yard at any tick can be worked out from the
+//! replay, the replay can be scrubbed to any tick while it's being watched.
+
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use trainyard_core::{
+    level::LevelLoadInfo,
+    replay::{Replay, REPLAY_VERSION},
+};
+
+use crate::{
+    level::{
+        loader::StockLevelInfos,
+        persistence::{tracks_are_valid, write_atomically, SAVE_DIR_NAME},
+        tick_yard_tick_timer,
+        yard::{Yard, YardEditedState, YardTickedEvent},
+        CurrentLevelName, EndTickEvent, LevelSet, LevelState, LevelStateIsRunning, MidTickEvent,
+        WinLevelEvent, YardTickTimer,
+    },
+    ui::UIState,
+};
+
+pub const REPLAY_DIR_NAME: &str = "replays";
+/// A run that hasn't been won after this many ticks isn't going to be, so recording stops there.
+pub const MAX_REPLAY_TICKS: usize = 1000;
+
+/// Whether the yard is showing a replay instead of the player's own tracks.
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReplayState {
+    #[default]
+    Off,
+    Watching,
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum ReplayEvent {
+    Watch,
+    Stop,
+    /// Moves a replay that's being watched to the given tick.
+    SeekTo(usize),
+    /// Shows or hides the replay's trains over the yard while the player draws.
+    ToggleGhost,
+}
+
+#[derive(Resource, Default)]
+pub struct ReplayPlayer {
+    /// The latest winning run of the level being played, if it has been won.
+    pub replay: Option<Replay>,
+    /// How many ticks into the replay the yard is, while it's being watched.
+    pub tick: usize,
+    /// A tick that the yard is waiting to be moved to.
+    seek: Option<usize>,
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<ReplayState>()
+            .add_event::<ReplayEvent>()
+            .init_resource::<ReplayPlayer>()
+            .add_systems(OnExit(LevelStateIsRunning::Running), stop_watching)
+            .add_systems(
+                Update,
+                (
+                    load_saved_replay.run_if(resource_changed::<CurrentLevelName>),
+                    record_winning_replay.run_if(
+                        on_event::<WinLevelEvent>
+                            .and(in_state(UIState::Level))
+                            .and(in_state(ReplayState::Off)),
+                    ),
+                    (
+                        handle_replay_events.run_if(on_event::<ReplayEvent>),
+                        apply_replay_seek.run_if(
+                            in_state(ReplayState::Watching)
+                                .and(|player: Res<ReplayPlayer>| player.seek.is_some()),
+                        ),
+                    )
+                        .chain()
+                        .before(tick_yard_tick_timer),
+                    count_replay_ticks
+                        .after(tick_yard_tick_timer)
+                        .run_if(on_event::<YardTickedEvent>.and(in_state(ReplayState::Watching))),
+                )
+                    .in_set(LevelSet),
+            );
+    }
+}
+
+pub fn replay_file_path(level_name: &str) -> Option<PathBuf> {
+    // level names can have any characters in them, which file names can't
+    let file_name: String = level_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dirs::data_dir().map(|dir| {
+        dir.join(SAVE_DIR_NAME)
+            .join(REPLAY_DIR_NAME)
+            .join(format!("{}.json", file_name))
+    })
+}
+
+/// Reads the saved replay of `level`, if there is one that still plays the same way on it.
+fn read_replay(level: &LevelLoadInfo) -> Result<Option<Replay>, String> {
+    let Some(path) = replay_file_path(&level.name) else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let replay: Replay = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    if replay.version > REPLAY_VERSION {
+        return Err(format!(
+            "replay version {} is newer than the supported version {}",
+            replay.version, REPLAY_VERSION
+        ));
+    }
+    // a level that has changed since the replay was recorded would be drawn with tiles the replay doesn't have
+    if replay.level.name != level.name
+        || (replay.level.width, replay.level.height) != (level.width, level.height)
+        || replay.level.tiles() != level.tiles()
+    {
+        return Err(String::from("the level has changed since it was recorded"));
+    }
+    if !tracks_are_valid(&replay.drawn_tracks) {
+        return Err(String::from("the replay has tracks that can't be drawn"));
+    }
+    if !replay.verify() {
+        return Err(String::from(
+            "the trains don't run the way they were recorded",
+        ));
+    }
+    Ok(Some(replay))
+}
+
+fn load_saved_replay(
+    curr_lvl_name: Res<CurrentLevelName>,
+    levels: Res<StockLevelInfos>,
+    mut player: ResMut<ReplayPlayer>,
+) {
+    player.replay = None;
+    let Some(level) = curr_lvl_name
+        .0
+        .as_ref()
+        .and_then(|name| levels.find_level(name))
+    else {
+        return;
+    };
+    match read_replay(level) {
+        Ok(replay) => player.replay = replay,
+        // the replay is just left out; it's replaced the next time the level is won
+        Err(e) => warn!("could not read the replay of {}: {}", level.name, e),
+    }
+}
+
+/// Records the tracks that just won the level, replacing the level's saved replay.
+pub fn record_winning_replay(
+    curr_lvl_name: Res<CurrentLevelName>,
+    levels: Res<StockLevelInfos>,
+    yard_edit_state_query: Query<&YardEditedState>,
+    mut player: ResMut<ReplayPlayer>,
+) {
+    let Some(level) = curr_lvl_name
+        .0
+        .as_ref()
+        .and_then(|name| levels.find_level(name))
+    else {
+        return;
+    };
+    // the tracks in the running yard have had their switches flipped by the trains
+    let Ok(yard) = yard_edit_state_query.get_single() else {
+        return;
+    };
+    let replay = Replay::record(level, &yard.0.sim.get_progress(), MAX_REPLAY_TICKS);
+    if !replay.has_won() {
+        warn!(
+            "the tracks that won {} did not win again when recorded",
+            level.name
+        );
+        return;
+    }
+
+    if let Some(path) = replay_file_path(&level.name) {
+        match write_atomically(&path, &serde_json::to_vec(&replay).unwrap()) {
+            Ok(()) => info!("saved a replay of {} to {:?}", level.name, path),
+            Err(e) => error!("could not save a replay to {:?}: {}", path, e),
+        }
+    }
+    player.replay = Some(replay);
+}
+
+fn handle_replay_events(
+    mut events: EventReader<ReplayEvent>,
+    level_state: Res<State<LevelState>>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+    replay_state: Res<State<ReplayState>>,
+    mut next_replay_state: ResMut<NextState<ReplayState>>,
+    mut player: ResMut<ReplayPlayer>,
+) {
+    for event in events.read() {
+        match (event, replay_state.get()) {
+            (ReplayEvent::Watch, ReplayState::Off)
+                if *level_state.get() == LevelState::Editing && player.replay.is_some() =>
+            {
+                // the replay's tracks are only swapped in once the player's have been saved by starting the trains
+                player.seek = Some(0);
+                next_level_state.set(LevelState::RunningNotCrashed);
+                next_replay_state.set(ReplayState::Watching);
+            }
+            (ReplayEvent::Stop, ReplayState::Watching) => {
+                next_level_state.set(LevelState::Editing);
+            }
+            (ReplayEvent::SeekTo(tick), ReplayState::Watching) => player.seek = Some(*tick),
+            _ => {}
+        }
+    }
+}
+
+/// Puts the yard back the way it was after the tick that's being seeked to.
+fn apply_replay_seek(
+    mut commands: Commands,
+    mut player: ResMut<ReplayPlayer>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+    mut running_query: Query<(&mut Yard, &mut YardTickTimer)>,
+    mid_tick_events_query: Query<Entity, With<MidTickEvent>>,
+    end_tick_events_query: Query<Entity, With<EndTickEvent>>,
+) {
+    let player = player.as_mut();
+    let (Some(tick), Some(replay)) = (player.seek.take(), player.replay.as_ref()) else {
+        return;
+    };
+    let Ok((mut yard, mut timer)) = running_query.get_single_mut() else {
+        return;
+    };
+    let tick = tick.min(replay.len());
+
+    yard.sim = replay.yard_at(tick);
+    yard.reset_tile_inner_entities_and_train_entities(&mut commands);
+    for entity in mid_tick_events_query
+        .iter()
+        .chain(end_tick_events_query.iter())
+    {
+        commands.entity(entity).despawn();
+    }
+    if tick == 0 {
+        *timer = YardTickTimer::default();
+    } else {
+        timer.restart_tick();
+        let last_tick = &replay.ticks[tick - 1];
+        for event in &last_tick.mid_tick_events {
+            commands.spawn(MidTickEvent(event.clone()));
+        }
+        for event in &last_tick.end_tick_events {
+            commands.spawn(EndTickEvent(event.clone()));
+        }
+    }
+    let crashed = replay.ticks[..tick].iter().any(|tick| tick.has_crashed());
+    next_level_state.set(if crashed {
+        LevelState::RunningCrashed
+    } else {
+        LevelState::RunningNotCrashed
+    });
+    player.tick = tick;
+}
+
+fn count_replay_ticks(mut player: ResMut<ReplayPlayer>) {
+    player.tick += 1;
+}
+
+fn stop_watching(
+    mut player: ResMut<ReplayPlayer>,
+    mut next_replay_state: ResMut<NextState<ReplayState>>,
+) {
+    player.seek = None;
+    player.tick = 0;
+    next_replay_state.set(ReplayState::Off);
+}
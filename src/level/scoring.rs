@@ -0,0 +1,91 @@
+//! Scoring every win, and keeping the best scores of each level.
+
+use bevy::prelude::*;
+use trainyard_core::score::{NewRecords, Score};
+
+use crate::{
+    level::{
+        loader::StockLevelInfos,
+        persistence::GameLevelProgress,
+        replays::{record_winning_replay, ReplayPlayer, ReplayState},
+        win_event_handler,
+        yard::YardEditedState,
+        CurrentLevelName, LevelSet, WinLevelEvent,
+    },
+    ui::UIState,
+};
+
+/// The score of the level that was just won, for the level-won dialog to show.
+#[derive(Resource, Default)]
+pub struct LastWinScore {
+    pub score: Option<Score>,
+    /// The level's best scores, including this one.
+    pub best: Option<Score>,
+    pub new_records: NewRecords,
+}
+
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LastWinScore>().add_systems(
+            Update,
+            score_win
+                .after(win_event_handler)
+                .after(record_winning_replay)
+                .run_if(
+                    on_event::<WinLevelEvent>
+                        .and(in_state(UIState::Level))
+                        .and(in_state(ReplayState::Off)),
+                )
+                .in_set(LevelSet),
+        );
+    }
+}
+
+/// Scores the replay that `record_winning_replay` just recorded of the win.
+fn score_win(
+    curr_lvl_name: Res<CurrentLevelName>,
+    levels: Res<StockLevelInfos>,
+    yard_edit_state_query: Query<&YardEditedState>,
+    player: Res<ReplayPlayer>,
+    mut persistence: ResMut<GameLevelProgress>,
+    mut last_win: ResMut<LastWinScore>,
+) {
+    *last_win = LastWinScore::default();
+    let Some(level) = curr_lvl_name
+        .0
+        .as_ref()
+        .and_then(|name| levels.find_level(name))
+    else {
+        return;
+    };
+    let Ok(yard) = yard_edit_state_query.get_single() else {
+        return;
+    };
+    // the replay is left as it was when the win couldn't be recorded, so it could be of some earlier win
+    let drawn_tracks = yard.0.sim.get_progress();
+    let Some(score) = player
+        .replay
+        .as_ref()
+        .filter(|replay| replay.level.name == level.name && replay.drawn_tracks == drawn_tracks)
+        .and_then(Score::of_replay)
+    else {
+        warn!(
+            "the win of {} was not recorded, so it isn't scored",
+            level.name
+        );
+        return;
+    };
+
+    let progress = persistence.0.entry(level.name.clone()).or_default();
+    let best = progress
+        .best_score
+        .map_or(score, |best_score| best_score.best_of(&score));
+    *last_win = LastWinScore {
+        score: Some(score),
+        best: Some(best),
+        new_records: score.new_records(progress.best_score.as_ref()),
+    };
+    progress.best_score = Some(best);
+}
@@ -1,30 +1,33 @@
-pub mod connections;
 pub mod drawable_tile;
+pub mod painter_tile;
 pub mod rock_tile;
 pub mod sink_tile;
 pub mod source_tile;
+pub mod splitter_tile;
 pub mod tile;
 pub mod tile_animations;
 
 use bevy::{prelude::*, ui::UiSystem};
 
+use trainyard_core::tiles::TileConstructionInfo;
+
 use crate::{
     level::{
-        direction::Dir,
         restore_yard_edited_state,
-        trains::TrainColor,
         yard::YardEditedState,
         yard::{Yard, YardTickedEvent},
         LevelSet, LevelState,
     },
     ui::level::YardPlaceholderNode,
-    NUM_COLS, NUM_ROWS, TILE_SIZE_PX,
+    TILE_SIZE_PX,
 };
-use drawable_tile::DrawableTile;
-use rock_tile::RockTile;
-use sink_tile::SinkTile;
-use source_tile::SourceTile;
-use tile::Tile;
+use drawable_tile::DrawableTileRenderer;
+use painter_tile::PainterTileRenderer;
+use rock_tile::RockTileRenderer;
+use sink_tile::SinkTileRenderer;
+use source_tile::SourceTileRenderer;
+use splitter_tile::SplitterTileRenderer;
+use tile::TileRenderer;
 
 use super::{
     persistence::{GameLevelProgress, LevelProgress},
@@ -69,28 +72,14 @@ impl Plugin for TilePlugin {
     }
 }
 
-pub enum TileConstructionInfo {
-    DrawableTile,
-    Rock,
-    SourceTile {
-        out: Dir,
-        trains: Vec<TrainColor>,
-    },
-    SinkTile {
-        ins: [bool; 4],
-        trains: Vec<TrainColor>,
-    },
-    Painter,
-    Splitter,
-}
-
-pub fn construct_new_tile(
-    tile_type: TileConstructionInfo,
+/// Spawns the entities for drawing a tile, positioned at the given row and column of the yard.
+pub fn construct_new_tile_renderer(
+    tile_type: &TileConstructionInfo,
     row: u8,
     col: u8,
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
-) -> Box<dyn Tile + Send + Sync> {
+) -> Box<dyn TileRenderer + Send + Sync> {
     let x = col as f32 * TILE_SIZE_PX + TILE_SIZE_PX / 2.0;
     let y = row as f32 * TILE_SIZE_PX + TILE_SIZE_PX / 2.0;
 
@@ -102,17 +91,33 @@ pub fn construct_new_tile(
         ))
         .id();
 
-    match tile_type {
-        TileConstructionInfo::DrawableTile => Box::new(DrawableTile::new(entity)),
-        TileConstructionInfo::Rock => Box::new(RockTile::new(entity)),
-        TileConstructionInfo::SourceTile { out, trains } => {
-            Box::new(SourceTile::new(out, trains, entity, commands, asset_server))
+    match *tile_type {
+        TileConstructionInfo::DrawableTile => Box::new(DrawableTileRenderer::new(entity)),
+        TileConstructionInfo::Rock => Box::new(RockTileRenderer::new(entity)),
+        TileConstructionInfo::SourceTile { out, .. } => {
+            Box::new(SourceTileRenderer::new(out, entity, commands, asset_server))
         }
-        TileConstructionInfo::SinkTile { ins, trains } => {
-            Box::new(SinkTile::new(ins, trains, entity, commands, asset_server))
+        TileConstructionInfo::SinkTile { ins, .. } => {
+            Box::new(SinkTileRenderer::new(ins, entity, commands, asset_server))
         }
-        TileConstructionInfo::Painter => todo!(),
-        TileConstructionInfo::Splitter => todo!(),
+        TileConstructionInfo::Painter {
+            color,
+            in_dir,
+            out_dir,
+        } => Box::new(PainterTileRenderer::new(
+            color,
+            in_dir,
+            out_dir,
+            entity,
+            commands,
+            asset_server,
+        )),
+        TileConstructionInfo::Splitter { in_dir } => Box::new(SplitterTileRenderer::new(
+            in_dir,
+            entity,
+            commands,
+            asset_server,
+        )),
     }
 }
 
@@ -128,15 +133,17 @@ fn persist_yard_and_despawn_game_tiles(
         commands.entity(entity).despawn_recursive();
         if let Some(name) = curr_lvl_name.0.as_ref() {
             let mut has_won = *lvl_state.get() == LevelState::Won;
-            let drawn_tracks = yard.get_progress();
+            let drawn_tracks = yard.sim.get_progress();
 
-            if persistence.0.remove(name).unwrap_or_default().has_won {
+            let old_progress = persistence.0.remove(name).unwrap_or_default();
+            if old_progress.has_won {
                 has_won = true;
             }
 
             let progress = LevelProgress {
                 has_won,
                 drawn_tracks,
+                ..old_progress
             };
             persistence.0.insert(name.to_string(), progress);
         }
@@ -172,16 +179,26 @@ fn render_yard_trains(
     }
 }
 
+/// Moves the yard over its placeholder in the UI, and sizes the placeholder to match the yard.
 pub fn adjust_yard_position_to_match_placeholder(
-    yard_query: Query<Entity, With<Yard>>,
-    placeholder_query: Query<(&Transform, &Parent), With<YardPlaceholderNode>>,
+    yard_query: Query<(Entity, &Yard)>,
+    mut placeholder_query: Query<(&Transform, &Parent, &mut Node), With<YardPlaceholderNode>>,
     parent_query: Query<(&Transform, &Parent)>,
     mut commands: Commands,
-    _window_query: Query<&Window, With<bevy::window::PrimaryWindow>>,
 ) {
-    for yard_entity in yard_query.iter() {
-        println!("adjusting yard position to match placeholder...");
-        if let Ok((placeholder_transform, parent)) = placeholder_query.get_single() {
+    for (yard_entity, yard) in yard_query.iter() {
+        if let Ok((placeholder_transform, parent, mut placeholder_node)) =
+            placeholder_query.get_single_mut()
+        {
+            // the layout has already run this frame, so a resized placeholder only moves on the next one
+            let size = yard.display_size_px();
+            if placeholder_node.width != Val::Px(size.x)
+                || placeholder_node.height != Val::Px(size.y)
+            {
+                placeholder_node.width = Val::Px(size.x);
+                placeholder_node.height = Val::Px(size.y);
+            }
+
             let mut final_transform: Vec2 = Vec2::ZERO;
 
             // add up the transforms of all parent entities
@@ -198,11 +215,12 @@ pub fn adjust_yard_position_to_match_placeholder(
                 }
             }
 
-            let x = final_transform.x - (NUM_COLS as f32 * TILE_SIZE_PX) / 2.0 + 120.0;
-            let y = final_transform.y - (NUM_ROWS as f32 * TILE_SIZE_PX) / 2.0;
+            let x = final_transform.x - size.x / 2.0 + 120.0;
+            let y = final_transform.y - size.y / 2.0;
+            let scale = yard.display_scale();
             commands
                 .entity(yard_entity)
-                .insert(Transform::from_xyz(x, y, 0.0));
+                .insert(Transform::from_xyz(x, y, 0.0).with_scale(Vec3::new(scale, scale, 1.0)));
         }
     }
 }
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use trainyard_core::{
+    connections::{Connection, TileConnections},
+    direction::Dir,
+    tiles::tile::TileEvent,
+    trains::TrainColor,
+};
+
+use super::{
+    drawable_tile::get_track_asset_path, tile::TileRenderer,
+    tile_animations::SpriteSheetAnimationComponent,
+};
+
+pub const PAINTER_BRUSH_ANIMATION_FRAMES: u32 = 10;
+
+#[derive(Clone)]
+pub struct PainterTileRenderer {
+    pub base_entity: Entity,
+    pub background_entity: Entity,
+    pub track_entity: Entity,
+    pub brush_entity: Entity,
+}
+
+impl PainterTileRenderer {
+    pub fn new(
+        color: TrainColor,
+        in_dir: Dir,
+        out_dir: Dir,
+        base_entity: Entity,
+        commands: &mut Commands,
+        asset_server: &Res<AssetServer>,
+    ) -> Self {
+        let mut background_entity = base_entity;
+        let mut track_entity = base_entity;
+        let mut brush_entity = base_entity;
+
+        let (track_type, track_rotation) =
+            TileConnections::from_active(Connection::from_dirs(in_dir, out_dir))
+                .type_and_rotation();
+
+        commands
+            .get_entity(base_entity)
+            .unwrap()
+            .with_children(|parent| {
+                background_entity = parent
+                    .spawn((
+                        Sprite::from_image(asset_server.load("sprites/Painter_bg.png")),
+                        Name::new("painter background"),
+                    ))
+                    .id();
+                track_entity = parent
+                    .spawn((
+                        Transform::from_xyz(0.0, 0.0, 0.1)
+                            .with_rotation(Quat::from(track_rotation)),
+                        Sprite::from_image(asset_server.load(get_track_asset_path(track_type))),
+                        Name::new("painter track sprite"),
+                    ))
+                    .id();
+                brush_entity = parent
+                    .spawn((
+                        Transform::from_xyz(0.0, 0.0, 1.0),
+                        Sprite {
+                            image: asset_server.load("sprites/Painter_brush.png"),
+                            color: Color::from(color),
+                            ..default()
+                        },
+                        Name::new("painter brush sprite"),
+                    ))
+                    .id();
+            });
+
+        Self {
+            base_entity,
+            background_entity,
+            track_entity,
+            brush_entity,
+        }
+    }
+}
+
+impl TileRenderer for PainterTileRenderer {
+    fn animate_event(&mut self, event: &TileEvent, commands: &mut Commands) {
+        if let TileEvent::PaintTrain(_) = event {
+            commands
+                .entity(self.brush_entity)
+                .insert(SpriteSheetAnimationComponent::new(
+                    "sprites/Painter_brush_animation.png",
+                    "sprites/Painter_brush.png",
+                    PAINTER_BRUSH_ANIMATION_FRAMES,
+                ));
+        }
+    }
+
+    fn despawn_entities_recursive(&self, commands: &mut Commands) {
+        commands.entity(self.base_entity).despawn_recursive();
+    }
+
+    fn get_entity(&self) -> Entity {
+        self.base_entity
+    }
+
+    fn box_clone(&self) -> Box<dyn TileRenderer + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
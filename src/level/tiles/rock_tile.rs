@@ -1,21 +1,18 @@
 use bevy::prelude::*;
+use trainyard_core::tiles::tile::Tile;
 
-use super::{
-    connections::TileBorderState,
-    tile::{Tile, TileEvent, TileProcessTickResult},
-};
-use crate::level::direction::Dir;
+use super::tile::TileRenderer;
 
 #[derive(Component)]
 pub struct RockTileSpriteComponent;
 
 #[derive(Clone)]
-pub struct RockTile {
+pub struct RockTileRenderer {
     entity: Entity,
     sprite_entity: Option<Entity>,
 }
 
-impl RockTile {
+impl RockTileRenderer {
     pub fn new(entity: Entity) -> Self {
         Self {
             entity,
@@ -24,23 +21,13 @@ impl RockTile {
     }
 }
 
-impl Tile for RockTile {
-    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult {
-        let mut start_tick_events = Vec::new();
-
-        for dir_u8 in 0..4 {
-            if let Some(color) = incoming.get_train(Dir::from(dir_u8)) {
-                start_tick_events.push(TileEvent::CrashedOnEdge(color, dir_u8.into()));
-            }
-        }
-
-        TileProcessTickResult {
-            start_tick_events,
-            ..default()
-        }
-    }
-
-    fn render(&mut self, commands: &mut Commands, asset_server: &Res<AssetServer>) {
+impl TileRenderer for RockTileRenderer {
+    fn render(
+        &mut self,
+        _tile: &dyn Tile,
+        commands: &mut Commands,
+        asset_server: &Res<AssetServer>,
+    ) {
         let bundle = (
             RockTileSpriteComponent,
             Sprite::from_image(asset_server.load("sprites/Rock.png")),
@@ -71,7 +58,7 @@ impl Tile for RockTile {
         self.entity
     }
 
-    fn box_clone(&self) -> Box<dyn Tile + Send + Sync> {
+    fn box_clone(&self) -> Box<dyn TileRenderer + Send + Sync> {
         Box::new(self.clone())
     }
 }
@@ -1,26 +1,27 @@
 use bevy::prelude::*;
+use trainyard_core::{
+    direction::Dir,
+    tiles::tile::{Tile, TileEvent},
+    trains::TrainColor,
+};
 
 use super::source_tile::INNER_SPRITE_SIZE;
-use super::tile::{TileEvent, TileProcessTickResult, TileTrainActivity};
-use super::{connections::TileBorderState, tile::Tile};
-use crate::level::{direction::Dir, trains::TrainColor};
+use crate::level::color_glyphs::ColorGlyph;
 
-#[derive(Clone)]
-pub struct SinkTile {
-    pub in_dirs: [bool; 4],
-    pub trains: Vec<TrainColor>,
+use super::{tile::TileRenderer, tile_animations::SrinkToNoneAnimationComponent};
 
+#[derive(Clone)]
+pub struct SinkTileRenderer {
     pub base_entity: Entity,
     pub background_entity: Entity,
     pub entry_spout_entities: Vec<Entity>,
     pub border_entity: Entity,
-    pub inner_entities: Vec<Entity>, // these are the entities for the sprites for the small plus symbols inside the source tile
+    pub inner_entities: Vec<(TrainColor, Entity)>, // these are the entities for the sprites for the small circles inside the sink tile
 }
 
-impl SinkTile {
+impl SinkTileRenderer {
     pub fn new(
         in_dirs: [bool; 4],
-        trains: Vec<TrainColor>,
         base_entity: Entity,
         commands: &mut Commands,
         asset_server: &Res<AssetServer>,
@@ -65,8 +66,6 @@ impl SinkTile {
                     .id();
             });
         Self {
-            in_dirs,
-            trains,
             base_entity,
             background_entity,
             entry_spout_entities,
@@ -76,59 +75,17 @@ impl SinkTile {
     }
 }
 
-impl Tile for SinkTile {
-    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult {
-        let mut train_activity = Vec::new();
-        let mut mid_tick_events = Vec::new();
-        let mut start_tick_events = Vec::new();
-
-        for dir in Dir::all_dirs() {
-            if !self.in_dirs[u8::from(dir) as usize] {
-                if let Some(color) = incoming.get_train(dir) {
-                    start_tick_events.push(TileEvent::CrashedOnEdge(color, dir));
-                    continue;
-                }
-            }
-
-            if let Some(train) = incoming.get_train(dir) {
-                let mut index = 0;
-                let mut inner_entity_to_remove = None;
-                while index < self.trains.len() {
-                    if self.trains[index] == train {
-                        self.trains.remove(index);
-                        inner_entity_to_remove = Some(self.inner_entities.remove(index));
-                        break;
-                    }
-                    index += 1;
-                }
-
-                if let Some(entity) = inner_entity_to_remove {
-                    train_activity.push(TileTrainActivity {
-                        from_dir: Some(dir),
-                        to_dir: None,
-                        start_color: train,
-                        end_color: train,
-                    });
-                    mid_tick_events.push(TileEvent::ShrinkAwayInnerEntity(entity));
-                    mid_tick_events.push(TileEvent::SinkReceivedTrain(train));
-                } else {
-                    start_tick_events.push(TileEvent::CrashedOnEdge(train, dir));
-                }
-            }
-        }
-
-        TileProcessTickResult {
-            trains: train_activity,
-            mid_tick_events,
-            start_tick_events,
-            ..default()
-        }
-    }
-
-    fn render(&mut self, commands: &mut Commands, asset_server: &Res<AssetServer>) {
+impl TileRenderer for SinkTileRenderer {
+    fn render(
+        &mut self,
+        tile: &dyn Tile,
+        commands: &mut Commands,
+        asset_server: &Res<AssetServer>,
+    ) {
         if self.inner_entities.is_empty() {
-            let cap = self.trains.len();
-            for (index, color) in self.trains.iter().enumerate() {
+            let trains = tile.remaining_trains();
+            let cap = trains.len();
+            for (index, color) in trains.iter().enumerate() {
                 let num_cols = if cap <= 1 {
                     1
                 } else if cap <= 4 {
@@ -148,7 +105,7 @@ impl Tile for SinkTile {
                     (INNER_SPRITE_SIZE / 2.0) - row_size / 2.0 - row_size * curr_row as f32,
                     1.5,
                 )
-                .with_scale(Vec2::splat(1.0 / (num_cols as f32)).extend(0.0));
+                .with_scale(Vec2::splat(1.0 / (num_cols as f32)).extend(1.0));
 
                 let bundle = (
                     xf,
@@ -157,6 +114,7 @@ impl Tile for SinkTile {
                         color: Color::from(*color),
                         ..default()
                     },
+                    ColorGlyph(*color),
                     Name::new("circle sprite"),
                 );
 
@@ -165,12 +123,23 @@ impl Tile for SinkTile {
                     .unwrap()
                     .with_children(|parent| {
                         let inner_entity = parent.spawn(bundle).id();
-                        self.inner_entities.push(inner_entity);
+                        self.inner_entities.push((*color, inner_entity));
                     });
             }
         }
     }
 
+    fn animate_event(&mut self, event: &TileEvent, commands: &mut Commands) {
+        if let TileEvent::SinkReceivedTrain(train) = event {
+            if let Some(index) = self.inner_entities.iter().position(|(c, _)| c == train) {
+                let (_, entity) = self.inner_entities.remove(index);
+                commands
+                    .entity(entity)
+                    .insert(SrinkToNoneAnimationComponent(1.0));
+            }
+        }
+    }
+
     fn get_entity(&self) -> Entity {
         self.base_entity
     }
@@ -179,12 +148,12 @@ impl Tile for SinkTile {
         commands.entity(self.base_entity).despawn_recursive();
     }
 
-    fn box_clone(&self) -> Box<dyn Tile + Send + Sync> {
+    fn box_clone(&self) -> Box<dyn TileRenderer + Send + Sync> {
         Box::new(self.clone())
     }
 
     fn reset_inner_entities(&mut self, commands: &mut Commands) {
-        for entity in &self.inner_entities {
+        for (_, entity) in &self.inner_entities {
             if let Some(entity_cmds) = commands.get_entity(*entity) {
                 entity_cmds.despawn_recursive();
                 commands
@@ -194,8 +163,4 @@ impl Tile for SinkTile {
         }
         self.inner_entities = Vec::new();
     }
-
-    fn has_no_remaining_trains(&self) -> bool {
-        self.trains.is_empty()
-    }
 }
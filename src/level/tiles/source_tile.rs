@@ -1,31 +1,28 @@
 use bevy::prelude::*;
+use trainyard_core::{
+    direction::Dir,
+    tiles::tile::{Tile, TileEvent},
+    trains::TrainColor,
+};
 
-use crate::level::{direction::Dir, trains::TrainColor};
+use crate::level::color_glyphs::ColorGlyph;
 
-use super::{
-    connections::TileBorderState,
-    tile::{Tile, TileEvent, TileProcessTickResult, TileTrainActivity},
-    tile_animations::SrinkToNoneAnimationComponent,
-};
+use super::{tile::TileRenderer, tile_animations::SrinkToNoneAnimationComponent};
 
 pub const INNER_SPRITE_SIZE: f32 = 52.0;
 
 #[derive(Clone)]
-pub struct SourceTile {
-    pub out_dir: Dir,
-    pub trains: Vec<TrainColor>,
-
+pub struct SourceTileRenderer {
     pub base_entity: Entity,
     pub background_entity: Entity,
     pub exit_spout_entity: Entity,
     pub border_entity: Entity,
-    pub inner_entities: Vec<Entity>, // these are the entities for the sprites for the small plus symbols inside the source tile
+    pub inner_entities: Vec<(TrainColor, Entity)>, // these are the entities for the sprites for the small plus symbols inside the source tile
 }
 
-impl SourceTile {
+impl SourceTileRenderer {
     pub fn new(
         out_dir: Dir,
-        trains: Vec<TrainColor>,
         base_entity: Entity,
         commands: &mut Commands,
         asset_server: &Res<AssetServer>,
@@ -61,8 +58,6 @@ impl SourceTile {
             });
 
         Self {
-            out_dir,
-            trains,
             base_entity,
             background_entity,
             exit_spout_entity,
@@ -72,43 +67,17 @@ impl SourceTile {
     }
 }
 
-impl Tile for SourceTile {
-    fn process_and_output(&mut self, incoming: TileBorderState) -> TileProcessTickResult {
-        let mut start_tick_events = Vec::new();
-
-        for dir_u8 in 0..4 {
-            if let Some(color) = incoming.get_train(Dir::from(dir_u8)) {
-                start_tick_events.push(TileEvent::CrashedOnEdge(color, dir_u8.into()))
-            }
-        }
-
-        if !self.trains.is_empty() {
-            let outgoing_train_color = self.trains.remove(0);
-            let trains = vec![TileTrainActivity {
-                from_dir: None,
-                to_dir: Some(self.out_dir),
-                start_color: outgoing_train_color,
-                end_color: outgoing_train_color,
-            }];
-            return TileProcessTickResult {
-                trains,
-                mid_tick_events: vec![TileEvent::ShrinkAwayInnerEntity(
-                    self.inner_entities.remove(0),
-                )],
-                start_tick_events,
-                ..default()
-            };
-        }
-        return TileProcessTickResult {
-            start_tick_events,
-            ..default()
-        };
-    }
-
-    fn render(&mut self, commands: &mut Commands, asset_server: &Res<AssetServer>) {
+impl TileRenderer for SourceTileRenderer {
+    fn render(
+        &mut self,
+        tile: &dyn Tile,
+        commands: &mut Commands,
+        asset_server: &Res<AssetServer>,
+    ) {
         if self.inner_entities.is_empty() {
-            let cap = self.trains.len();
-            for (index, color) in self.trains.iter().enumerate() {
+            let trains = tile.remaining_trains();
+            let cap = trains.len();
+            for (index, color) in trains.iter().enumerate() {
                 let num_cols = if cap <= 1 {
                     1
                 } else if cap <= 4 {
@@ -128,7 +97,7 @@ impl Tile for SourceTile {
                     (INNER_SPRITE_SIZE / 2.0) - row_size / 2.0 - row_size * curr_row as f32,
                     1.5,
                 )
-                .with_scale(Vec2::splat(1.0 / (num_cols as f32)).extend(0.0));
+                .with_scale(Vec2::splat(1.0 / (num_cols as f32)).extend(1.0));
 
                 let bundle = (
                     xf,
@@ -137,6 +106,7 @@ impl Tile for SourceTile {
                         color: Color::from(*color),
                         ..default()
                     },
+                    ColorGlyph(*color),
                     Name::new("plus sign sprite"),
                 );
 
@@ -145,12 +115,16 @@ impl Tile for SourceTile {
                     .unwrap()
                     .with_children(|parent| {
                         let inner_entity = parent.spawn(bundle).id();
-                        self.inner_entities.push(inner_entity);
+                        self.inner_entities.push((*color, inner_entity));
                     });
             }
-        } else if self.inner_entities.len() > self.trains.len() {
-            while self.inner_entities.len() > self.trains.len() {
-                let entity = self.inner_entities.remove(0);
+        }
+    }
+
+    fn animate_event(&mut self, event: &TileEvent, commands: &mut Commands) {
+        if let TileEvent::SourceEmittedTrain(train) = event {
+            if let Some(index) = self.inner_entities.iter().position(|(c, _)| c == train) {
+                let (_, entity) = self.inner_entities.remove(index);
                 commands
                     .entity(entity)
                     .insert(SrinkToNoneAnimationComponent(1.0));
@@ -167,12 +141,12 @@ impl Tile for SourceTile {
         self.base_entity
     }
 
-    fn box_clone(&self) -> Box<dyn Tile + Send + Sync> {
+    fn box_clone(&self) -> Box<dyn TileRenderer + Send + Sync> {
         Box::new(self.clone())
     }
 
     fn reset_inner_entities(&mut self, commands: &mut Commands) {
-        for entity in &self.inner_entities {
+        for (_, entity) in &self.inner_entities {
             if let Some(entity_cmds) = commands.get_entity(*entity) {
                 entity_cmds.despawn_recursive();
                 commands
@@ -182,8 +156,4 @@ impl Tile for SourceTile {
         }
         self.inner_entities = Vec::new();
     }
-
-    fn has_no_remaining_trains(&self) -> bool {
-        self.trains.is_empty()
-    }
 }
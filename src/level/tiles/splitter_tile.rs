@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use trainyard_core::{direction::Dir, tiles::tile::TileEvent};
+
+use super::{
+    source_tile::INNER_SPRITE_SIZE, tile::TileRenderer,
+    tile_animations::SpriteSheetAnimationComponent,
+};
+use crate::TILE_SIZE_PX;
+
+pub const SPLITTER_ANIMATION_FRAMES: u32 = 5;
+
+#[derive(Clone)]
+pub struct SplitterTileRenderer {
+    pub base_entity: Entity,
+    pub background_entity: Entity,
+    pub scissors_entity: Entity,
+}
+
+impl SplitterTileRenderer {
+    pub fn new(
+        in_dir: Dir,
+        base_entity: Entity,
+        commands: &mut Commands,
+        asset_server: &Res<AssetServer>,
+    ) -> Self {
+        let mut background_entity = base_entity;
+        let mut scissors_entity = base_entity;
+
+        commands
+            .get_entity(base_entity)
+            .unwrap()
+            .with_children(|parent| {
+                // the background sprite has its entryway at the top edge
+                background_entity = parent
+                    .spawn((
+                        Transform::from_rotation(Quat::from(in_dir)),
+                        Sprite::from_image(asset_server.load("sprites/Splitter_bg.png")),
+                        Name::new("splitter background"),
+                    ))
+                    .id();
+                // the scissors sprite has no colored border on the bottom edge, which is where trains enter
+                scissors_entity = parent
+                    .spawn((
+                        Transform::from_xyz(0.0, 0.0, 1.0)
+                            .with_rotation(Quat::from(in_dir.flip()))
+                            .with_scale(Vec3::new(
+                                INNER_SPRITE_SIZE / TILE_SIZE_PX,
+                                INNER_SPRITE_SIZE / TILE_SIZE_PX,
+                                1.0,
+                            )),
+                        Sprite::from_image(asset_server.load("sprites/Splitter.png")),
+                        Name::new("splitter scissors sprite"),
+                    ))
+                    .id();
+            });
+
+        Self {
+            base_entity,
+            background_entity,
+            scissors_entity,
+        }
+    }
+}
+
+impl TileRenderer for SplitterTileRenderer {
+    fn animate_event(&mut self, event: &TileEvent, commands: &mut Commands) {
+        if let TileEvent::SplitTrain(_) = event {
+            commands
+                .entity(self.scissors_entity)
+                .insert(SpriteSheetAnimationComponent::new(
+                    "sprites/Splitter_animation.png",
+                    "sprites/Splitter.png",
+                    SPLITTER_ANIMATION_FRAMES,
+                ));
+        }
+    }
+
+    fn despawn_entities_recursive(&self, commands: &mut Commands) {
+        commands.entity(self.base_entity).despawn_recursive();
+    }
+
+    fn get_entity(&self) -> Entity {
+        self.base_entity
+    }
+
+    fn box_clone(&self) -> Box<dyn TileRenderer + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
@@ -1,6 +1,10 @@
 use bevy::prelude::*;
 use rand::random;
 
+use crate::TILE_SIZE_PX;
+
+pub const SPRITE_SHEET_FRAME_SECS: f32 = 0.05;
+
 #[derive(Component)]
 pub struct SrinkToNoneAnimationComponent(pub f32);
 
@@ -21,13 +25,37 @@ impl FloatingFadingAnimationComponent {
     }
 }
 
+/// Plays a horizontal strip of tile-sized frames once, then puts the still image back on the sprite.
+#[derive(Component)]
+pub struct SpriteSheetAnimationComponent {
+    pub sheet_path: &'static str,
+    pub still_path: &'static str,
+    pub num_frames: u32,
+    pub elapsed: f32,
+}
+
+impl SpriteSheetAnimationComponent {
+    pub fn new(sheet_path: &'static str, still_path: &'static str, num_frames: u32) -> Self {
+        Self {
+            sheet_path,
+            still_path,
+            num_frames,
+            elapsed: 0.0,
+        }
+    }
+}
+
 pub struct TileAnimationPlugin;
 
 impl Plugin for TileAnimationPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (shrink_to_zero_animation_system, float_fade_animation_system),
+            (
+                shrink_to_zero_animation_system,
+                float_fade_animation_system,
+                sprite_sheet_animation_system,
+            ),
         );
     }
 }
@@ -86,3 +114,46 @@ fn float_fade_animation_system(
         }
     }
 }
+
+fn sprite_sheet_animation_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Sprite, &mut SpriteSheetAnimationComponent)>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    for (entity, mut sprite, mut animation) in query.iter_mut() {
+        let frame = (animation.elapsed / SPRITE_SHEET_FRAME_SECS) as u32;
+
+        if frame >= animation.num_frames {
+            sprite.image = asset_server.load(animation.still_path);
+            sprite.texture_atlas = None;
+            commands
+                .entity(entity)
+                .remove::<SpriteSheetAnimationComponent>();
+            continue;
+        }
+
+        match sprite.texture_atlas.as_mut() {
+            Some(atlas) => {
+                atlas.index = frame as usize;
+            }
+            None => {
+                let layout = TextureAtlasLayout::from_grid(
+                    UVec2::splat(TILE_SIZE_PX as u32),
+                    animation.num_frames,
+                    1,
+                    None,
+                    None,
+                );
+                sprite.image = asset_server.load(animation.sheet_path);
+                sprite.texture_atlas = Some(TextureAtlas {
+                    layout: texture_atlas_layouts.add(layout),
+                    index: frame as usize,
+                });
+            }
+        }
+
+        animation.elapsed += time.delta_secs();
+    }
+}
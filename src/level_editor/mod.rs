@@ -0,0 +1,529 @@
+pub mod my_levels;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use my_levels::{save_my_levels, UnplayableMyLevels, MY_LEVELS_CITY};
+use trainyard_core::{
+    direction::Dir,
+    level::{LevelLoadInfo, PainterInfo, SinkInfo, SourceInfo, SplitterInfo},
+    lint::lint_level,
+    trains::TrainColor,
+    yard_code::MAX_SOURCE_TRAINS,
+    MAX_YARD_SIZE,
+};
+
+use crate::{
+    input::InputAction,
+    level::{
+        cursor::CursorComponent,
+        loader::StockLevelInfos,
+        persistence::GameLevelProgress,
+        replace_yard, spawn_yard,
+        yard::{Yard, YardEditedState},
+        CurrentLevelName, LevelState, WinLevelEvent,
+    },
+    ui::{level_picker::LevelPickerPage, UIState},
+    TILE_SIZE_PX,
+};
+
+/// Sinks show their trains in a grid of up to 4x4.
+pub const MAX_SINK_TRAINS: usize = 16;
+
+/// What clicking on the yard does in the level editor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditorTool {
+    /// Draws track like when playing a level, for trying the level out.
+    Track,
+    #[default]
+    Source,
+    Sink,
+    Rock,
+    Painter,
+    Splitter,
+    /// Takes trains off of sources and sinks one at a time, and removes anything else.
+    Erase,
+}
+
+impl EditorTool {
+    pub const ALL: [EditorTool; 7] = [
+        EditorTool::Track,
+        EditorTool::Erase,
+        EditorTool::Source,
+        EditorTool::Sink,
+        EditorTool::Rock,
+        EditorTool::Painter,
+        EditorTool::Splitter,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorTool::Track => "Track",
+            EditorTool::Source => "Source",
+            EditorTool::Sink => "Sink",
+            EditorTool::Rock => "Rock",
+            EditorTool::Painter => "Painter",
+            EditorTool::Splitter => "Splitter",
+            EditorTool::Erase => "Erase",
+        }
+    }
+}
+
+/// The level being made in the level editor, and the pieces that clicking on it puts down.
+#[derive(Resource)]
+pub struct LevelEditor {
+    pub level: LevelLoadInfo,
+    pub tool: EditorTool,
+    /// The color of the trains that sources and sinks are given, and of painters.
+    pub color: TrainColor,
+    /// Which way sources send trains, sinks take them in, and painters and splitters face.
+    pub dir: Dir,
+    /// What happened last, like a problem that stopped the level from being saved.
+    pub message: String,
+}
+
+impl Default for LevelEditor {
+    fn default() -> Self {
+        LevelEditor {
+            level: LevelLoadInfo::default(),
+            tool: EditorTool::default(),
+            color: TrainColor::Red,
+            dir: Dir::Right,
+            message: String::new(),
+        }
+    }
+}
+
+impl LevelEditor {
+    /// The yard for the level, with `tracks` drawn on it, or its solution if there aren't any.
+    pub fn to_yard(&self, tracks: Option<&[u8]>) -> trainyard_core::yard::Yard {
+        let mut sim = self.level.to_yard();
+        if let Some(tracks) = tracks.or(self.level.solution.as_deref()) {
+            sim.set_progress(tracks);
+        }
+        sim
+    }
+
+    /// Uses the selected tool on the tile at `position`, as (row, col).
+    /// Clicking a source or sink again gives it another train, and clicking a painter that's already the selected
+    /// color turns its second end around, for painters on corners.
+    pub fn use_tool(&mut self, position: (u8, u8)) {
+        let (color, dir) = (self.color, self.dir);
+        let level = &mut self.level;
+        match self.tool {
+            EditorTool::Track => return,
+            EditorTool::Erase => return self.take_away(position),
+            EditorTool::Source => match level.sources.iter_mut().find(|s| s.position == position) {
+                Some(source) => {
+                    if source.trains.len() < MAX_SOURCE_TRAINS {
+                        source.trains.push(color);
+                    }
+                    source.out_dir = dir;
+                }
+                None => {
+                    level.remove_tile(position);
+                    level.sources.push(SourceInfo {
+                        trains: vec![color],
+                        out_dir: dir,
+                        position,
+                    });
+                }
+            },
+            EditorTool::Sink => match level.sinks.iter_mut().find(|s| s.position == position) {
+                Some(sink) => {
+                    if sink.trains.len() < MAX_SINK_TRAINS {
+                        sink.trains.push(color);
+                    }
+                    if !sink.in_dirs.contains(&dir) {
+                        sink.in_dirs.push(dir);
+                    }
+                }
+                None => {
+                    level.remove_tile(position);
+                    level.sinks.push(SinkInfo {
+                        trains: vec![color],
+                        in_dirs: vec![dir],
+                        position,
+                    });
+                }
+            },
+            EditorTool::Rock => {
+                level.remove_tile(position);
+                level.rocks.push(position);
+            }
+            EditorTool::Painter => {
+                let painter = level.painters.iter_mut().find(|p| p.position == position);
+                match painter {
+                    Some(painter) if painter.color != color => painter.color = color,
+                    Some(painter) => {
+                        painter.out_dir = painter.out_dir.rotate_cw();
+                        if painter.out_dir == painter.in_dir {
+                            painter.out_dir = painter.out_dir.rotate_cw();
+                        }
+                    }
+                    None => {
+                        level.remove_tile(position);
+                        level.painters.push(PainterInfo {
+                            color,
+                            in_dir: dir,
+                            out_dir: dir.flip(),
+                            position,
+                        });
+                    }
+                }
+            }
+            EditorTool::Splitter => {
+                level.remove_tile(position);
+                level.splitters.push(SplitterInfo {
+                    in_dir: dir,
+                    position,
+                });
+            }
+        }
+        self.level_changed();
+    }
+
+    /// Takes a train off of the source or sink at `position`, or the whole tile if it's down to its last train or
+    /// isn't a source or sink.
+    pub fn take_away(&mut self, position: (u8, u8)) {
+        let level = &mut self.level;
+        let source = level.sources.iter_mut().find(|s| s.position == position);
+        if let Some(source) = source.filter(|s| s.trains.len() > 1) {
+            source.trains.pop();
+        } else if let Some(sink) = level
+            .sinks
+            .iter_mut()
+            .find(|s| s.position == position)
+            .filter(|s| s.trains.len() > 1)
+        {
+            sink.trains.pop();
+        } else {
+            level.remove_tile(position);
+        }
+        self.level_changed();
+    }
+
+    /// Makes the yard `width` by `height`, taking away anything that no longer fits on it. Rows come off of the top
+    /// and columns off of the right. Returns whether the size changed.
+    pub fn resize(&mut self, width: u8, height: u8) -> bool {
+        let width = width.clamp(1, MAX_YARD_SIZE);
+        let height = height.clamp(1, MAX_YARD_SIZE);
+        if (width, height) == (self.level.width, self.level.height) {
+            return false;
+        }
+        for (_, (row, col)) in self.level.tiles() {
+            if row >= height || col >= width {
+                self.level.remove_tile((row, col));
+            }
+        }
+        self.level.width = width;
+        self.level.height = height;
+        self.level_changed();
+        true
+    }
+
+    fn level_changed(&mut self) {
+        // tracks that beat the old level might not beat this one
+        self.level.solution = None;
+        self.message.clear();
+    }
+}
+
+#[derive(Event, Clone, PartialEq, Eq)]
+pub enum LevelEditorEvent {
+    /// Opens the editor on the level in My Levels with this name, or on a new level.
+    Open(Option<String>),
+    SelectTool(EditorTool),
+    SelectColor(TrainColor),
+    /// Turns the selected direction clockwise.
+    Turn,
+    /// Grows or shrinks the yard by this many columns and rows.
+    Resize {
+        width: i8,
+        height: i8,
+    },
+    Save,
+}
+
+/// Sent when the level in the editor has changed, so that its yard needs to be built again.
+#[derive(Event, Default)]
+pub struct LevelEditedEvent;
+
+pub struct LevelEditorPlugin;
+impl Plugin for LevelEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelEditor>()
+            .init_resource::<UnplayableMyLevels>()
+            .add_event::<LevelEditorEvent>()
+            .add_event::<LevelEditedEvent>()
+            .add_systems(Startup, my_levels::load_my_levels)
+            .add_systems(OnEnter(UIState::LevelEditor), begin_editing)
+            .add_systems(
+                Update,
+                (
+                    handle_level_editor_events.run_if(on_event::<LevelEditorEvent>),
+                    (
+                        turn_from_keypress,
+                        edit_level_on_click.run_if(in_state(LevelState::Editing)),
+                        respawn_edited_yard.run_if(on_event::<LevelEditedEvent>),
+                        record_solution.run_if(on_event::<WinLevelEvent>),
+                        show_cursor_when_drawing,
+                    )
+                        .chain()
+                        .run_if(in_state(UIState::LevelEditor)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Whether the player can draw track: always while playing a level, but only with the track tool in the level
+/// editor.
+pub fn player_can_draw_track(ui_state: Res<State<UIState>>, editor: Res<LevelEditor>) -> bool {
+    *ui_state.get() != UIState::LevelEditor || editor.tool == EditorTool::Track
+}
+
+fn handle_level_editor_events(
+    mut events: EventReader<LevelEditorEvent>,
+    (mut levels, unplayable): (ResMut<StockLevelInfos>, Res<UnplayableMyLevels>),
+    mut progress: ResMut<GameLevelProgress>,
+    mut editor: ResMut<LevelEditor>,
+    mut picker_page: ResMut<LevelPickerPage>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+    mut edited_ev_writer: EventWriter<LevelEditedEvent>,
+) {
+    for event in events.read() {
+        match event {
+            LevelEditorEvent::Open(level_name) => {
+                let level = level_name
+                    .as_ref()
+                    .and_then(|name| levels.find_level(name))
+                    .cloned()
+                    .unwrap_or_else(|| new_level(&levels));
+                *editor = LevelEditor { level, ..default() };
+                next_ui_state.set(UIState::LevelEditor);
+            }
+            LevelEditorEvent::SelectTool(tool) => editor.tool = *tool,
+            LevelEditorEvent::SelectColor(color) => editor.color = *color,
+            LevelEditorEvent::Turn => editor.dir = editor.dir.rotate_cw(),
+            LevelEditorEvent::Resize { width, height } => {
+                let width = editor.level.width.saturating_add_signed(*width);
+                let height = editor.level.height.saturating_add_signed(*height);
+                if editor.resize(width, height) {
+                    edited_ev_writer.send_default();
+                }
+            }
+            LevelEditorEvent::Save => {
+                let name = editor.level.name.clone();
+                editor.message = match save_level(&mut levels, &unplayable, &editor.level) {
+                    Ok(layout_changed) => {
+                        // whatever was drawn on the old version of the level doesn't mean anything anymore
+                        if layout_changed {
+                            progress.0.remove(&name);
+                        }
+                        *picker_page = LevelPickerPage::City(String::from(MY_LEVELS_CITY));
+                        format!("Saved to My Levels as \"{}\"", name)
+                    }
+                    Err(e) => format!("Couldn't save: {}", e),
+                };
+            }
+        }
+    }
+}
+
+/// A new, empty level, with a name that no other level has.
+fn new_level(levels: &StockLevelInfos) -> LevelLoadInfo {
+    let name = (1..)
+        .map(|number| format!("My level {}", number))
+        .find(|name| levels.find_level(name).is_none())
+        .unwrap();
+    LevelLoadInfo {
+        name,
+        city: String::from(MY_LEVELS_CITY),
+        ..default()
+    }
+}
+
+/// Checks that `level` can be played, then puts it in My Levels and writes them all to disk. Returns whether it was
+/// laid out differently from the level it replaced, if there was one.
+fn save_level(
+    levels: &mut StockLevelInfos,
+    unplayable: &UnplayableMyLevels,
+    level: &LevelLoadInfo,
+) -> Result<bool, String> {
+    if level.sources.is_empty() || level.sinks.is_empty() {
+        return Err(String::from("a level needs a source and a sink"));
+    }
+    if let Some(error) = lint_level(level).first() {
+        return Err(error.to_string());
+    }
+
+    let layout_changed = match levels.0.iter_mut().find(|other| other.name == level.name) {
+        Some(other) => {
+            let layout_changed = !same_layout(other, level);
+            *other = level.clone();
+            layout_changed
+        }
+        None => {
+            levels.0.push(level.clone());
+            true
+        }
+    };
+    save_my_levels(levels, unplayable)?;
+    Ok(layout_changed)
+}
+
+/// Whether the same tracks would play out the same way on `a` as on `b`, whatever they're called or rated.
+fn same_layout(a: &LevelLoadInfo, b: &LevelLoadInfo) -> bool {
+    a.width == b.width
+        && a.height == b.height
+        && a.sources == b.sources
+        && a.sinks == b.sinks
+        && a.rocks == b.rocks
+        && a.painters == b.painters
+        && a.splitters == b.splitters
+}
+
+fn begin_editing(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    editor: Res<LevelEditor>,
+    mut curr_lvl_name: ResMut<CurrentLevelName>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+) {
+    // the level isn't one of the game's levels until it's saved, so nothing about playing it gets saved either
+    curr_lvl_name.0 = None;
+    spawn_yard(&mut commands, &asset_server, editor.to_yard(None));
+    next_level_state.set(LevelState::Editing);
+}
+
+fn turn_from_keypress(
+    actions: Res<ButtonInput<InputAction>>,
+    mut editor_ev_writer: EventWriter<LevelEditorEvent>,
+) {
+    if actions.just_pressed(InputAction::EditorTurn) {
+        editor_ev_writer.send(LevelEditorEvent::Turn);
+    }
+}
+
+/// Left clicking a tile uses the selected tool on it, and right clicking takes something away from it.
+fn edit_level_on_click(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    yard_query: Query<&GlobalTransform, With<Yard>>,
+    mut editor: ResMut<LevelEditor>,
+    mut edited_ev_writer: EventWriter<LevelEditedEvent>,
+) {
+    let left_click = mouse_input.just_pressed(MouseButton::Left);
+    let right_click = mouse_input.just_pressed(MouseButton::Right);
+    if !(left_click || right_click) || editor.tool == EditorTool::Track {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform)), Ok(yard_transform)) = (
+        window_query.get_single(),
+        camera_query.get_single(),
+        yard_query.get_single(),
+    ) else {
+        return;
+    };
+    let Some(mouse_position) = window
+        .cursor_position()
+        .and_then(|position| camera.viewport_to_world_2d(camera_transform, position).ok())
+    else {
+        return;
+    };
+
+    // the yard is scaled down when it's too big to fit on screen
+    let yard_local_coords = yard_transform
+        .affine()
+        .inverse()
+        .transform_point3(mouse_position.extend(0.0))
+        .truncate();
+    let c = (yard_local_coords.x / TILE_SIZE_PX).floor() as i32;
+    let r = (yard_local_coords.y / TILE_SIZE_PX).floor() as i32;
+    if c < 0 || c >= editor.level.width as i32 || r < 0 || r >= editor.level.height as i32 {
+        return;
+    }
+
+    let position = (r as u8, c as u8);
+    if left_click {
+        editor.use_tool(position);
+    } else {
+        editor.take_away(position);
+    }
+    edited_ev_writer.send_default();
+}
+
+/// Builds the yard again after the level has changed, keeping the tracks that were drawn on it (as long as they're
+/// still inside the yard, if it changed size).
+fn respawn_edited_yard(
+    mut events: EventReader<LevelEditedEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    editor: Res<LevelEditor>,
+    yard_query: Query<(Entity, &Yard)>,
+    cursor_query: Query<Entity, With<CursorComponent>>,
+) {
+    events.clear();
+    let Ok((yard_entity, yard)) = yard_query.get_single() else {
+        return;
+    };
+    let old_width = yard.sim.width();
+    let (width, height) = (editor.level.width as usize, editor.level.height as usize);
+    let old_tracks = yard.sim.get_progress();
+    let mut tracks = vec![0; width * height];
+    for row in 0..height.min(yard.sim.height()) {
+        for col in 0..width.min(old_width) {
+            tracks[row * width + col] = old_tracks[row * old_width + col];
+        }
+    }
+    replace_yard(
+        &mut commands,
+        &asset_server,
+        yard_entity,
+        cursor_query.get_single().ok(),
+        editor.to_yard(Some(&tracks)),
+    );
+    commands.spawn(AudioPlayer::<AudioSource>(
+        asset_server.load("audio/draw_track.ogg"),
+    ));
+}
+
+/// Beating the level while trying it out keeps the tracks that did it, as the level's solution.
+fn record_solution(
+    mut win_events: EventReader<WinLevelEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut editor: ResMut<LevelEditor>,
+    yard_edited_state_query: Query<&YardEditedState>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+) {
+    win_events.clear();
+    let Ok(edited_yard) = yard_edited_state_query.get_single() else {
+        return;
+    };
+    next_level_state.set(LevelState::Editing);
+
+    // the trains can tick again before they stop
+    let solution = edited_yard.0.sim.get_progress();
+    if editor.level.solution.as_ref() != Some(&solution) {
+        editor.level.solution = Some(solution);
+        editor.message = String::from("Beaten! Saving keeps these tracks as the solution.");
+        commands.spawn(AudioPlayer::<AudioSource>(
+            asset_server.load("audio/win_level.ogg"),
+        ));
+    }
+}
+
+fn show_cursor_when_drawing(
+    editor: Res<LevelEditor>,
+    mut cursor_query: Query<&mut Visibility, With<CursorComponent>>,
+) {
+    let visibility = if editor.tool == EditorTool::Track {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut cursor_visibility in cursor_query.iter_mut() {
+        cursor_visibility.set_if_neq(visibility);
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use trainyard_core::{level::LevelLoadInfo, lint::lint_level};
+
+use std::{fs, path::PathBuf};
+
+use crate::level::{
+    loader::StockLevelInfos,
+    persistence::{write_atomically, SAVE_DIR_NAME},
+};
+
+/// The city that levels made in the level editor are put under.
+pub const MY_LEVELS_CITY: &str = "My Levels";
+
+/// Bump this whenever the layout of `MyLevelsFile` changes in a way that old files can't be read as-is.
+pub const MY_LEVELS_FILE_VERSION: u32 = 1;
+pub const MY_LEVELS_FILE_NAME: &str = "my_levels.json";
+
+#[derive(Serialize, Deserialize)]
+struct MyLevelsFile {
+    version: u32,
+    levels: Vec<LevelLoadInfo>,
+}
+
+/// Levels from the my levels file that have problems that would stop them from being played, such as tiles outside of
+/// the yard. They're kept out of the game, but are written back with the other levels so that saving doesn't lose them.
+#[derive(Resource, Default)]
+pub struct UnplayableMyLevels(pub Vec<LevelLoadInfo>);
+
+pub fn my_levels_file_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(SAVE_DIR_NAME).join(MY_LEVELS_FILE_NAME))
+}
+
+/// Adds the levels that were saved from the level editor to the game's levels.
+pub fn load_my_levels(
+    mut levels: ResMut<StockLevelInfos>,
+    mut unplayable: ResMut<UnplayableMyLevels>,
+) {
+    let Some(path) = my_levels_file_path() else {
+        warn!("could not find a data directory, levels from the level editor will not be loaded");
+        return;
+    };
+    if !path.exists() {
+        return;
+    }
+
+    let my_levels = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            serde_json::from_str::<MyLevelsFile>(&contents).map_err(|e| e.to_string())
+        })
+        .and_then(|file| {
+            if file.version > MY_LEVELS_FILE_VERSION {
+                Err(format!(
+                    "my levels file version {} is newer than the supported version {}",
+                    file.version, MY_LEVELS_FILE_VERSION
+                ))
+            } else {
+                Ok(file.levels)
+            }
+        });
+
+    match my_levels {
+        Ok(my_levels) => {
+            info!("loaded {} of my levels from {:?}", my_levels.len(), path);
+            for level in my_levels {
+                // the file can be edited by hand, and a level that doesn't lint could panic when its yard is built
+                let errors = lint_level(&level);
+                if !errors.is_empty() {
+                    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                    warn!(
+                        "skipping a level that can't be played: {}",
+                        errors.join("; ")
+                    );
+                    unplayable.0.push(level);
+                    continue;
+                }
+                // a level with the same name as one that's already in the game would share its progress
+                if levels.find_level(&level.name).is_none() {
+                    levels.0.push(LevelLoadInfo {
+                        city: String::from(MY_LEVELS_CITY),
+                        ..level
+                    });
+                }
+            }
+        }
+        // unlike level progress, the file is left where it is: these levels can't be made again by replaying them
+        Err(e) => error!("could not read my levels from {:?}: {}", path, e),
+    }
+}
+
+/// Writes every level in `MY_LEVELS_CITY` to disk, along with the unplayable levels that weren't replaced by one of
+/// them.
+pub fn save_my_levels(
+    levels: &StockLevelInfos,
+    unplayable: &UnplayableMyLevels,
+) -> Result<(), String> {
+    let path = my_levels_file_path().ok_or("could not find a data directory")?;
+    let mut my_levels: Vec<LevelLoadInfo> =
+        levels.levels_in_city(MY_LEVELS_CITY).cloned().collect();
+    my_levels.extend(
+        unplayable
+            .0
+            .iter()
+            .filter(|level| levels.find_level(&level.name).is_none())
+            .cloned(),
+    );
+    let file = MyLevelsFile {
+        version: MY_LEVELS_FILE_VERSION,
+        levels: my_levels,
+    };
+    write_atomically(&path, &serde_json::to_vec_pretty(&file).unwrap())
+        .map_err(|e| e.to_string())?;
+    info!("saved my levels to {:?}", path);
+    Ok(())
+}
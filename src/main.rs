@@ -1,11 +1,15 @@
+pub mod input;
 pub mod level;
+pub mod level_editor;
+pub mod settings;
+pub mod tutorial;
 pub mod ui;
 
 use bevy::input::common_conditions::input_toggle_active;
 use bevy::prelude::*;
+use input::InputAction;
+use trainyard_core::{NUM_COLS, NUM_ROWS};
 
-const NUM_ROWS: u8 = 7;
-const NUM_COLS: u8 = 7;
 const TILE_SIZE_PX: f32 = 96.0;
 
 fn main() {
@@ -19,8 +23,12 @@ fn main() {
         ui::TrainyardUIPlugin,
         level::LevelPlugin,
         level::loader::LevelLoaderPlugin,
+        tutorial::TutorialPlugin,
+        settings::SettingsPlugin,
+        input::InputActionPlugin,
+        level_editor::LevelEditorPlugin,
         bevy_inspector_egui::quick::WorldInspectorPlugin::default()
-            .run_if(input_toggle_active(false, KeyCode::Escape)),
+            .run_if(input_toggle_active(false, InputAction::InspectorToggle)),
     ))
     .add_systems(Startup, spawn_camera)
     .add_systems(Update, despawn_empty_audio_sinks);
@@ -0,0 +1,381 @@
+//! The player's settings, which are kept in a config file of their own and applied when the game starts.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{MonitorSelection, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::KeyBindings,
+    level::{
+        color_glyphs::ColorblindSettings,
+        persistence::{backup_path, write_atomically, SAVE_DIR_NAME},
+    },
+    ui::{
+        level::speed_slider::{TrainSpeed, DEFAULT_TRAIN_SPEED},
+        level_picker::CityUnlockRule,
+    },
+};
+
+/// Bump this whenever the layout of `Settings` changes in a way that old config files can't be read as-is.
+pub const SETTINGS_FILE_VERSION: u32 = 1;
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+/// The window sizes that can be chosen from, besides fullscreen.
+pub const WINDOW_SIZES: [(u32, u32); 3] = [(1280, 720), (1600, 900), (1920, 1080)];
+/// The choices for `Settings::stars_per_city`. Only 6 stars can be earned before the second city, and 11 before the
+/// third, so 5 is the most that still lets every stock city be opened.
+pub const STARS_PER_CITY_CHOICES: [Option<u32>; 3] = [None, Some(3), Some(5)];
+
+#[derive(Resource, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Scales every sound, on top of `sfx_volume` or `ui_volume`.
+    pub master_volume: f32,
+    /// The volume of the sounds of the yard, like trains arriving and crashing.
+    pub sfx_volume: f32,
+    /// The volume of the sounds of pressing buttons.
+    pub ui_volume: f32,
+    /// The speed trains run at when the game starts, until it's changed with the speed slider.
+    pub default_train_speed: f32,
+    pub window: WindowChoice,
+    pub colorblind: ColorblindSettings,
+    pub key_bindings: KeyBindings,
+    /// How many stars each city asks for before it opens, as in `CityUnlockRule`. `None` leaves every city open.
+    pub stars_per_city: Option<u32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            ui_volume: 1.0,
+            default_train_speed: DEFAULT_TRAIN_SPEED,
+            window: WindowChoice::default(),
+            colorblind: ColorblindSettings::default(),
+            key_bindings: KeyBindings::default(),
+            stars_per_city: None,
+        }
+    }
+}
+
+impl Settings {
+    /// How loud a sound that's of the kind of `sound` is played, relative to its own volume.
+    pub fn volume(&self, sound: SoundKind) -> f32 {
+        self.master_volume
+            * match sound {
+                SoundKind::Sfx => self.sfx_volume,
+                SoundKind::Ui => self.ui_volume,
+            }
+    }
+
+    /// Moves `stars_per_city` on to the next of `STARS_PER_CITY_CHOICES`, going back to the first after the last.
+    pub fn next_stars_per_city(&mut self) {
+        let index = STARS_PER_CITY_CHOICES
+            .iter()
+            .position(|choice| *choice == self.stars_per_city)
+            .map_or(0, |index| (index + 1) % STARS_PER_CITY_CHOICES.len());
+        self.stars_per_city = STARS_PER_CITY_CHOICES[index];
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowChoice {
+    Windowed { width: u32, height: u32 },
+    Fullscreen,
+}
+
+impl Default for WindowChoice {
+    fn default() -> Self {
+        let (width, height) = WINDOW_SIZES[0];
+        WindowChoice::Windowed { width, height }
+    }
+}
+
+impl WindowChoice {
+    /// The choice after this one, going through the window sizes from smallest to largest and then fullscreen.
+    pub fn next(self) -> Self {
+        let index = match self {
+            WindowChoice::Windowed { width, height } => WINDOW_SIZES
+                .iter()
+                .position(|size| *size == (width, height))
+                .map_or(0, |index| index + 1),
+            WindowChoice::Fullscreen => 0,
+        };
+        match WINDOW_SIZES.get(index) {
+            Some(&(width, height)) => WindowChoice::Windowed { width, height },
+            None => WindowChoice::Fullscreen,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            WindowChoice::Windowed { width, height } => format!("{}x{}", width, height),
+            WindowChoice::Fullscreen => String::from("Fullscreen"),
+        }
+    }
+}
+
+/// Which of the volumes in `Settings` a sound is played at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundKind {
+    Sfx,
+    Ui,
+}
+
+/// Marks a sound as being played at the UI volume. Sounds without it are played at the SFX volume.
+#[derive(Component)]
+pub struct UiSound;
+
+/// The on-disk representation of `Settings`.
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+    version: u32,
+    settings: Settings,
+}
+
+/// Set when the settings file was written by a newer version of the game. Like `SaveFileIsNewer`, it stops this
+/// version from overwriting it, so settings changed in the meantime aren't saved.
+#[derive(Resource, Default)]
+pub struct SettingsFileIsNewer(pub bool);
+
+/// Just enough of a `SettingsFile` to tell which version wrote it.
+#[derive(Deserialize)]
+struct SettingsFileVersion {
+    version: u32,
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let (settings, settings_file_is_newer) = match settings_file_path() {
+            Some(path) => load_settings(&path),
+            None => {
+                warn!("could not find a config directory, settings will not be loaded");
+                (Settings::default(), SettingsFileIsNewer(false))
+            }
+        };
+        app.insert_resource(settings)
+            .insert_resource(settings_file_is_newer)
+            .add_observer(apply_sound_volume)
+            .add_systems(Startup, apply_default_train_speed)
+            .add_systems(
+                Update,
+                (
+                    apply_window_choice,
+                    apply_colorblind_settings,
+                    apply_city_unlock_rule,
+                )
+                    .run_if(resource_changed::<Settings>),
+            )
+            .add_systems(
+                Last,
+                save_settings.run_if(
+                    resource_changed::<Settings>
+                        .and(not(resource_added::<Settings>))
+                        .and(|newer: Res<SettingsFileIsNewer>| !newer.0),
+                ),
+            );
+    }
+}
+
+pub fn settings_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(SAVE_DIR_NAME).join(SETTINGS_FILE_NAME))
+}
+
+fn load_settings(path: &Path) -> (Settings, SettingsFileIsNewer) {
+    if !path.exists() {
+        return (Settings::default(), SettingsFileIsNewer(false));
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string());
+    let version = contents
+        .as_deref()
+        .ok()
+        .and_then(|contents| serde_json::from_str::<SettingsFileVersion>(contents).ok());
+    if let Some(SettingsFileVersion { version }) = version {
+        if version > SETTINGS_FILE_VERSION {
+            warn!(
+                "{:?} was written by a newer version of the game (settings file version {}, this version reads up to \
+                 {}), so it will be left as it is and settings will not be saved",
+                path, version, SETTINGS_FILE_VERSION
+            );
+            return (Settings::default(), SettingsFileIsNewer(true));
+        }
+    }
+
+    let settings_file = contents.and_then(|contents| {
+        serde_json::from_str::<SettingsFile>(&contents).map_err(|e| e.to_string())
+    });
+    match settings_file {
+        Ok(mut settings_file) => {
+            info!("loaded settings from {:?}", path);
+            settings_file.settings.key_bindings.add_missing_defaults();
+            (settings_file.settings, SettingsFileIsNewer(false))
+        }
+        Err(e) => {
+            // the same as with level progress, the unreadable file is kept around and the defaults are used instead
+            let backup_path = backup_path(path);
+            warn!(
+                "could not read settings from {:?} ({}), moving it to {:?}",
+                path, e, backup_path
+            );
+            if let Err(e) = fs::rename(path, &backup_path) {
+                error!("could not back up unreadable settings file: {}", e);
+            }
+            (Settings::default(), SettingsFileIsNewer(false))
+        }
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    let Some(path) = settings_file_path() else {
+        return;
+    };
+    let settings_file = SettingsFile {
+        version: SETTINGS_FILE_VERSION,
+        settings: settings.clone(),
+    };
+
+    match write_atomically(&path, &serde_json::to_vec_pretty(&settings_file).unwrap()) {
+        Ok(()) => info!("saved settings to {:?}", path),
+        Err(e) => error!("could not save settings to {:?}: {}", path, e),
+    }
+}
+
+fn apply_default_train_speed(settings: Res<Settings>, mut train_speed: ResMut<TrainSpeed>) {
+    train_speed.0 = settings.default_train_speed;
+}
+
+fn apply_window_choice(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+    match settings.window {
+        WindowChoice::Windowed { width, height } => {
+            if window.mode != WindowMode::Windowed {
+                window.mode = WindowMode::Windowed;
+            }
+            if window.resolution.width() != width as f32
+                || window.resolution.height() != height as f32
+            {
+                window.resolution.set(width as f32, height as f32);
+            }
+        }
+        WindowChoice::Fullscreen => {
+            let mode = WindowMode::BorderlessFullscreen(MonitorSelection::Current);
+            if window.mode != mode {
+                window.mode = mode;
+            }
+        }
+    }
+}
+
+fn apply_colorblind_settings(
+    settings: Res<Settings>,
+    mut colorblind_settings: ResMut<ColorblindSettings>,
+) {
+    if *colorblind_settings != settings.colorblind {
+        *colorblind_settings = settings.colorblind.clone();
+    }
+}
+
+fn apply_city_unlock_rule(settings: Res<Settings>, mut unlock_rule: ResMut<CityUnlockRule>) {
+    unlock_rule.set_if_neq(CityUnlockRule {
+        stars_per_city: settings.stars_per_city,
+    });
+}
+
+/// Turns every sound down by the volume it's played at, as it starts playing. Sounds are only ever played once, so
+/// there's no need to change the volume of the ones that are already playing.
+fn apply_sound_volume(
+    trigger: Trigger<OnAdd, AudioPlayer>,
+    settings: Res<Settings>,
+    mut sound_query: Query<(&mut PlaybackSettings, Has<UiSound>)>,
+) {
+    let Ok((mut playback, is_ui_sound)) = sound_query.get_mut(trigger.entity()) else {
+        return;
+    };
+    let sound = if is_ui_sound {
+        SoundKind::Ui
+    } else {
+        SoundKind::Sfx
+    };
+    playback.volume = Volume::new(playback.volume.get() * settings.volume(sound));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_dir(test_name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("trainyard-{}-{}", test_name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn settings_from_newer_versions_are_left_alone() {
+        let dir = empty_dir("newer-settings");
+        let path = dir.join(SETTINGS_FILE_NAME);
+        let contents = format!(
+            r#"{{"version": {}, "settings": {{"master_volume": "loud"}}}}"#,
+            SETTINGS_FILE_VERSION + 1
+        );
+        fs::write(&path, &contents).unwrap();
+
+        let (settings, newer) = load_settings(&path);
+        assert!(newer.0);
+        assert_eq!(settings, Settings::default());
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+        assert!(!path.with_extension("json.bak").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_settings_are_backed_up_without_overwriting_older_backups() {
+        let dir = empty_dir("unreadable-settings");
+        let path = dir.join(SETTINGS_FILE_NAME);
+        let current = format!(
+            r#"{{"version": {}, "settings": {{"master_volume": 0.5}}}}"#,
+            SETTINGS_FILE_VERSION
+        );
+        fs::write(&path, &current).unwrap();
+        let (settings, newer) = load_settings(&path);
+        assert!(!newer.0);
+        assert_eq!(settings.master_volume, 0.5);
+        assert!(path.exists());
+
+        for contents in ["first", "second"] {
+            fs::write(&path, contents).unwrap();
+            let (settings, newer) = load_settings(&path);
+            assert!(!newer.0);
+            assert_eq!(settings, Settings::default());
+            assert!(!path.exists());
+        }
+        let mut backups: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        backups.sort();
+        assert_eq!(backups, ["first", "second"]);
+        assert_eq!(
+            fs::read_to_string(path.with_extension("json.bak")).unwrap(),
+            "first"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,280 @@
+pub mod player;
+pub mod script;
+
+use bevy::prelude::*;
+use player::TutorialPlayer;
+use script::{Tutorial, YardReset};
+
+use crate::{
+    level::{
+        cursor::CursorComponent,
+        loader::StockLevelInfos,
+        replace_yard, spawn_yard,
+        yard::{Yard, YardEditedState},
+        CurrentLevelName, LevelState,
+    },
+    ui::{
+        level::speed_slider::{TrainSpeed, DEFAULT_TRAIN_SPEED},
+        level_picker::StartLevelEvent,
+        UIState,
+    },
+};
+
+pub const TUTORIAL_DATA: &str = include_str!("../../assets/tutorials.xml");
+
+#[derive(Resource, Debug)]
+pub struct Tutorials(pub Vec<Tutorial>);
+
+impl Tutorials {
+    pub fn find(&self, slug: &str) -> Option<&Tutorial> {
+        self.0.iter().find(|tutorial| tutorial.slug == slug)
+    }
+
+    /// The tutorial that's shown right before `level_name`, if there is one.
+    pub fn before_level(&self, level_name: &str) -> Option<&Tutorial> {
+        self.0
+            .iter()
+            .find(|tutorial| tutorial.before_level.as_deref() == Some(level_name))
+    }
+}
+
+#[derive(Event, Clone, PartialEq, Eq)]
+pub enum TutorialEvent {
+    /// Plays the tutorial with this slug.
+    Start(String),
+    Next,
+    Skip,
+}
+
+/// A change to the yard under a tutorial. Trains are run through the same level states as when playing a level, and
+/// paused by pausing virtual time.
+#[derive(Event, Clone)]
+pub enum TutorialYardEvent {
+    Reset(Box<YardReset>),
+    SwitchTracks((usize, usize)),
+    StartTrains(f32),
+    PauseTrains,
+    ResumeTrains(f32),
+    StopTrains,
+}
+
+/// Holds a tutorial's boxes, arrows and hand, and sits on top of the yard.
+#[derive(Component)]
+pub struct TutorialLayer;
+
+pub struct TutorialPlugin;
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        let tutorials = script::parse_tutorials(TUTORIAL_DATA)
+            .unwrap_or_else(|e| panic!("could not parse tutorials.xml: {}", e));
+        app.insert_resource(Tutorials(tutorials))
+            .init_resource::<TutorialPlayer>()
+            .add_event::<TutorialEvent>()
+            .add_event::<TutorialYardEvent>()
+            .add_systems(OnEnter(UIState::Tutorial), begin_tutorial)
+            .add_systems(OnExit(UIState::Tutorial), end_tutorial)
+            .add_systems(
+                Update,
+                (
+                    handle_tutorial_events.run_if(on_event::<TutorialEvent>),
+                    start_level_after_tutorial.run_if(in_state(UIState::Level)),
+                    (
+                        player::tick_tutorial_waits,
+                        player::run_tutorial_actions,
+                        (update_tutorial_yard, control_tutorial_trains),
+                        player::move_tutorial_hand,
+                        player::show_cursor_when_touch_enabled,
+                        follow_yard,
+                    )
+                        .chain()
+                        .run_if(in_state(UIState::Tutorial)),
+                ),
+            );
+    }
+}
+
+/// Whether the player can draw on the yard: always while playing a level, but only when the script allows it in a
+/// tutorial.
+pub fn player_can_touch_yard(ui_state: Res<State<UIState>>, player: Res<TutorialPlayer>) -> bool {
+    *ui_state.get() != UIState::Tutorial || player.touch_enabled()
+}
+
+fn handle_tutorial_events(
+    mut events: EventReader<TutorialEvent>,
+    tutorials: Res<Tutorials>,
+    levels: Res<StockLevelInfos>,
+    mut player: ResMut<TutorialPlayer>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+) {
+    for event in events.read() {
+        let finished = match event {
+            TutorialEvent::Start(slug) => {
+                if let Some(tutorial) = tutorials.find(slug) {
+                    *player = TutorialPlayer::new(tutorial.clone());
+                    next_ui_state.set(UIState::Tutorial);
+                }
+                false
+            }
+            TutorialEvent::Next => player.step_finished() && !player.next_step(),
+            TutorialEvent::Skip => true,
+        };
+
+        if finished {
+            // the yard has to go away before the level can start, so the level is started once the level UI is up
+            player.level_after = player
+                .tutorial()
+                .and_then(|tutorial| tutorial.before_level.clone())
+                .filter(|name| levels.find_level(name).is_some());
+            match player.level_after {
+                Some(_) => next_ui_state.set(UIState::Level),
+                None => next_ui_state.set(UIState::LevelPicker),
+            }
+            next_level_state.set(LevelState::None);
+        }
+    }
+}
+
+fn start_level_after_tutorial(
+    mut player: ResMut<TutorialPlayer>,
+    mut start_lvl_ev_writer: EventWriter<StartLevelEvent>,
+) {
+    if player.level_after.is_some() {
+        let level_name = player.level_after.take().unwrap();
+        start_lvl_ev_writer.send(StartLevelEvent { level_name });
+    }
+}
+
+fn begin_tutorial(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut curr_lvl_name: ResMut<CurrentLevelName>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+) {
+    // tutorials aren't levels, so nothing about them gets saved
+    curr_lvl_name.0 = None;
+    spawn_yard(&mut commands, &asset_server, default());
+    commands.spawn((
+        TutorialLayer,
+        Transform::default(),
+        Visibility::default(),
+        Name::new("Tutorial layer"),
+    ));
+    next_level_state.set(LevelState::Editing);
+}
+
+fn end_tutorial(
+    mut commands: Commands,
+    layer_query: Query<Entity, With<TutorialLayer>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    for entity in layer_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    time.unpause();
+    time.set_relative_speed(1.0);
+}
+
+fn update_tutorial_yard(
+    mut events: EventReader<TutorialYardEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut yard_query: Query<(Entity, &mut Yard)>,
+    yard_edited_state_query: Query<(Entity, &YardEditedState)>,
+    cursor_query: Query<Entity, With<CursorComponent>>,
+) {
+    for event in events.read() {
+        let Ok((yard_entity, mut yard)) = yard_query.get_single_mut() else {
+            return;
+        };
+        match event {
+            TutorialYardEvent::SwitchTracks((row, col)) => {
+                yard.sim.switch_active_passive(*row, *col);
+            }
+            TutorialYardEvent::Reset(reset) => {
+                // trains might be running, so new tracks go on the yard from before they started
+                let edited_yard = yard_edited_state_query
+                    .get_single()
+                    .map_or(&*yard, |(_, edited)| &edited.0);
+                let sim = match &**reset {
+                    YardReset::Level(level, tracks) => {
+                        let mut sim = level.to_yard();
+                        if let Some(tracks) = tracks {
+                            sim.set_progress(tracks);
+                        }
+                        sim
+                    }
+                    YardReset::Tracks(tracks) => {
+                        let mut sim = edited_yard.sim.clone();
+                        sim.set_progress(tracks);
+                        sim
+                    }
+                    YardReset::Empty => default(),
+                };
+
+                // stopping the trains would bring back the old yard otherwise
+                for (entity, _) in yard_edited_state_query.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                replace_yard(
+                    &mut commands,
+                    &asset_server,
+                    yard_entity,
+                    cursor_query.get_single().ok(),
+                    sim,
+                );
+                // the new yard needs to exist before anything else can change it
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn control_tutorial_trains(
+    mut events: EventReader<TutorialYardEvent>,
+    level_state: Res<State<LevelState>>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+    mut time: ResMut<Time<Virtual>>,
+    train_speed: Res<TrainSpeed>,
+) {
+    for event in events.read() {
+        match event {
+            TutorialYardEvent::StartTrains(speed) | TutorialYardEvent::ResumeTrains(speed) => {
+                if *level_state.get() == LevelState::Editing {
+                    next_level_state.set(LevelState::RunningNotCrashed);
+                }
+                // scripts were written for the default train speed, whatever the speed slider is set to
+                let speed = speed * DEFAULT_TRAIN_SPEED / train_speed.0.max(0.01);
+                time.set_relative_speed(speed);
+                time.unpause();
+            }
+            TutorialYardEvent::PauseTrains => time.pause(),
+            TutorialYardEvent::StopTrains | TutorialYardEvent::Reset(_) => {
+                if matches!(
+                    level_state.get(),
+                    LevelState::RunningNotCrashed | LevelState::RunningCrashed
+                ) {
+                    next_level_state.set(LevelState::Editing);
+                }
+                time.set_relative_speed(1.0);
+                time.unpause();
+            }
+            TutorialYardEvent::SwitchTracks(_) => {}
+        }
+    }
+}
+
+/// Keeps the tutorial's boxes and arrows over the yard, which moves with the window.
+fn follow_yard(
+    yard_query: Query<&Transform, (With<Yard>, Without<TutorialLayer>)>,
+    mut layer_query: Query<&mut Transform, With<TutorialLayer>>,
+) {
+    let Ok(yard_transform) = yard_query.get_single() else {
+        return;
+    };
+    for mut transform in layer_query.iter_mut() {
+        // above the tiles and trains
+        transform.translation = yard_transform.translation + Vec3::Z * 10.0;
+    }
+}
@@ -0,0 +1,533 @@
+use bevy::{prelude::*, sprite::Anchor, text::TextBounds};
+use trainyard_core::{direction::Dir, yard as sim};
+
+use super::{
+    script::{TilePos, Tutorial, TutorialAction, TutorialDelay},
+    TutorialLayer, TutorialYardEvent,
+};
+use crate::{
+    level::{
+        cursor::CursorComponent,
+        yard::{Yard, YardMidTickEvent, YardTickedEvent},
+    },
+    NUM_COLS, NUM_ROWS, TILE_SIZE_PX,
+};
+
+/// How many pixels here one of the original game's screen points covers.
+const POINTS_TO_PX: f32 = NUM_COLS as f32 * TILE_SIZE_PX / 320.0;
+/// Where the bottom of the yard was on the original game's screen, in points.
+const YARD_BOTTOM_POINTS: f32 = 80.0;
+/// The training atlas is drawn at twice the original game's resolution.
+const ATLAS_TO_PX: f32 = POINTS_TO_PX / 2.0;
+
+const BOX_RECT: Rect = atlas_rect(0.0, 0.0, 420.0, 160.0);
+const ARROW_RECT: Rect = atlas_rect(144.0, 724.0, 56.0, 192.0);
+const HAND_UP_RECT: Rect = atlas_rect(434.0, 522.0, 140.0, 200.0);
+const HAND_DOWN_RECT: Rect = atlas_rect(2.0, 522.0, 140.0, 200.0);
+/// Where the tip of the hand's finger is in its sprite.
+const FINGERTIP_ANCHOR: Vec2 = Vec2::new(-0.1, 0.36);
+
+pub const BOX_FONT_SIZE: f32 = 28.0;
+/// How fast the hand moves at a speed of 1.
+pub const HAND_SPEED_PX_PER_SEC: f32 = 2.5 * TILE_SIZE_PX;
+/// Where the hand comes in from and leaves to, off the bottom of the screen.
+pub const HAND_OFFSCREEN_POSITION: Vec2 =
+    Vec2::new(NUM_COLS as f32 * TILE_SIZE_PX * 0.75, -3.0 * TILE_SIZE_PX);
+pub const DOUBLE_TAP_SECS: f32 = 0.4;
+
+const fn atlas_rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+    Rect {
+        min: Vec2::new(x, y),
+        max: Vec2::new(x + width, y + height),
+    }
+}
+
+/// Marks the boxes and arrows a tutorial has put up, which `reset_allActions` takes down.
+#[derive(Component)]
+pub struct TutorialSprite;
+
+#[derive(Component)]
+pub struct TutorialHand;
+
+/// How far through its tutorial the player is, and what it's waiting on.
+#[derive(Resource)]
+pub struct TutorialPlayer {
+    tutorial: Option<Tutorial>,
+    step: usize,
+    /// The next action of the step to play.
+    action: usize,
+    wait: Option<Wait>,
+    hand: Option<Hand>,
+    /// The hand's speed, which carries over from one `hand_enterTo` to the next.
+    hand_speed: f32,
+    touch_enabled: bool,
+    /// The level to start once the tutorial is done.
+    pub level_after: Option<String>,
+}
+
+impl Default for TutorialPlayer {
+    fn default() -> Self {
+        TutorialPlayer {
+            tutorial: None,
+            step: 0,
+            action: 0,
+            wait: None,
+            hand: None,
+            hand_speed: 1.0,
+            touch_enabled: false,
+            level_after: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Wait {
+    Seconds(f32),
+    Ticks(f32),
+    /// Until the hand gets to where it's going.
+    Hand,
+    /// Seconds left of tapping twice on a tile.
+    DoubleTap(f32),
+}
+
+struct Hand {
+    entity: Entity,
+    position: Vec2,
+    finger_down: bool,
+    /// The tile under the finger while it's down, and the side it came into that tile from.
+    tile: Option<TilePos>,
+    entry_side: Option<Dir>,
+    movement: Option<HandMovement>,
+}
+
+struct HandMovement {
+    from: Vec2,
+    to: Vec2,
+    elapsed: f32,
+    duration: f32,
+    /// Set when the hand is going away, after which it's despawned.
+    leaving: bool,
+}
+
+impl TutorialPlayer {
+    pub fn new(tutorial: Tutorial) -> Self {
+        TutorialPlayer {
+            tutorial: Some(tutorial),
+            ..default()
+        }
+    }
+
+    pub fn tutorial(&self) -> Option<&Tutorial> {
+        self.tutorial.as_ref()
+    }
+
+    /// The step being played, counting from 1.
+    pub fn step_number(&self) -> usize {
+        self.step + 1
+    }
+
+    pub fn step_count(&self) -> usize {
+        self.tutorial().map_or(0, |tutorial| tutorial.steps.len())
+    }
+
+    pub fn is_last_step(&self) -> bool {
+        self.step_number() >= self.step_count()
+    }
+
+    /// Whether every action in the current step has been played.
+    pub fn step_finished(&self) -> bool {
+        self.wait.is_none() && self.next_action().is_none()
+    }
+
+    /// Moves on to the next step, returning false if there isn't one.
+    pub fn next_step(&mut self) -> bool {
+        if self.is_last_step() {
+            return false;
+        }
+        self.step += 1;
+        self.action = 0;
+        true
+    }
+
+    pub fn touch_enabled(&self) -> bool {
+        self.touch_enabled
+    }
+
+    fn next_action(&self) -> Option<&TutorialAction> {
+        self.tutorial()?
+            .steps
+            .get(self.step)?
+            .actions
+            .get(self.action)
+    }
+
+    fn move_hand(&mut self, to: Vec2, duration: Option<f32>, leaving: bool) {
+        let speed = HAND_SPEED_PX_PER_SEC * self.hand_speed;
+        let Some(hand) = self.hand.as_mut() else {
+            return;
+        };
+        let duration = duration.unwrap_or(hand.position.distance(to) / speed);
+        hand.movement = Some(HandMovement {
+            from: hand.position,
+            to,
+            elapsed: 0.0,
+            duration,
+            leaving,
+        });
+        self.wait = Some(Wait::Hand);
+    }
+}
+
+/// Turns a point on the original game's screen into a position on the yard.
+fn screen_point_to_yard(point: Vec2) -> Vec2 {
+    Vec2::new(point.x, point.y - YARD_BOTTOM_POINTS) * POINTS_TO_PX
+}
+
+fn tile_center((row, col): TilePos) -> Vec2 {
+    Vec2::new(
+        col as f32 * TILE_SIZE_PX + TILE_SIZE_PX / 2.0,
+        row as f32 * TILE_SIZE_PX + TILE_SIZE_PX / 2.0,
+    )
+}
+
+fn tile_at(position: Vec2) -> Option<TilePos> {
+    let col = (position.x / TILE_SIZE_PX).floor();
+    let row = (position.y / TILE_SIZE_PX).floor();
+    let in_yard = (0.0..NUM_COLS as f32).contains(&col) && (0.0..NUM_ROWS as f32).contains(&row);
+    in_yard.then_some((row as usize, col as usize))
+}
+
+pub fn tick_tutorial_waits(
+    time: Res<Time<Real>>,
+    mut player: ResMut<TutorialPlayer>,
+    mut ticked_events: EventReader<YardTickedEvent>,
+    mut mid_tick_events: EventReader<YardMidTickEvent>,
+) {
+    let ticks = ticked_events.read().count() as f32 + mid_tick_events.read().count() as f32 * 0.5;
+    let left = match player.wait {
+        Some(Wait::Seconds(secs)) => Wait::Seconds(secs - time.delta_secs()),
+        Some(Wait::DoubleTap(secs)) => Wait::DoubleTap(secs - time.delta_secs()),
+        Some(Wait::Ticks(count)) if ticks > 0.0 => Wait::Ticks(count - ticks),
+        _ => return,
+    };
+    player.wait = match left {
+        Wait::Seconds(left) | Wait::DoubleTap(left) | Wait::Ticks(left) if left <= 0.0 => None,
+        left => Some(left),
+    };
+}
+
+/// Plays the current step's actions, up until one that has to be waited on.
+pub fn run_tutorial_actions(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut player: ResMut<TutorialPlayer>,
+    layer_query: Query<Entity, With<TutorialLayer>>,
+    sprite_query: Query<Entity, With<TutorialSprite>>,
+    mut yard_events: EventWriter<TutorialYardEvent>,
+) {
+    let Ok(layer) = layer_query.get_single() else {
+        return;
+    };
+
+    while player.wait.is_none() {
+        let Some(action) = player.next_action().cloned() else {
+            break;
+        };
+        player.action += 1;
+
+        match action {
+            TutorialAction::CreateBox { position, text } => {
+                let position = screen_point_to_yard(position);
+                spawn_box(&mut commands, &asset_server, layer, position, text);
+            }
+            TutorialAction::CreateArrow {
+                tile,
+                offset,
+                rotation,
+            } => {
+                let tip = match tile {
+                    // pointing at a tile from just outside of it
+                    Some(tile) => {
+                        let pointing = Vec2::NEG_Y.rotate(Vec2::from_angle(-rotation.to_radians()));
+                        tile_center(tile) - pointing * TILE_SIZE_PX / 2.0 + offset * POINTS_TO_PX
+                    }
+                    None => screen_point_to_yard(offset),
+                };
+                spawn_arrow(&mut commands, &asset_server, layer, tip, rotation);
+            }
+            TutorialAction::ResetAllActions => {
+                for entity in sprite_query.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+            TutorialAction::Delay(TutorialDelay::Seconds(secs)) => {
+                player.wait = Some(Wait::Seconds(secs));
+            }
+            TutorialAction::Delay(TutorialDelay::Ticks(count)) => {
+                player.wait = Some(Wait::Ticks(count));
+            }
+            TutorialAction::YardReset(reset) => {
+                yard_events.send(TutorialYardEvent::Reset(Box::new(reset)));
+            }
+            TutorialAction::HandEnterTo {
+                tile,
+                offset,
+                speed,
+            } => {
+                if let Some(speed) = speed {
+                    player.hand_speed = speed;
+                }
+                if player.hand.is_none() {
+                    let entity = spawn_hand(&mut commands, &asset_server, layer);
+                    player.hand = Some(Hand {
+                        entity,
+                        position: HAND_OFFSCREEN_POSITION,
+                        finger_down: false,
+                        tile: None,
+                        entry_side: None,
+                        movement: None,
+                    });
+                }
+                player.move_hand(tile_center(tile) + offset * POINTS_TO_PX, None, false);
+            }
+            TutorialAction::HandMoveTo {
+                tile,
+                offset,
+                duration,
+            } => {
+                player.move_hand(tile_center(tile) + offset * POINTS_TO_PX, duration, false);
+            }
+            TutorialAction::HandFingerDown | TutorialAction::HandFingerUp => {
+                let finger_down = matches!(action, TutorialAction::HandFingerDown);
+                if let Some(hand) = player.hand.as_mut() {
+                    hand.finger_down = finger_down;
+                    hand.tile = tile_at(hand.position).filter(|_| finger_down);
+                    hand.entry_side = None;
+                }
+            }
+            TutorialAction::HandFingerDoubleTap => {
+                if let Some(tile) = player.hand.as_ref().and_then(|hand| tile_at(hand.position)) {
+                    yard_events.send(TutorialYardEvent::SwitchTracks(tile));
+                    commands.spawn(AudioPlayer::<AudioSource>(
+                        asset_server.load("audio/switch_track.ogg"),
+                    ));
+                    player.wait = Some(Wait::DoubleTap(DOUBLE_TAP_SECS));
+                }
+            }
+            TutorialAction::HandLeave => {
+                player.move_hand(HAND_OFFSCREEN_POSITION, None, true);
+            }
+            TutorialAction::GameStart { speed } => {
+                yard_events.send(TutorialYardEvent::StartTrains(speed));
+            }
+            TutorialAction::GamePause => {
+                yard_events.send(TutorialYardEvent::PauseTrains);
+            }
+            TutorialAction::GameResume { speed } => {
+                yard_events.send(TutorialYardEvent::ResumeTrains(speed));
+            }
+            TutorialAction::GameStop => {
+                yard_events.send(TutorialYardEvent::StopTrains);
+            }
+            TutorialAction::EnableTouch | TutorialAction::DisableTouch => {
+                player.touch_enabled = matches!(action, TutorialAction::EnableTouch);
+            }
+        }
+    }
+}
+
+/// Moves the hand along, drawing track wherever it drags its finger.
+pub fn move_tutorial_hand(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time<Real>>,
+    player: ResMut<TutorialPlayer>,
+    mut yard_query: Query<&mut Yard>,
+    mut hand_query: Query<(&mut Transform, &mut Sprite), With<TutorialHand>>,
+) {
+    let player = player.into_inner();
+    let Some(hand) = player.hand.as_mut() else {
+        return;
+    };
+
+    let mut arrived = false;
+    let mut left = false;
+    if let Some(movement) = hand.movement.as_mut() {
+        movement.elapsed += time.delta_secs();
+        let t = (movement.elapsed / movement.duration.max(f32::EPSILON)).min(1.0);
+        hand.position = movement.from.lerp(movement.to, t);
+        arrived = t >= 1.0;
+        left = arrived && movement.leaving;
+    }
+    if arrived {
+        hand.movement = None;
+        if player.wait == Some(Wait::Hand) {
+            player.wait = None;
+        }
+    }
+    if left {
+        commands.entity(hand.entity).remove_parent().despawn();
+        player.hand = None;
+        return;
+    }
+
+    if hand.finger_down {
+        if let Ok(mut yard) = yard_query.get_single_mut() {
+            let target = tile_at(hand.position);
+            drag_finger(&mut commands, &asset_server, hand, &mut yard.sim, target);
+        }
+    }
+
+    // the finger goes down and up twice while double tapping
+    let tapping = match player.wait {
+        Some(Wait::DoubleTap(left)) => (left / (DOUBLE_TAP_SECS / 4.0)) as u32 % 2 == 1,
+        _ => false,
+    };
+    if let Ok((mut transform, mut sprite)) = hand_query.get_mut(hand.entity) {
+        transform.translation = hand.position.extend(transform.translation.z);
+        sprite.rect = Some(if hand.finger_down || tapping {
+            HAND_DOWN_RECT
+        } else {
+            HAND_UP_RECT
+        });
+    }
+}
+
+/// Draws track from the tile under the finger to `target` one tile at a time, the same way the cursor does.
+fn drag_finger(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    hand: &mut Hand,
+    sim: &mut sim::Yard,
+    target: Option<TilePos>,
+) {
+    let (Some((mut row, mut col)), Some(target)) = (hand.tile, target) else {
+        hand.tile = target;
+        hand.entry_side = None;
+        return;
+    };
+
+    while (row, col) != target {
+        let d_row = target.0 as isize - row as isize;
+        let d_col = target.1 as isize - col as isize;
+        let dir = match (d_col.abs() >= d_row.abs(), d_col > 0, d_row > 0) {
+            (true, true, _) => Dir::Right,
+            (true, false, _) => Dir::Left,
+            (false, _, true) => Dir::Up,
+            (false, _, false) => Dir::Down,
+        };
+        if let Some(entry_side) = hand.entry_side {
+            sim.tiles[row][col].add_connection(dir, entry_side);
+        }
+        match dir {
+            Dir::Up => row += 1,
+            Dir::Right => col += 1,
+            Dir::Down => row -= 1,
+            Dir::Left => col -= 1,
+        }
+        hand.entry_side = Some(dir.flip());
+        commands.spawn(AudioPlayer::<AudioSource>(
+            asset_server.load("audio/draw_track.ogg"),
+        ));
+    }
+    hand.tile = Some(target);
+}
+
+/// Hides the cursor unless the script lets the player draw.
+pub fn show_cursor_when_touch_enabled(
+    player: Res<TutorialPlayer>,
+    mut cursor_query: Query<&mut Visibility, With<CursorComponent>>,
+) {
+    let visibility = if player.touch_enabled() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut cursor_visibility in cursor_query.iter_mut() {
+        cursor_visibility.set_if_neq(visibility);
+    }
+}
+
+fn spawn_box(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    layer: Entity,
+    position: Vec2,
+    text: String,
+) {
+    let size = BOX_RECT.size() * ATLAS_TO_PX;
+    let text = (
+        Text2d::new(text),
+        TextFont {
+            font: asset_server.load("fonts/kenyan_coffee_rg.otf"),
+            font_size: BOX_FONT_SIZE,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextBounds::new_horizontal(size.x - 20.0),
+        Transform::from_xyz(0.0, 0.0, 0.1),
+    );
+
+    let text = commands.spawn(text).id();
+    let text_box = commands
+        .spawn((
+            Transform::from_translation(position.extend(2.0)),
+            Sprite {
+                image: asset_server.load("sprites/Training_atlas.png"),
+                rect: Some(BOX_RECT),
+                custom_size: Some(size),
+                ..default()
+            },
+            TutorialSprite,
+            Name::new("tutorial box"),
+        ))
+        .add_children(&[text])
+        .id();
+    commands.entity(layer).add_children(&[text_box]);
+}
+
+/// Spawns an arrow with its tip at `tip`, rotated `rotation` degrees clockwise from pointing down.
+fn spawn_arrow(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    layer: Entity,
+    tip: Vec2,
+    rotation: f32,
+) {
+    let arrow = commands
+        .spawn((
+            Transform::from_translation(tip.extend(1.0))
+                .with_rotation(Quat::from_rotation_z(-rotation.to_radians())),
+            Sprite {
+                image: asset_server.load("sprites/Training_atlas.png"),
+                rect: Some(ARROW_RECT),
+                custom_size: Some(ARROW_RECT.size() * ATLAS_TO_PX),
+                anchor: Anchor::BottomCenter,
+                ..default()
+            },
+            TutorialSprite,
+            Name::new("tutorial arrow"),
+        ))
+        .id();
+    commands.entity(layer).add_children(&[arrow]);
+}
+
+fn spawn_hand(commands: &mut Commands, asset_server: &Res<AssetServer>, layer: Entity) -> Entity {
+    let hand = commands
+        .spawn((
+            Transform::from_translation(HAND_OFFSCREEN_POSITION.extend(3.0)),
+            Sprite {
+                image: asset_server.load("sprites/Training_atlas.png"),
+                rect: Some(HAND_UP_RECT),
+                custom_size: Some(HAND_UP_RECT.size() * ATLAS_TO_PX),
+                anchor: Anchor::Custom(FINGERTIP_ANCHOR),
+                ..default()
+            },
+            TutorialHand,
+            Name::new("tutorial hand"),
+        ))
+        .id();
+    commands.entity(layer).add_children(&[hand]);
+    hand
+}
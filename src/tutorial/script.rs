@@ -0,0 +1,480 @@
+//! Reading the tutorial scripts in `tutorials.xml`, which come from the original game.
+//!
+//! ```text
+//! <tutorial slug="welcome" before_level="Red Line" name="Welcome!">
+//!     <step>
+//!         <yard_reset full="hh002OjC3GiaC00"/>
+//!         <create_arrow cr="1,3" xy="0,-11" rotation="0"/>
+//!         <create_box xy="120,340">OUTLET stations have[br]a PLUS symbol on them</create_box>
+//!         <delay time="2"/>
+//!         <hand_enterTo cr="1,3"/>
+//!         <hand_fingerDown/>
+//!         <hand_moveTo cr="5,3"/>
+//!         <hand_fingerUp/>
+//!         <hand_leave/>
+//!         <game_start speed="1.3"/>
+//!         <delay steps="4"/>
+//!         <game_pause/>
+//!     </step>
+//! </tutorial>
+//! ```
+//!
+//! Each step plays its actions in order, and the player moves on to the next step with a NEXT button.
+//! `cr` is a tile as `column,row`, with the row counting down from the top of the yard. `xy` is in the original
+//! game's screen points: a 320x480 screen, with the yard covering `y` from 80 to 400 and `y` counting up. On an arrow
+//! or hand with a `cr`, `xy` is an offset from that tile instead.
+//!
+//! `before_level` isn't in the original file: it's the level a tutorial is shown before in the level picker. The
+//! arrow pointing at the NEXT button in the first tutorial has also been moved to where that button is here.
+//! Tags starting with `XXX` are ones that were switched off in the original, and are skipped.
+
+use std::fmt;
+
+use bevy::math::Vec2;
+use trainyard_core::{
+    level::LevelLoadInfo,
+    yard_code::{decode_tracks_code, decode_yard_code},
+    NUM_COLS, NUM_ROWS,
+};
+
+/// How long a `<delay/>` with no time or steps lasts, in seconds.
+pub const DEFAULT_DELAY_SECS: f32 = 0.5;
+
+const DISABLED_TAG_PREFIX: &str = "XXX";
+
+#[derive(Debug, Clone)]
+pub struct Tutorial {
+    pub slug: String,
+    pub name: String,
+    /// The level this tutorial comes right before, if it's part of the game.
+    pub before_level: Option<String>,
+    pub steps: Vec<TutorialStep>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TutorialStep {
+    pub actions: Vec<TutorialAction>,
+}
+
+/// A tile, as (row, col) with row 0 at the bottom of the yard like everywhere else in the game.
+pub type TilePos = (usize, usize);
+
+#[derive(Debug, Clone)]
+pub enum TutorialAction {
+    /// A text box centred on `position`, in screen points.
+    CreateBox {
+        position: Vec2,
+        text: String,
+    },
+    /// An arrow pointing at `tile` (moved by `offset` points), or at the screen point `offset` if there's no tile.
+    /// `rotation` is in degrees clockwise, with 0 pointing down.
+    CreateArrow {
+        tile: Option<TilePos>,
+        offset: Vec2,
+        rotation: f32,
+    },
+    /// Removes every box and arrow that's showing.
+    ResetAllActions,
+    Delay(TutorialDelay),
+    YardReset(YardReset),
+    /// Brings the hand in to a tile. `speed` stays in effect for the hand's later moves.
+    HandEnterTo {
+        tile: TilePos,
+        offset: Vec2,
+        speed: Option<f32>,
+    },
+    /// Moves the hand to a tile, drawing track on the way if its finger is down. `duration` overrides the hand's
+    /// speed.
+    HandMoveTo {
+        tile: TilePos,
+        offset: Vec2,
+        duration: Option<f32>,
+    },
+    HandFingerDown,
+    HandFingerUp,
+    /// Switches the active and passive tracks of the tile under the hand.
+    HandFingerDoubleTap,
+    HandLeave,
+    GameStart {
+        speed: f32,
+    },
+    GamePause,
+    GameResume {
+        speed: f32,
+    },
+    GameStop,
+    /// Lets the player draw on the yard themselves.
+    EnableTouch,
+    DisableTouch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TutorialDelay {
+    Seconds(f32),
+    /// A number of yard ticks, which can include halves.
+    Ticks(f32),
+}
+
+#[derive(Debug, Clone)]
+pub enum YardReset {
+    /// A whole new level, with tracks if the code has them.
+    Level(LevelLoadInfo, Option<Vec<u8>>),
+    /// New tracks for the level that's already there, in the same format as `Yard::get_progress`.
+    Tracks(Vec<u8>),
+    /// An empty yard, with no pieces or tracks.
+    Empty,
+}
+
+/// A problem with `tutorials.xml`. Line numbers start from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TutorialXmlError {
+    pub line: u32,
+    pub message: String,
+}
+
+impl fmt::Display for TutorialXmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TutorialXmlError {}
+
+/// Parses every tutorial in `text`, in the order they appear.
+pub fn parse_tutorials(text: &str) -> Result<Vec<Tutorial>, TutorialXmlError> {
+    let doc = roxmltree::Document::parse(text).map_err(|e| TutorialXmlError {
+        line: e.pos().row,
+        message: e.to_string(),
+    })?;
+    let error = |node: roxmltree::Node, message: String| TutorialXmlError {
+        line: doc.text_pos_at(node.range().start).row,
+        message,
+    };
+
+    let mut tutorials = Vec::new();
+    for tutorial_node in doc.root_element().children().filter(|n| n.is_element()) {
+        if tutorial_node.tag_name().name() != "tutorial" {
+            return Err(error(
+                tutorial_node,
+                format!(
+                    "expected <tutorial>, found <{}>",
+                    tutorial_node.tag_name().name()
+                ),
+            ));
+        }
+        let attribute = |name: &str| {
+            tutorial_node
+                .attribute(name)
+                .map(String::from)
+                .ok_or_else(|| error(tutorial_node, format!("<tutorial> is missing {:?}", name)))
+        };
+        let mut tutorial = Tutorial {
+            slug: attribute("slug")?,
+            name: attribute("name")?,
+            before_level: tutorial_node.attribute("before_level").map(String::from),
+            steps: Vec::new(),
+        };
+
+        for step_node in tutorial_node.children().filter(|n| n.is_element()) {
+            if step_node.tag_name().name() != "step" {
+                return Err(error(
+                    step_node,
+                    format!("expected <step>, found <{}>", step_node.tag_name().name()),
+                ));
+            }
+            let mut step = TutorialStep {
+                actions: Vec::new(),
+            };
+            for action_node in step_node.children().filter(|n| n.is_element()) {
+                if let Some(action) =
+                    parse_action(action_node).map_err(|message| error(action_node, message))?
+                {
+                    step.actions.push(action);
+                }
+            }
+            tutorial.steps.push(step);
+        }
+        tutorials.push(tutorial);
+    }
+    Ok(tutorials)
+}
+
+/// Reads a single action, or `None` for one that's switched off.
+fn parse_action(node: roxmltree::Node) -> Result<Option<TutorialAction>, String> {
+    let tag = node.tag_name().name();
+    let offset =
+        || Ok::<_, String>(parse_attribute(node, "xy", parse_point)?.unwrap_or(Vec2::ZERO));
+    let tile = || {
+        parse_attribute(node, "cr", parse_tile)?.ok_or_else(|| format!("<{}> needs a \"cr\"", tag))
+    };
+    let speed = || Ok::<_, String>(parse_attribute(node, "speed", parse_number)?.unwrap_or(1.0));
+
+    let action = match tag {
+        "create_box" => TutorialAction::CreateBox {
+            position: parse_attribute(node, "xy", parse_point)?
+                .ok_or_else(|| String::from("<create_box> needs an \"xy\""))?,
+            text: node.text().unwrap_or("").trim().replace("[br]", "\n"),
+        },
+        "create_arrow" => TutorialAction::CreateArrow {
+            tile: parse_attribute(node, "cr", parse_tile)?,
+            offset: offset()?,
+            rotation: parse_attribute(node, "rotation", parse_number)?.unwrap_or(0.0),
+        },
+        "reset_allActions" => TutorialAction::ResetAllActions,
+        "delay" => {
+            let time = parse_attribute(node, "time", parse_number)?;
+            let steps = parse_attribute(node, "steps", parse_number)?;
+            TutorialAction::Delay(match (time, steps) {
+                (Some(_), Some(_)) => {
+                    return Err(String::from("<delay> can't have both a time and steps"));
+                }
+                (Some(time), None) => TutorialDelay::Seconds(time),
+                (None, Some(steps)) => TutorialDelay::Ticks(steps),
+                (None, None) => TutorialDelay::Seconds(DEFAULT_DELAY_SECS),
+            })
+        }
+        "yard_reset" => {
+            let reset = if let Some(code) = node.attribute("full") {
+                let (level, tracks) = decode_yard_code(code).map_err(|e| e.to_string())?;
+                // the hand and the highlights are placed as if the yard is the original game's size
+                if (level.width, level.height) != (NUM_COLS, NUM_ROWS) {
+                    return Err(format!(
+                        "tutorial yards have to be {}x{}, this one is {}x{}",
+                        NUM_COLS, NUM_ROWS, level.width, level.height
+                    ));
+                }
+                YardReset::Level(level, tracks)
+            } else if let Some(code) = node.attribute("solution") {
+                let tracks = decode_tracks_code(code, NUM_COLS, NUM_ROWS);
+                YardReset::Tracks(tracks.map_err(|e| e.to_string())?)
+            } else {
+                YardReset::Empty
+            };
+            TutorialAction::YardReset(reset)
+        }
+        "hand_enterTo" => TutorialAction::HandEnterTo {
+            tile: tile()?,
+            offset: offset()?,
+            speed: parse_attribute(node, "speed", parse_number)?,
+        },
+        "hand_moveTo" => TutorialAction::HandMoveTo {
+            tile: tile()?,
+            offset: offset()?,
+            duration: parse_attribute(node, "time", parse_number)?,
+        },
+        "hand_fingerDown" => TutorialAction::HandFingerDown,
+        "hand_fingerUp" => TutorialAction::HandFingerUp,
+        "hand_fingerDoubleTap" => TutorialAction::HandFingerDoubleTap,
+        "hand_leave" => TutorialAction::HandLeave,
+        "game_start" => TutorialAction::GameStart { speed: speed()? },
+        "game_pause" => TutorialAction::GamePause,
+        "game_resume" => TutorialAction::GameResume { speed: speed()? },
+        "game_stop" => TutorialAction::GameStop,
+        "yard_enableTouch" => TutorialAction::EnableTouch,
+        "yard_disableTouch" => TutorialAction::DisableTouch,
+        _ if tag.starts_with(DISABLED_TAG_PREFIX) => return Ok(None),
+        _ => return Err(format!("unknown action <{}>", tag)),
+    };
+    Ok(Some(action))
+}
+
+fn parse_attribute<T>(
+    node: roxmltree::Node,
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+    node.attribute(name)
+        .map(|value| parse(value).map_err(|e| format!("{} on <{}>", e, node.tag_name().name())))
+        .transpose()
+}
+
+fn parse_number(word: &str) -> Result<f32, String> {
+    word.trim()
+        .parse()
+        .map_err(|_| format!("{:?} is not a number", word))
+}
+
+fn parse_point(word: &str) -> Result<Vec2, String> {
+    let (x, y) = word
+        .split_once(',')
+        .ok_or_else(|| format!("{:?} is not a point like \"160,340\"", word))?;
+    Ok(Vec2::new(parse_number(x)?, parse_number(y)?))
+}
+
+/// Turns `column,row` (with the row counting down from the top) into (row, col).
+fn parse_tile(word: &str) -> Result<TilePos, String> {
+    let invalid = || format!("{:?} is not a tile like \"3,4\"", word);
+    let (c, r) = word.split_once(',').ok_or_else(invalid)?;
+    let c: usize = c.trim().parse().map_err(|_| invalid())?;
+    let r: usize = r.trim().parse().map_err(|_| invalid())?;
+    if c >= NUM_COLS as usize || r >= NUM_ROWS as usize {
+        return Err(format!(
+            "{:?} is outside of the {}x{} grid",
+            word, NUM_COLS, NUM_ROWS
+        ));
+    }
+    Ok((NUM_ROWS as usize - 1 - r, c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tutorial::TUTORIAL_DATA;
+
+    const SMALL_SCRIPT: &str = r#"<tutorials>
+<tutorial slug="first" before_level="Red Line" name="First">
+    <step>
+        <yard_reset full="hh002OjC3GiaC00"/>
+        <create_arrow cr="1,3" xy="0,-11" rotation="90"/>
+        <create_box xy="120,340">Two[br]lines</create_box>
+        <XXXcreate_box xy="0,0">switched off</XXXcreate_box>
+        <delay time="2"/>
+    </step>
+    <step>
+        <hand_enterTo cr="0,0" speed="2"/>
+        <hand_fingerDown/>
+        <hand_moveTo cr="6,6" time="1.5"/>
+        <delay steps="0.5"/>
+        <delay/>
+    </step>
+</tutorial>
+<tutorial slug="second" name="Second">
+    <step>
+        <yard_reset solution="hh_4Q"/>
+        <yard_reset/>
+        <game_start/>
+    </step>
+</tutorial>
+</tutorials>"#;
+
+    fn parse_error(text: &str) -> TutorialXmlError {
+        parse_tutorials(text).expect_err("the script should not parse")
+    }
+
+    /// A script with a single step holding `action`, which is on line 3.
+    fn one_action(action: &str) -> String {
+        format!(
+            "<tutorials>\n<tutorial slug=\"a\" name=\"A\"><step>\n{}\n</step></tutorial>\n</tutorials>",
+            action
+        )
+    }
+
+    #[test]
+    fn stock_tutorials_parse() {
+        let tutorials = parse_tutorials(TUTORIAL_DATA).unwrap();
+        assert!(!tutorials.is_empty());
+        assert!(tutorials.iter().all(|tutorial| !tutorial.steps.is_empty()));
+    }
+
+    #[test]
+    fn parses_tutorials_and_their_steps() {
+        let tutorials = parse_tutorials(SMALL_SCRIPT).unwrap();
+        assert_eq!(tutorials.len(), 2);
+        let first = &tutorials[0];
+        assert_eq!(first.slug, "first");
+        assert_eq!(first.name, "First");
+        assert_eq!(first.before_level.as_deref(), Some("Red Line"));
+        assert_eq!(first.steps.len(), 2);
+        // the switched off box is left out
+        assert_eq!(first.steps[0].actions.len(), 4);
+        assert_eq!(tutorials[1].before_level, None);
+
+        assert!(matches!(
+            first.steps[0].actions[0],
+            TutorialAction::YardReset(YardReset::Level(ref level, None)) if level.sources.len() == 1
+        ));
+        assert!(matches!(
+            first.steps[0].actions[2],
+            TutorialAction::CreateBox { position, ref text } if position == Vec2::new(120.0, 340.0) && text == "Two\nlines"
+        ));
+        assert!(matches!(
+            first.steps[0].actions[3],
+            TutorialAction::Delay(TutorialDelay::Seconds(2.0))
+        ));
+        assert!(matches!(
+            first.steps[1].actions[3],
+            TutorialAction::Delay(TutorialDelay::Ticks(0.5))
+        ));
+        assert!(matches!(
+            first.steps[1].actions[4],
+            TutorialAction::Delay(TutorialDelay::Seconds(DEFAULT_DELAY_SECS))
+        ));
+        assert!(matches!(
+            tutorials[1].steps[0].actions[..],
+            [
+                TutorialAction::YardReset(YardReset::Tracks(_)),
+                TutorialAction::YardReset(YardReset::Empty),
+                TutorialAction::GameStart { speed: 1.0 },
+            ]
+        ));
+    }
+
+    #[test]
+    fn tiles_count_rows_from_the_top() {
+        let tutorials = parse_tutorials(SMALL_SCRIPT).unwrap();
+        let first = &tutorials[0];
+        assert!(matches!(
+            first.steps[0].actions[1],
+            TutorialAction::CreateArrow { tile: Some((3, 1)), offset, rotation: 90.0 } if offset == Vec2::new(0.0, -11.0)
+        ));
+        assert!(matches!(
+            first.steps[1].actions[0],
+            TutorialAction::HandEnterTo {
+                tile: (6, 0),
+                speed: Some(2.0),
+                ..
+            }
+        ));
+        assert!(matches!(
+            first.steps[1].actions[2],
+            TutorialAction::HandMoveTo {
+                tile: (0, 6),
+                duration: Some(1.5),
+                ..
+            }
+        ));
+
+        assert_eq!(parse_tile("0,6"), Ok((0, 0)));
+        assert_eq!(parse_tile(" 6 , 0 "), Ok((6, 6)));
+        assert!(parse_tile("7,0").is_err());
+        assert!(parse_tile("0,7").is_err());
+        assert!(parse_tile("3").is_err());
+        assert!(parse_tile("a,b").is_err());
+    }
+
+    #[test]
+    fn tutorial_yards_are_the_original_size() {
+        // a 3x2 yard would put the hand and arrows in the wrong places
+        let error = parse_error(&one_action(r#"<yard_reset full="dcR"/>"#));
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("7x7"), "{}", error.message);
+        // tracks on their own are read for a 7x7 yard too
+        let solution = |code: &str| format!(r#"<yard_reset solution="{}"/>"#, code);
+        assert!(parse_tutorials(&one_action(&solution("hh_00008Q"))).is_ok());
+        assert!(parse_tutorials(&one_action(&solution("hh_00009Q"))).is_err());
+        assert!(parse_tutorials(&one_action(&solution("dc_4Q"))).is_err());
+    }
+
+    #[test]
+    fn problems_are_reported_with_their_line() {
+        assert_eq!(parse_error(&one_action("<dance/>")).line, 3);
+        assert_eq!(parse_error(&one_action("<hand_moveTo/>")).line, 3);
+        assert_eq!(
+            parse_error(&one_action(r#"<delay time="1" steps="1"/>"#)).line,
+            3
+        );
+        assert_eq!(
+            parse_error(&one_action(r#"<create_box xy="here">hi</create_box>"#)).line,
+            3
+        );
+        assert_eq!(parse_error("<tutorials>\n<lesson/>\n</tutorials>").line, 2);
+        assert_eq!(
+            parse_error("<tutorials>\n<tutorial name=\"No slug\"/>\n</tutorials>").line,
+            2
+        );
+        assert_eq!(
+            parse_error("<tutorials>\n<tutorial name=>\n</tutorials>").line,
+            2
+        );
+    }
+}
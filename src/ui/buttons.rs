@@ -2,10 +2,26 @@ pub mod erase;
 pub mod level_run;
 
 use bevy::prelude::*;
+use trainyard_core::trains::TrainColor;
 
-use super::{level_picker::StartLevelEvent, UIState};
-use crate::level::{
-    cursor::CursorState, loader::StockLevelInfos, toggle_level_state, CurrentLevelName, LevelState,
+use super::{
+    level::{
+        share_dialog::{ShareDialogEvent, ShareDialogState},
+        solutions_dialog::SolutionsDialogEvent,
+    },
+    level_picker::{LevelPickerPage, PasteLevelEvent, StartLevelEvent},
+    settings::SettingsButton,
+    UIState,
+};
+use crate::{
+    level::{
+        cursor::CursorState, debugger::DebuggerEvent, hints::HintRequestedEvent,
+        history::EditHistoryEvent, loader::StockLevelInfos, replays::ReplayEvent,
+        toggle_level_state, CurrentLevelName, LevelState,
+    },
+    level_editor::EditorTool,
+    settings::UiSound,
+    tutorial::TutorialEvent,
 };
 
 #[derive(Component, Clone, PartialEq, Eq)]
@@ -14,11 +30,43 @@ pub enum TrainyardButton {
     Unknown,
     MainMenuStartGame,
     MainMenuCredits,
+    MainMenuSettings,
     CreditsBack,
+    Settings(SettingsButton),
+    SettingsBack,
     LevelPickerStartLevel(String),
+    LevelPickerCity(String),
+    LevelPickerPreviousCity,
+    LevelPickerNextCity,
+    LevelPickerCityOverview,
+    LevelPickerMainMenu,
+    LevelPickerPasteLevel,
+    LevelPickerStartTutorial(String),
+    LevelPickerNewLevel,
+    LevelPickerEditLevel(String),
     LevelBackButton,
     LevelStartTrainsButton,
     LevelStartEraseButton,
+    LevelHintButton,
+    LevelUndoButton,
+    LevelRedoButton,
+    LevelShareButton,
+    LevelSolutionsButton,
+    Debugger(DebuggerEvent),
+    Replay(ReplayEvent),
+    ShareDialogCopyLevel,
+    ShareDialogCopyLevelAndTracks,
+    ShareDialogPasteTracks,
+    ShareDialogClose,
+    SolutionsDialog(SolutionsDialogEvent),
+    SolutionsDialogClose,
+    TutorialNext,
+    TutorialSkip,
+    LevelEditorTool(EditorTool),
+    LevelEditorColor(TrainColor),
+    LevelEditorTurn,
+    LevelEditorResize { width: i8, height: i8 },
+    LevelEditorSave,
     LevelWinDialogNextButton,
     LevelWinDialogBackButton,
 }
@@ -95,8 +143,9 @@ pub fn button_sounds_system(
 ) {
     for interaction in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
-            commands.spawn(AudioPlayer::<AudioSource>(
-                asset_server.load("audio/button_press.ogg"),
+            commands.spawn((
+                AudioPlayer::<AudioSource>(asset_server.load("audio/button_press.ogg")),
+                UiSound,
             ));
         }
     }
@@ -109,9 +158,16 @@ pub fn trainyard_ui_button_handler(
     mut next_ui_state: ResMut<NextState<UIState>>,
     mut next_level_state: ResMut<NextState<LevelState>>,
     mut next_cursor_state: ResMut<NextState<CursorState>>,
+    mut next_share_dialog_state: ResMut<NextState<ShareDialogState>>,
     mut start_lvl_ev_writer: EventWriter<StartLevelEvent>,
+    mut hint_ev_writer: EventWriter<HintRequestedEvent>,
+    mut history_ev_writer: EventWriter<EditHistoryEvent>,
+    mut share_ev_writer: EventWriter<ShareDialogEvent>,
+    mut paste_level_ev_writer: EventWriter<PasteLevelEvent>,
+    mut tutorial_ev_writer: EventWriter<TutorialEvent>,
     curr_lvl_name: Res<CurrentLevelName>,
     levels: Res<StockLevelInfos>,
+    mut picker_page: ResMut<LevelPickerPage>,
 ) {
     for (interaction, button) in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
@@ -123,7 +179,10 @@ pub fn trainyard_ui_button_handler(
                 TrainyardButton::MainMenuCredits => {
                     next_ui_state.set(UIState::Credits);
                 }
-                TrainyardButton::CreditsBack => {
+                TrainyardButton::MainMenuSettings => {
+                    next_ui_state.set(UIState::Settings);
+                }
+                TrainyardButton::CreditsBack | TrainyardButton::SettingsBack => {
                     next_ui_state.set(UIState::MainMenu);
                 }
                 TrainyardButton::LevelPickerStartLevel(level_name) => {
@@ -132,6 +191,31 @@ pub fn trainyard_ui_button_handler(
                     });
                     next_ui_state.set(UIState::Level);
                 }
+                TrainyardButton::LevelPickerCity(city) => {
+                    *picker_page = LevelPickerPage::City(city.clone());
+                }
+                TrainyardButton::LevelPickerPreviousCity => {
+                    if let Some(city) = find_neighbouring_city(&picker_page, &levels, -1) {
+                        *picker_page = LevelPickerPage::City(city);
+                    }
+                }
+                TrainyardButton::LevelPickerNextCity => {
+                    if let Some(city) = find_neighbouring_city(&picker_page, &levels, 1) {
+                        *picker_page = LevelPickerPage::City(city);
+                    }
+                }
+                TrainyardButton::LevelPickerCityOverview => {
+                    *picker_page = LevelPickerPage::Cities;
+                }
+                TrainyardButton::LevelPickerMainMenu => {
+                    next_ui_state.set(UIState::MainMenu);
+                }
+                TrainyardButton::LevelPickerPasteLevel => {
+                    paste_level_ev_writer.send_default();
+                }
+                TrainyardButton::LevelPickerStartTutorial(slug) => {
+                    tutorial_ev_writer.send(TutorialEvent::Start(slug.clone()));
+                }
                 TrainyardButton::LevelBackButton => {
                     if *level_state.get() != LevelState::Won {
                         next_ui_state.set(UIState::LevelPicker);
@@ -146,10 +230,70 @@ pub fn trainyard_ui_button_handler(
                         next_cursor_state.set(cursor_state.get().toggle_erase())
                     }
                 }
+                TrainyardButton::LevelHintButton => {
+                    if *level_state.get() == LevelState::Editing {
+                        hint_ev_writer.send_default();
+                    }
+                }
+                TrainyardButton::LevelUndoButton => {
+                    if *level_state.get() == LevelState::Editing {
+                        history_ev_writer.send(EditHistoryEvent::Undo);
+                    }
+                }
+                TrainyardButton::LevelRedoButton => {
+                    if *level_state.get() == LevelState::Editing {
+                        history_ev_writer.send(EditHistoryEvent::Redo);
+                    }
+                }
+                TrainyardButton::LevelShareButton => {
+                    if *level_state.get() == LevelState::Editing {
+                        next_share_dialog_state.set(ShareDialogState::Open);
+                    }
+                }
+                TrainyardButton::ShareDialogCopyLevel => {
+                    share_ev_writer.send(ShareDialogEvent::CopyLevel);
+                }
+                TrainyardButton::ShareDialogCopyLevelAndTracks => {
+                    share_ev_writer.send(ShareDialogEvent::CopyLevelAndTracks);
+                }
+                TrainyardButton::ShareDialogPasteTracks => {
+                    share_ev_writer.send(ShareDialogEvent::PasteTracks);
+                }
+                TrainyardButton::ShareDialogClose => {
+                    next_share_dialog_state.set(ShareDialogState::Closed);
+                }
+                TrainyardButton::TutorialNext => {
+                    tutorial_ev_writer.send(TutorialEvent::Next);
+                }
+                TrainyardButton::TutorialSkip => {
+                    tutorial_ev_writer.send(TutorialEvent::Skip);
+                }
+                // the level editor's buttons have a handler of their own
+                TrainyardButton::LevelPickerNewLevel
+                | TrainyardButton::LevelPickerEditLevel(_)
+                | TrainyardButton::LevelEditorTool(_)
+                | TrainyardButton::LevelEditorColor(_)
+                | TrainyardButton::LevelEditorTurn
+                | TrainyardButton::LevelEditorResize { .. }
+                | TrainyardButton::LevelEditorSave => {}
+                // and so do the settings', the debugger's and the replays'
+                TrainyardButton::Settings(_)
+                | TrainyardButton::Debugger(_)
+                | TrainyardButton::Replay(_)
+                | TrainyardButton::LevelSolutionsButton
+                | TrainyardButton::SolutionsDialog(_)
+                | TrainyardButton::SolutionsDialogClose => {}
                 TrainyardButton::LevelWinDialogNextButton => {
-                    start_lvl_ev_writer.send(StartLevelEvent {
-                        level_name: find_next_level(curr_lvl_name.0.as_deref().unwrap(), &levels),
-                    });
+                    match find_next_level(curr_lvl_name.0.as_deref().unwrap(), &levels) {
+                        Some(level_name) => {
+                            start_lvl_ev_writer.send(StartLevelEvent { level_name });
+                        }
+                        // the last level (like one that was just pasted in) has nothing after it
+                        None => {
+                            next_ui_state.set(UIState::LevelPicker);
+                            next_level_state.set(LevelState::None);
+                        }
+                    }
                 }
                 TrainyardButton::LevelWinDialogBackButton => {
                     next_ui_state.set(UIState::LevelPicker);
@@ -160,10 +304,25 @@ pub fn trainyard_ui_button_handler(
     }
 }
 
-fn find_next_level(curr_lvl_name: &str, levels: &Res<StockLevelInfos>) -> String {
+/// The city `offset` places before or after the one the level picker is showing, if there is one.
+fn find_neighbouring_city(
+    page: &LevelPickerPage,
+    levels: &StockLevelInfos,
+    offset: isize,
+) -> Option<String> {
+    let LevelPickerPage::City(city) = page else {
+        return None;
+    };
+    let cities = levels.cities();
+    let index = cities.iter().position(|c| c == city)?;
+    let new_index = index.checked_add_signed(offset)?;
+    cities.get(new_index).map(|city| city.to_string())
+}
+
+fn find_next_level(curr_lvl_name: &str, levels: &Res<StockLevelInfos>) -> Option<String> {
     for (index, level) in levels.0.iter().enumerate() {
         if level.name == curr_lvl_name {
-            return levels.0[index + 1].name.clone();
+            return levels.0.get(index + 1).map(|level| level.name.clone());
         }
     }
     panic!("could not find the next level.");
@@ -0,0 +1,27 @@
+use arboard::Clipboard;
+use bevy::prelude::*;
+
+/// The system clipboard, opened the first time it's used.
+///
+/// It's kept open for as long as the game runs, since on some platforms whatever we copied disappears from the
+/// clipboard once it's closed.
+#[derive(Resource, Default)]
+pub struct SystemClipboard(Option<Clipboard>);
+
+impl SystemClipboard {
+    fn open(&mut self) -> Result<&mut Clipboard, String> {
+        if self.0.is_none() {
+            let clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+            self.0 = Some(clipboard);
+        }
+        Ok(self.0.as_mut().unwrap())
+    }
+
+    pub fn set_text(&mut self, text: &str) -> Result<(), String> {
+        self.open()?.set_text(text).map_err(|e| e.to_string())
+    }
+
+    pub fn get_text(&mut self) -> Result<String, String> {
+        self.open()?.get_text().map_err(|e| e.to_string())
+    }
+}
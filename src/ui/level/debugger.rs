@@ -0,0 +1,229 @@
+use std::fmt::Write;
+
+use bevy::prelude::*;
+use trainyard_core::{direction::Dir, yard::TileEventWithLocation};
+
+use crate::{
+    input::{InputAction, KeyBindings},
+    level::debugger::{DebuggerEvent, DebuggerSnapshot, DebuggerState, TrainDebugger},
+    settings::Settings,
+    ui::{
+        buttons::{create_trainyard_button, TrainyardButton},
+        UIRootContainer,
+    },
+};
+
+use super::{BUTTON_BORDER_COLOR, BUTTON_HEIGHT, BUTTON_TEXT_SIZE, BUTTON_WIDTH};
+
+pub const DEBUGGER_TEXT_SIZE: f32 = 18.0;
+
+/// The panel on the left of the screen with the debugger's controls, shown while the debugger is on.
+#[derive(Component)]
+pub struct DebuggerPanel;
+
+/// Lists the trains and the events that are waiting to happen.
+#[derive(Component)]
+pub struct DebuggerPanelText;
+
+pub struct DebuggerUIPlugin;
+impl Plugin for DebuggerUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(DebuggerState::Off), spawn_debugger_panel)
+            .add_systems(OnEnter(DebuggerState::Off), despawn_debugger_panel)
+            .add_systems(
+                Update,
+                (
+                    debugger_button_handler,
+                    update_debugger_panel.run_if(not(in_state(DebuggerState::Off))),
+                ),
+            );
+    }
+}
+
+fn spawn_debugger_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ui_root_query: Query<Entity, With<UIRootContainer>>,
+    settings: Res<Settings>,
+) {
+    let ui_root = ui_root_query.single();
+    let bindings = &settings.key_bindings;
+    let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
+
+    let panel = (
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(7.0),
+            top: Val::Px(7.0),
+            width: Val::Px(BUTTON_WIDTH),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        DebuggerPanel,
+        Name::new("Debugger panel"),
+    );
+    let row = || Node {
+        flex_direction: FlexDirection::Row,
+        ..default()
+    };
+    let mut half_width_button = |text: &str, event| {
+        create_trainyard_button(
+            &mut commands,
+            text,
+            BUTTON_WIDTH / 2.0 - 10.0,
+            BUTTON_HEIGHT,
+            BUTTON_TEXT_SIZE,
+            BUTTON_BORDER_COLOR,
+            font.clone(),
+            TrainyardButton::Debugger(event),
+        )
+    };
+    let top_row_buttons = [
+        half_width_button(
+            &with_key("Back", bindings, InputAction::DebuggerStepBack),
+            DebuggerEvent::StepBack,
+        ),
+        half_width_button(
+            &play_pause_text(&DebuggerState::Paused, bindings),
+            DebuggerEvent::PlayPause,
+        ),
+    ];
+    let bottom_row_buttons = [
+        half_width_button(
+            &with_key("Half", bindings, InputAction::DebuggerHalfStep),
+            DebuggerEvent::StepHalfTick,
+        ),
+        half_width_button(
+            &with_key("Tick", bindings, InputAction::DebuggerStep),
+            DebuggerEvent::StepTick,
+        ),
+    ];
+
+    let text = (
+        Text::new(""),
+        TextFont {
+            font,
+            font_size: DEBUGGER_TEXT_SIZE,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            width: Val::Px(BUTTON_WIDTH),
+            margin: UiRect::top(Val::Px(10.0)),
+            ..default()
+        },
+        DebuggerPanelText,
+    );
+
+    let panel = commands.spawn(panel).id();
+    let top_row = commands.spawn(row()).id();
+    let bottom_row = commands.spawn(row()).id();
+    let text = commands.spawn(text).id();
+
+    commands.entity(ui_root).add_children(&[panel]);
+    commands
+        .entity(panel)
+        .add_children(&[top_row, bottom_row, text]);
+    commands.entity(top_row).add_children(&top_row_buttons);
+    commands
+        .entity(bottom_row)
+        .add_children(&bottom_row_buttons);
+}
+
+fn despawn_debugger_panel(mut commands: Commands, panel_query: Query<Entity, With<DebuggerPanel>>) {
+    for entity in panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn debugger_button_handler(
+    interaction_query: Query<(&Interaction, &TrainyardButton), Changed<Interaction>>,
+    mut debugger_ev_writer: EventWriter<DebuggerEvent>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if let (Interaction::Pressed, TrainyardButton::Debugger(event)) = (interaction, button) {
+            debugger_ev_writer.send(*event);
+        }
+    }
+}
+
+fn update_debugger_panel(
+    debugger: Res<TrainDebugger>,
+    debugger_state: Res<State<DebuggerState>>,
+    settings: Res<Settings>,
+    mut panel_text_query: Query<&mut Text, (With<DebuggerPanelText>, Without<TrainyardButton>)>,
+    mut button_text_query: Query<(&mut Text, &TrainyardButton)>,
+) {
+    let panel_text = debugger.latest().map(snapshot_text).unwrap_or_default();
+    for mut text in panel_text_query.iter_mut() {
+        if text.0 != panel_text {
+            text.0 = panel_text.clone();
+        }
+    }
+
+    let play_pause = play_pause_text(debugger_state.get(), &settings.key_bindings);
+    for (mut text, button) in button_text_query.iter_mut() {
+        if *button == TrainyardButton::Debugger(DebuggerEvent::PlayPause) && text.0 != play_pause {
+            text.0 = play_pause.clone();
+        }
+    }
+}
+
+/// The text of a button, along with the first key that does the same as the button, if there is one.
+fn with_key(text: &str, bindings: &KeyBindings, action: InputAction) -> String {
+    match bindings.get(action).first() {
+        Some(binding) => format!("{} ({})", text, binding.label()),
+        None => String::from(text),
+    }
+}
+
+fn play_pause_text(state: &DebuggerState, bindings: &KeyBindings) -> String {
+    let text = match state {
+        DebuggerState::Playing => "Pause",
+        DebuggerState::Off | DebuggerState::Paused => "Play",
+    };
+    with_key(text, bindings, InputAction::DebuggerPlayPause)
+}
+
+fn snapshot_text(snapshot: &DebuggerSnapshot) -> String {
+    let mut text = format!(
+        "Tick {}, {}\n",
+        snapshot.ticks,
+        if snapshot.halfway { "halfway" } else { "start" }
+    );
+    if snapshot.crashed {
+        text.push_str("Crashed\n");
+    }
+
+    text.push_str("\nTrains:\n");
+    for train in &snapshot.sim.train_activity {
+        let activity = &train.activity;
+        let _ = writeln!(
+            text,
+            "({}, {}) {:?} from {} -> {:?} to {}",
+            train.row,
+            train.col,
+            activity.start_color,
+            dir_text(activity.from_dir),
+            activity.end_color,
+            dir_text(activity.to_dir),
+        );
+    }
+
+    text.push_str("\nMid-tick:\n");
+    events_text(&mut text, &snapshot.mid_tick_events);
+    text.push_str("\nEnd of tick:\n");
+    events_text(&mut text, &snapshot.end_tick_events);
+    text
+}
+
+fn events_text(text: &mut String, events: &[TileEventWithLocation]) {
+    for event in events {
+        let _ = writeln!(text, "({}, {}) {:?}", event.row, event.col, event.event);
+    }
+}
+
+fn dir_text(dir: Option<Dir>) -> String {
+    dir.map_or(String::from("-"), |dir| format!("{:?}", dir))
+}
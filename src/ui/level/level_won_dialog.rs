@@ -1,10 +1,12 @@
 use bevy::prelude::*;
 
 use crate::{
-    level::LevelState,
+    level::{scoring::LastWinScore, LevelState},
     ui::buttons::{create_trainyard_button, TrainyardButton},
 };
 
+pub const NEW_RECORD_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+
 #[derive(Component)]
 pub struct LevelWonDialogRoot;
 
@@ -24,7 +26,11 @@ impl Plugin for LevelWonDialogPlugin {
     }
 }
 
-fn spawn_level_won_dialog(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn spawn_level_won_dialog(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    last_win: Res<LastWinScore>,
+) {
     let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
 
     // =============================================================================================
@@ -69,8 +75,9 @@ fn spawn_level_won_dialog(mut commands: Commands, asset_server: Res<AssetServer>
     let dialog_box = (
         Node {
             width: Val::Px(300.0),
-            height: Val::Px(300.0),
+            height: Val::Auto,
             min_height: Val::Px(300.0),
+            padding: UiRect::bottom(Val::Px(20.0)),
             border: UiRect::all(Val::Px(3.0)),
             justify_content: JustifyContent::Center,
             flex_direction: FlexDirection::Column,
@@ -101,6 +108,66 @@ fn spawn_level_won_dialog(mut commands: Commands, asset_server: Res<AssetServer>
         },
     );
 
+    // =============================================================================================
+    // the score, next to the best score of each kind
+    // =============================================================================================
+    let score_rows: Vec<(String, Color)> = match (last_win.score, last_win.best) {
+        (Some(score), Some(best)) => [
+            (
+                "Tracks",
+                score.tracks,
+                best.tracks,
+                last_win.new_records.tracks,
+            ),
+            ("Ticks", score.ticks, best.ticks, last_win.new_records.ticks),
+            (
+                "Switches",
+                score.switches,
+                best.switches,
+                last_win.new_records.switches,
+            ),
+        ]
+        .into_iter()
+        .map(|(label, value, best, new_record)| {
+            if new_record {
+                (
+                    format!("{}: {} - new record!", label, value),
+                    NEW_RECORD_COLOR,
+                )
+            } else {
+                (
+                    format!("{}: {} (best {})", label, value, best),
+                    Color::WHITE,
+                )
+            }
+        })
+        .collect(),
+        _ => Vec::new(),
+    };
+    let score_texts: Vec<Entity> = score_rows
+        .into_iter()
+        .map(|(text, color)| {
+            commands
+                .spawn((
+                    Text::new(text),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 23.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                ))
+                .id()
+        })
+        .collect();
+    let score_box = Node {
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        margin: UiRect::bottom(Val::Px(20.0)),
+        ..default()
+    };
+
     // =============================================================================================
     // buttons
     // =============================================================================================
@@ -137,13 +204,18 @@ fn spawn_level_won_dialog(mut commands: Commands, asset_server: Res<AssetServer>
     let dialog_box_spacer = commands.spawn(dialog_box_spacer).id();
     let dialog_box = commands.spawn(dialog_box).id();
     let title_text = commands.spawn(title_text).id();
+    let score_box = commands.spawn(score_box).id();
 
     commands
         .entity(dialog_box_root)
         .add_children(&[dialog_box_spacer, dialog_box]);
-    commands
-        .entity(dialog_box)
-        .add_children(&[title_text, next_level_button, back_button]);
+    commands.entity(dialog_box).add_children(&[
+        title_text,
+        score_box,
+        next_level_button,
+        back_button,
+    ]);
+    commands.entity(score_box).add_children(&score_texts);
 }
 
 fn despawn_level_won_dialog(
@@ -1,4 +1,8 @@
+pub mod debugger;
 pub mod level_won_dialog;
+pub mod replay_panel;
+pub mod share_dialog;
+pub mod solutions_dialog;
 pub mod speed_slider;
 pub mod status_text;
 
@@ -6,7 +10,14 @@ use bevy::prelude::*;
 use speed_slider::{spawn_speed_slider, TrainSpeed};
 use status_text::update_status_text;
 
-use crate::{level::LevelState, NUM_COLS, NUM_ROWS, TILE_SIZE_PX};
+use crate::level::{
+    debugger::DebuggerEvent,
+    hints::HINT_BUTTON_TEXT,
+    history::{REDO_BUTTON_TEXT, UNDO_BUTTON_TEXT},
+    replays::ReplayEvent,
+    yard::MAX_YARD_SIZE_PX,
+    LevelState,
+};
 
 use super::{
     buttons::{create_trainyard_button, TrainyardButton},
@@ -26,7 +37,11 @@ pub struct LevelUIPlugin;
 impl Plugin for LevelUIPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
+            debugger::DebuggerUIPlugin,
             level_won_dialog::LevelWonDialogPlugin,
+            replay_panel::ReplayPanelPlugin,
+            share_dialog::ShareDialogPlugin,
+            solutions_dialog::SolutionsDialogPlugin,
             speed_slider::SpeedSliderPlugin,
         ))
         .add_systems(OnEnter(UIState::Level), spawn_level_ui)
@@ -68,12 +83,12 @@ fn spawn_level_ui(
     );
 
     // =============================================================================================
-    // canvas placeholder: a 672x672 rectangle where the trainyard yard will go
+    // canvas placeholder: where the trainyard yard will go, resized to fit the yard once it's spawned
     // =============================================================================================
     let canvas_placeholder = (
         Node {
-            width: Val::Px(NUM_COLS as f32 * TILE_SIZE_PX),
-            height: Val::Px(NUM_ROWS as f32 * TILE_SIZE_PX),
+            width: Val::Px(MAX_YARD_SIZE_PX),
+            height: Val::Px(MAX_YARD_SIZE_PX),
             ..default()
         },
         BackgroundColor(Color::srgba(1.0, 0.0, 0.0, 0.05)),
@@ -129,6 +144,106 @@ fn spawn_level_ui(
         TrainyardButton::LevelStartEraseButton,
     );
 
+    let hint_button = create_trainyard_button(
+        &mut commands,
+        HINT_BUTTON_TEXT,
+        BUTTON_WIDTH,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::LevelHintButton,
+    );
+
+    let solutions_button = create_trainyard_button(
+        &mut commands,
+        "Solutions",
+        BUTTON_WIDTH,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::LevelSolutionsButton,
+    );
+
+    // sharing and debugging share a row too
+    let share_and_debug_button_row = Node {
+        flex_direction: FlexDirection::Row,
+        ..default()
+    };
+    let share_button = create_trainyard_button(
+        &mut commands,
+        "Share",
+        BUTTON_WIDTH / 2.0 - 10.0,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::LevelShareButton,
+    );
+    let debug_button = create_trainyard_button(
+        &mut commands,
+        "Debug (B)",
+        BUTTON_WIDTH / 2.0 - 10.0,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::Debugger(DebuggerEvent::PlayPause),
+    );
+
+    // undo and redo share a row, so they each get half the width
+    let history_button_row = Node {
+        flex_direction: FlexDirection::Row,
+        ..default()
+    };
+    let undo_button = create_trainyard_button(
+        &mut commands,
+        UNDO_BUTTON_TEXT,
+        BUTTON_WIDTH / 2.0 - 10.0,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::LevelUndoButton,
+    );
+    let redo_button = create_trainyard_button(
+        &mut commands,
+        REDO_BUTTON_TEXT,
+        BUTTON_WIDTH / 2.0 - 10.0,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::LevelRedoButton,
+    );
+
+    // and so do watching the last winning run and showing its trains as a ghost
+    let replay_button_row = Node {
+        flex_direction: FlexDirection::Row,
+        ..default()
+    };
+    let replay_button = create_trainyard_button(
+        &mut commands,
+        "Replay",
+        BUTTON_WIDTH / 2.0 - 10.0,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::Replay(ReplayEvent::Watch),
+    );
+    let ghost_button = create_trainyard_button(
+        &mut commands,
+        "Ghost",
+        BUTTON_WIDTH / 2.0 - 10.0,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::Replay(ReplayEvent::ToggleGhost),
+    );
+
     // =============================================================================================
     // Status indicator (only visible when the level is running)
     // =============================================================================================
@@ -167,6 +282,18 @@ fn spawn_level_ui(
             Name::new("Yard placeholder in UI"),
         ))
         .id();
+    let history_button_row = commands.spawn(history_button_row).id();
+    commands
+        .entity(history_button_row)
+        .add_children(&[undo_button, redo_button]);
+    let share_and_debug_button_row = commands.spawn(share_and_debug_button_row).id();
+    commands
+        .entity(share_and_debug_button_row)
+        .add_children(&[share_button, debug_button]);
+    let replay_button_row = commands.spawn(replay_button_row).id();
+    commands
+        .entity(replay_button_row)
+        .add_children(&[replay_button, ghost_button]);
     let status_text_box = commands.spawn(status_text_box).id();
     let slider = spawn_speed_slider(&mut commands, font, &train_speed);
     let status_text = commands.spawn((status_text, LevelStatusText)).id();
@@ -179,6 +306,11 @@ fn spawn_level_ui(
         back_button,
         start_trains_button,
         start_erase_button,
+        history_button_row,
+        hint_button,
+        solutions_button,
+        share_and_debug_button_row,
+        replay_button_row,
         slider,
         status_text_box,
     ]);
@@ -0,0 +1,193 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+
+use crate::{
+    level::replays::{ReplayEvent, ReplayPlayer, ReplayState},
+    ui::{
+        buttons::{create_trainyard_button, TrainyardButton},
+        UIRootContainer, BTN_BG, BTN_BORDER_BLUE,
+    },
+};
+
+use super::{BUTTON_BORDER_COLOR, BUTTON_HEIGHT, BUTTON_TEXT_SIZE, BUTTON_WIDTH};
+
+pub const SCRUB_BAR_HEIGHT: f32 = 30.0;
+
+/// The panel on the left of the screen with the controls for the replay that's being watched.
+#[derive(Component)]
+pub struct ReplayPanel;
+
+#[derive(Component)]
+pub struct ReplayPanelText;
+
+/// The bar that's clicked or dragged along to move the replay to another tick.
+#[derive(Component)]
+pub struct ReplayScrubBar;
+
+/// The part of the scrub bar that's filled in, showing how far into the replay the yard is.
+#[derive(Component)]
+pub struct ReplayScrubBarFill;
+
+pub struct ReplayPanelPlugin;
+impl Plugin for ReplayPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ReplayState::Watching), spawn_replay_panel)
+            .add_systems(OnExit(ReplayState::Watching), despawn_replay_panel)
+            .add_systems(
+                Update,
+                (
+                    replay_button_handler,
+                    (handle_scrub_bar_interactions, update_replay_panel)
+                        .run_if(in_state(ReplayState::Watching)),
+                ),
+            );
+    }
+}
+
+fn spawn_replay_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ui_root_query: Query<Entity, With<UIRootContainer>>,
+) {
+    let ui_root = ui_root_query.single();
+    let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
+
+    let panel = (
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(7.0),
+            top: Val::Px(7.0),
+            width: Val::Px(BUTTON_WIDTH),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ReplayPanel,
+        Name::new("Replay panel"),
+    );
+
+    let text = (
+        Text::new(""),
+        TextFont {
+            font: font.clone(),
+            font_size: BUTTON_TEXT_SIZE,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        ReplayPanelText,
+    );
+
+    let scrub_bar = (
+        Node {
+            width: Val::Px(BUTTON_WIDTH),
+            height: Val::Px(SCRUB_BAR_HEIGHT),
+            margin: UiRect::vertical(Val::Px(10.0)),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BorderColor(Color::WHITE),
+        BorderRadius::all(Val::Px(12.0)),
+        BackgroundColor(Color::BLACK),
+        RelativeCursorPosition::default(),
+        Button,
+        ReplayScrubBar,
+    );
+    let scrub_bar_fill = (
+        Node {
+            width: Val::Percent(0.0),
+            height: Val::Percent(100.0),
+            border: UiRect::all(Val::Px(3.0)),
+            ..default()
+        },
+        BorderColor(BTN_BORDER_BLUE),
+        BorderRadius::all(Val::Px(12.0)),
+        BackgroundColor(BTN_BG),
+        ReplayScrubBarFill,
+    );
+
+    let stop_button = create_trainyard_button(
+        &mut commands,
+        "Stop replay",
+        BUTTON_WIDTH,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font,
+        TrainyardButton::Replay(ReplayEvent::Stop),
+    );
+
+    let panel = commands.spawn(panel).id();
+    let text = commands.spawn(text).id();
+    let scrub_bar = commands.spawn(scrub_bar).id();
+    let scrub_bar_fill = commands.spawn(scrub_bar_fill).id();
+
+    commands.entity(ui_root).add_children(&[panel]);
+    commands
+        .entity(panel)
+        .add_children(&[text, scrub_bar, stop_button]);
+    commands.entity(scrub_bar).add_children(&[scrub_bar_fill]);
+}
+
+fn despawn_replay_panel(mut commands: Commands, panel_query: Query<Entity, With<ReplayPanel>>) {
+    for entity in panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn replay_button_handler(
+    interaction_query: Query<(&Interaction, &TrainyardButton), Changed<Interaction>>,
+    mut replay_ev_writer: EventWriter<ReplayEvent>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if let (Interaction::Pressed, TrainyardButton::Replay(event)) = (interaction, button) {
+            replay_ev_writer.send(event.clone());
+        }
+    }
+}
+
+/// Moves the replay to wherever the scrub bar is being pressed, for as long as it's held down.
+fn handle_scrub_bar_interactions(
+    interaction_query: Query<(&Interaction, &RelativeCursorPosition), With<ReplayScrubBar>>,
+    player: Res<ReplayPlayer>,
+    mut replay_ev_writer: EventWriter<ReplayEvent>,
+) {
+    let Some(replay) = &player.replay else {
+        return;
+    };
+    for (interaction, rel_position) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(rel_position) = rel_position.normalized {
+            let tick = (rel_position.x.clamp(0.0, 1.0) * replay.len() as f32).round() as usize;
+            if tick != player.tick {
+                replay_ev_writer.send(ReplayEvent::SeekTo(tick));
+            }
+        }
+    }
+}
+
+fn update_replay_panel(
+    player: Res<ReplayPlayer>,
+    mut text_query: Query<&mut Text, With<ReplayPanelText>>,
+    mut fill_query: Query<&mut Node, With<ReplayScrubBarFill>>,
+) {
+    let Some(replay) = &player.replay else {
+        return;
+    };
+    // the trains carry on past the end of the replay, but there's nothing left to see
+    let tick = player.tick.min(replay.len());
+    let panel_text = format!("Replay: tick {} of {}", tick, replay.len());
+    for mut text in text_query.iter_mut() {
+        if text.0 != panel_text {
+            text.0 = panel_text.clone();
+        }
+    }
+
+    let fill_width = Val::Percent(100.0 * tick as f32 / replay.len().max(1) as f32);
+    for mut node in fill_query.iter_mut() {
+        if node.width != fill_width {
+            node.width = fill_width;
+        }
+    }
+}
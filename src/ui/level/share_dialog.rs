@@ -0,0 +1,324 @@
+use bevy::{prelude::*, text::LineBreak, ui::FocusPolicy};
+use trainyard_core::{
+    level::LevelLoadInfo,
+    yard_code::{decode_yard_code, encode_yard_code},
+};
+
+use crate::{
+    level::{
+        history::EditHistory,
+        loader::StockLevelInfos,
+        yard::Yard,
+        CurrentLevelName, LevelState,
+    },
+    ui::{
+        buttons::{create_trainyard_button, TrainyardButton},
+        clipboard::SystemClipboard,
+    },
+};
+
+/// Whether the dialog for copying and pasting the current level as a code is showing.
+/// Drawing on the yard is paused while it's open.
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShareDialogState {
+    #[default]
+    Closed,
+    Open,
+}
+
+#[derive(Event, Clone, Copy, PartialEq, Eq)]
+pub enum ShareDialogEvent {
+    CopyLevel,
+    CopyLevelAndTracks,
+    PasteTracks,
+}
+
+#[derive(Component)]
+pub struct ShareDialogRoot;
+
+/// The text showing the current level's code.
+#[derive(Component)]
+pub struct ShareDialogCode;
+
+/// The text reporting how the last copy or paste went.
+#[derive(Component)]
+pub struct ShareDialogMessage;
+
+pub struct ShareDialogPlugin;
+impl Plugin for ShareDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<ShareDialogState>()
+            .add_event::<ShareDialogEvent>()
+            .add_systems(OnEnter(ShareDialogState::Open), spawn_share_dialog)
+            .add_systems(OnExit(ShareDialogState::Open), despawn_share_dialog)
+            .add_systems(OnExit(LevelState::Editing), close_share_dialog)
+            .add_systems(
+                Update,
+                (handle_share_dialog_events, refresh_share_dialog_code)
+                    .chain()
+                    .run_if(in_state(ShareDialogState::Open).and(on_event::<ShareDialogEvent>)),
+            );
+    }
+}
+
+/// The code for the level being played, along with the tracks drawn on it if `with_tracks` is set.
+fn current_level_code(
+    curr_lvl_name: &CurrentLevelName,
+    levels: &StockLevelInfos,
+    yard: &Yard,
+    with_tracks: bool,
+) -> Result<String, String> {
+    let level = curr_lvl_name
+        .0
+        .as_ref()
+        .and_then(|name| levels.find_level(name))
+        .ok_or_else(|| String::from("no level is being played"))?;
+    let tracks = with_tracks.then(|| yard.sim.get_progress());
+    encode_yard_code(level, tracks.as_deref()).map_err(|e| e.to_string())
+}
+
+fn spawn_share_dialog(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    curr_lvl_name: Res<CurrentLevelName>,
+    levels: Res<StockLevelInfos>,
+    yard_query: Query<&Yard>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
+    let code = yard_query
+        .get_single()
+        .map_err(|e| e.to_string())
+        .and_then(|yard| current_level_code(&curr_lvl_name, &levels, yard, true))
+        .unwrap_or_else(|e| format!("this level can't be shared: {}", e));
+
+    // =============================================================================================
+    // root container that darkens the rest of the screen
+    // =============================================================================================
+    let dialog_root = (
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        // keeps clicks from reaching the buttons underneath
+        FocusPolicy::Block,
+        ShareDialogRoot,
+    );
+
+    // =============================================================================================
+    // box to contain the dialog
+    // =============================================================================================
+    let dialog_box = (
+        Node {
+            width: Val::Px(460.0),
+            border: UiRect::all(Val::Px(3.0)),
+            padding: UiRect::all(Val::Px(20.0)),
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderColor(Color::WHITE),
+        BorderRadius::all(Val::Px(24.0)),
+        BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+    );
+
+    // =============================================================================================
+    // texts: a title, the code itself, and a line for how copying/pasting went
+    // =============================================================================================
+    let title_text = (
+        Text::new("Share this level"),
+        TextFont {
+            font: font.clone(),
+            font_size: 35.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            margin: UiRect::bottom(Val::Px(15.0)),
+            ..default()
+        },
+    );
+    let code_text = (
+        Text::new(code),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        // codes are one long word, so they have to be allowed to break anywhere
+        TextLayout::new(JustifyText::Center, LineBreak::AnyCharacter),
+        Node {
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        ShareDialogCode,
+    );
+    let message_text = (
+        Text::new(""),
+        TextFont {
+            font: font.clone(),
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.0, 1.0, 0.0)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            width: Val::Percent(100.0),
+            min_height: Val::Px(30.0),
+            margin: UiRect::top(Val::Px(10.0)),
+            ..default()
+        },
+        ShareDialogMessage,
+    );
+
+    // =============================================================================================
+    // buttons
+    // =============================================================================================
+
+    let button_width = 260.0;
+    let button_height = 50.0;
+    let button_text_size = 23.0;
+    let button_border_color = Color::WHITE;
+
+    let buttons = [
+        ("Copy level", TrainyardButton::ShareDialogCopyLevel),
+        (
+            "Copy level and tracks",
+            TrainyardButton::ShareDialogCopyLevelAndTracks,
+        ),
+        ("Paste tracks", TrainyardButton::ShareDialogPasteTracks),
+        ("Close", TrainyardButton::ShareDialogClose),
+    ]
+    .map(|(text, button_type)| {
+        create_trainyard_button(
+            &mut commands,
+            text,
+            button_width,
+            button_height,
+            button_text_size,
+            button_border_color,
+            font.clone(),
+            button_type,
+        )
+    });
+
+    // putting it together:
+
+    let dialog_root = commands.spawn(dialog_root).id();
+    let dialog_box = commands.spawn(dialog_box).id();
+    let title_text = commands.spawn(title_text).id();
+    let code_text = commands.spawn(code_text).id();
+    let message_text = commands.spawn(message_text).id();
+
+    commands.entity(dialog_root).add_children(&[dialog_box]);
+    commands
+        .entity(dialog_box)
+        .add_children(&[title_text, code_text, message_text])
+        .add_children(&buttons);
+}
+
+fn despawn_share_dialog(mut commands: Commands, query: Query<Entity, With<ShareDialogRoot>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn close_share_dialog(mut next_state: ResMut<NextState<ShareDialogState>>) {
+    next_state.set(ShareDialogState::Closed);
+}
+
+fn handle_share_dialog_events(
+    mut events: EventReader<ShareDialogEvent>,
+    mut clipboard: ResMut<SystemClipboard>,
+    curr_lvl_name: Res<CurrentLevelName>,
+    levels: Res<StockLevelInfos>,
+    mut history: ResMut<EditHistory>,
+    mut yard_query: Query<&mut Yard>,
+    mut message_query: Query<(&mut Text, &mut TextColor), With<ShareDialogMessage>>,
+) {
+    let Ok(mut yard) = yard_query.get_single_mut() else {
+        events.clear();
+        return;
+    };
+
+    for event in events.read() {
+        let result = match event {
+            ShareDialogEvent::CopyLevel | ShareDialogEvent::CopyLevelAndTracks => {
+                let with_tracks = *event == ShareDialogEvent::CopyLevelAndTracks;
+                current_level_code(&curr_lvl_name, &levels, &yard, with_tracks)
+                    .and_then(|code| clipboard.set_text(&code))
+                    .map(|()| String::from("Copied!"))
+            }
+            ShareDialogEvent::PasteTracks => clipboard.get_text().and_then(|code| {
+                paste_tracks(&code, &curr_lvl_name, &levels, &mut yard, &mut history)
+            }),
+        };
+
+        for (mut text, mut color) in message_query.iter_mut() {
+            (text.0, color.0) = match &result {
+                Ok(message) => (message.clone(), Color::srgb(0.0, 1.0, 0.0)),
+                Err(message) => (message.clone(), Color::srgb(1.0, 0.3, 0.3)),
+            };
+        }
+    }
+}
+
+/// Keeps the code in the dialog up to date with the tracks, which pasting can change.
+fn refresh_share_dialog_code(
+    curr_lvl_name: Res<CurrentLevelName>,
+    levels: Res<StockLevelInfos>,
+    yard_query: Query<&Yard>,
+    mut code_text_query: Query<&mut Text, With<ShareDialogCode>>,
+) {
+    let Ok(yard) = yard_query.get_single() else {
+        return;
+    };
+    if let Ok(code) = current_level_code(&curr_lvl_name, &levels, yard, true) {
+        for mut text in code_text_query.iter_mut() {
+            text.0 = code.clone();
+        }
+    }
+}
+
+/// Draws the tracks from `code` onto the current level, as long as the code is for this level (or has no level in
+/// it at all, like `hh_...`).
+fn paste_tracks(
+    code: &str,
+    curr_lvl_name: &CurrentLevelName,
+    levels: &StockLevelInfos,
+    yard: &mut Yard,
+    history: &mut EditHistory,
+) -> Result<String, String> {
+    let (pasted_level, tracks) = decode_yard_code(code).map_err(|e| e.to_string())?;
+    let Some(tracks) = tracks else {
+        return Err(String::from("that code doesn't have any tracks in it"));
+    };
+    let Some(name) = curr_lvl_name.0.as_ref() else {
+        return Err(String::from("no level is being played"));
+    };
+    let current_code = current_level_code(curr_lvl_name, levels, yard, false)?;
+    let pasted_code = encode_yard_code(&pasted_level, None).map_err(|e| e.to_string())?;
+    let empty_level = LevelLoadInfo {
+        width: yard.sim.width() as u8,
+        height: yard.sim.height() as u8,
+        ..default()
+    };
+    let empty_code = encode_yard_code(&empty_level, None).map_err(|e| e.to_string())?;
+    if pasted_code != current_code && pasted_code != empty_code {
+        return Err(String::from(
+            "that code is for a different level, paste it in the level picker instead",
+        ));
+    }
+
+    let before = yard.sim.get_progress();
+    yard.sim.set_progress(&tracks);
+    history.record_separate_edit(name, &before, &yard.sim.get_progress());
+    Ok(String::from("Pasted!"))
+}
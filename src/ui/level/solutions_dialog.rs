@@ -0,0 +1,534 @@
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    ui::FocusPolicy,
+};
+
+use crate::{
+    input::TypingText,
+    level::{
+        history::EditHistory,
+        persistence::{GameLevelProgress, SolutionSlot},
+        yard::Yard,
+        CurrentLevelName, LevelState,
+    },
+    ui::{
+        buttons::{create_trainyard_button, TrainyardButton},
+        BTN_BORDER_GREEN,
+    },
+};
+
+pub const SELECTED_SOLUTION_BORDER_COLOR: Color = BTN_BORDER_GREEN;
+/// The longest name a solution can be given, which still fits on its button in the list.
+pub const MAX_SOLUTION_NAME_LEN: usize = 24;
+
+/// Whether the dialog for saving and loading the current level's solutions is showing.
+/// Drawing on the yard is paused while it's open.
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SolutionsDialogState {
+    #[default]
+    Closed,
+    Open,
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum SolutionsDialogEvent {
+    Select(SolutionSlot),
+    /// Saves the tracks on the yard in a new slot, named with what's been typed.
+    SaveAsNew,
+    /// Gives the selected slot the name that's been typed.
+    Rename,
+    /// Replaces the tracks on the yard with the selected slot's.
+    Load,
+    /// Copies the selected slot to a new one, named with what's been typed.
+    Duplicate,
+    Delete,
+}
+
+/// The slot that the dialog's buttons act on, and how the last of them went.
+#[derive(Resource)]
+pub struct SolutionsDialog {
+    pub selected: SolutionSlot,
+    pub message: Result<String, String>,
+}
+
+impl Default for SolutionsDialog {
+    fn default() -> Self {
+        Self {
+            selected: SolutionSlot::Autosave,
+            message: Ok(String::new()),
+        }
+    }
+}
+
+/// The name typed into the dialog, for the buttons that make or rename a slot. Kept apart from `SolutionsDialog` so
+/// that typing doesn't rebuild the list of slots.
+#[derive(Resource, Default)]
+pub struct SolutionNameInput(pub String);
+
+#[derive(Component)]
+pub struct SolutionsDialogRoot;
+
+/// The text showing what's been typed into `SolutionNameInput`.
+#[derive(Component)]
+pub struct SolutionNameText;
+
+/// The column of buttons for picking a slot, which is rebuilt whenever the slots change.
+#[derive(Component)]
+pub struct SolutionsList;
+
+/// The text reporting how the last button went.
+#[derive(Component)]
+pub struct SolutionsDialogMessage;
+
+pub struct SolutionsDialogPlugin;
+impl Plugin for SolutionsDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<SolutionsDialogState>()
+            .add_event::<SolutionsDialogEvent>()
+            .init_resource::<SolutionsDialog>()
+            .init_resource::<SolutionNameInput>()
+            .add_systems(
+                OnEnter(SolutionsDialogState::Open),
+                (spawn_solutions_dialog, start_typing),
+            )
+            .add_systems(
+                OnExit(SolutionsDialogState::Open),
+                (despawn_solutions_dialog, stop_typing),
+            )
+            .add_systems(OnExit(LevelState::Editing), close_solutions_dialog)
+            .add_systems(
+                Update,
+                (
+                    solutions_dialog_button_handler,
+                    (
+                        type_solution_name,
+                        refresh_solution_name_text.run_if(resource_changed::<SolutionNameInput>),
+                        handle_solutions_dialog_events.run_if(on_event::<SolutionsDialogEvent>),
+                        (refresh_solutions_list, refresh_solutions_dialog_message).run_if(
+                            resource_changed::<SolutionsDialog>
+                                .or(resource_changed::<GameLevelProgress>),
+                        ),
+                    )
+                        .chain()
+                        .run_if(in_state(SolutionsDialogState::Open)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_solutions_dialog(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut dialog: ResMut<SolutionsDialog>,
+    mut name_input: ResMut<SolutionNameInput>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
+    *dialog = SolutionsDialog::default();
+    name_input.0.clear();
+
+    // =============================================================================================
+    // root container that darkens the rest of the screen
+    // =============================================================================================
+    let dialog_root = (
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        // keeps clicks from reaching the buttons underneath
+        FocusPolicy::Block,
+        SolutionsDialogRoot,
+    );
+
+    // =============================================================================================
+    // box to contain the dialog
+    // =============================================================================================
+    let dialog_box = (
+        Node {
+            width: Val::Px(460.0),
+            border: UiRect::all(Val::Px(3.0)),
+            padding: UiRect::all(Val::Px(20.0)),
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderColor(Color::WHITE),
+        BorderRadius::all(Val::Px(24.0)),
+        BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+    );
+
+    // =============================================================================================
+    // texts: a title, the name being typed, and a line for how the last button went
+    // =============================================================================================
+    let title_text = (
+        Text::new("Solutions"),
+        TextFont {
+            font: font.clone(),
+            font_size: 35.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            margin: UiRect::bottom(Val::Px(15.0)),
+            ..default()
+        },
+    );
+    let solutions_list = (
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        SolutionsList,
+    );
+    let name_text = (
+        Text::new(name_line("")),
+        TextFont {
+            font: font.clone(),
+            font_size: 23.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            margin: UiRect::top(Val::Px(10.0)),
+            ..default()
+        },
+        SolutionNameText,
+    );
+    let message_text = (
+        Text::new(""),
+        TextFont {
+            font: font.clone(),
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.0, 1.0, 0.0)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            width: Val::Percent(100.0),
+            min_height: Val::Px(30.0),
+            margin: UiRect::top(Val::Px(10.0)),
+            ..default()
+        },
+        SolutionsDialogMessage,
+    );
+
+    // =============================================================================================
+    // buttons: the ones acting on the selected slot two to a row, then the close button
+    // =============================================================================================
+
+    let button_width = 190.0;
+    let button_height = 50.0;
+    let button_text_size = 23.0;
+    let button_border_color = Color::WHITE;
+
+    let button_rows = [
+        [
+            ("Save as new", SolutionsDialogEvent::SaveAsNew),
+            ("Rename", SolutionsDialogEvent::Rename),
+        ],
+        [
+            ("Load", SolutionsDialogEvent::Load),
+            ("Duplicate", SolutionsDialogEvent::Duplicate),
+        ],
+    ];
+    let button_rows = button_rows.map(|buttons| {
+        let buttons = buttons.map(|(text, event)| {
+            create_trainyard_button(
+                &mut commands,
+                text,
+                button_width,
+                button_height,
+                button_text_size,
+                button_border_color,
+                font.clone(),
+                TrainyardButton::SolutionsDialog(event),
+            )
+        });
+        let row = commands
+            .spawn(Node {
+                flex_direction: FlexDirection::Row,
+                ..default()
+            })
+            .id();
+        commands.entity(row).add_children(&buttons);
+        row
+    });
+    let delete_button = create_trainyard_button(
+        &mut commands,
+        "Delete",
+        button_width,
+        button_height,
+        button_text_size,
+        button_border_color,
+        font.clone(),
+        TrainyardButton::SolutionsDialog(SolutionsDialogEvent::Delete),
+    );
+    let close_button = create_trainyard_button(
+        &mut commands,
+        "Close",
+        button_width,
+        button_height,
+        button_text_size,
+        button_border_color,
+        font.clone(),
+        TrainyardButton::SolutionsDialogClose,
+    );
+    let last_row = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        })
+        .id();
+    commands
+        .entity(last_row)
+        .add_children(&[delete_button, close_button]);
+
+    // putting it together:
+
+    let dialog_root = commands.spawn(dialog_root).id();
+    let dialog_box = commands.spawn(dialog_box).id();
+    let title_text = commands.spawn(title_text).id();
+    let solutions_list = commands.spawn(solutions_list).id();
+    let name_text = commands.spawn(name_text).id();
+    let message_text = commands.spawn(message_text).id();
+
+    commands.entity(dialog_root).add_children(&[dialog_box]);
+    commands.entity(dialog_box).add_children(&[
+        title_text,
+        solutions_list,
+        name_text,
+        message_text,
+    ]);
+    commands.entity(dialog_box).add_children(&button_rows);
+    commands.entity(dialog_box).add_children(&[last_row]);
+}
+
+fn name_line(name: &str) -> String {
+    format!("Name: {}_", name)
+}
+
+fn start_typing(mut typing: ResMut<TypingText>) {
+    typing.0 = true;
+}
+
+fn stop_typing(mut typing: ResMut<TypingText>) {
+    typing.0 = false;
+}
+
+/// Types the keys pressed while the dialog is open into `SolutionNameInput`.
+fn type_solution_name(
+    mut key_events: EventReader<KeyboardInput>,
+    mut name_input: ResMut<SolutionNameInput>,
+) {
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        let typed = match &event.logical_key {
+            Key::Backspace => {
+                name_input.0.pop();
+                continue;
+            }
+            Key::Space => " ",
+            Key::Character(text) => text.as_str(),
+            _ => continue,
+        };
+        for c in typed.chars().filter(|c| !c.is_control()) {
+            if name_input.0.chars().count() < MAX_SOLUTION_NAME_LEN {
+                name_input.0.push(c);
+            }
+        }
+    }
+}
+
+fn refresh_solution_name_text(
+    name_input: Res<SolutionNameInput>,
+    mut text_query: Query<&mut Text, With<SolutionNameText>>,
+) {
+    for mut text in text_query.iter_mut() {
+        text.0 = name_line(&name_input.0);
+    }
+}
+
+fn despawn_solutions_dialog(
+    mut commands: Commands,
+    query: Query<Entity, With<SolutionsDialogRoot>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn close_solutions_dialog(mut next_state: ResMut<NextState<SolutionsDialogState>>) {
+    next_state.set(SolutionsDialogState::Closed);
+}
+
+fn solutions_dialog_button_handler(
+    interaction_query: Query<(&Interaction, &TrainyardButton), Changed<Interaction>>,
+    level_state: Res<State<LevelState>>,
+    mut next_state: ResMut<NextState<SolutionsDialogState>>,
+    mut solutions_ev_writer: EventWriter<SolutionsDialogEvent>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            TrainyardButton::LevelSolutionsButton if *level_state.get() == LevelState::Editing => {
+                next_state.set(SolutionsDialogState::Open);
+            }
+            TrainyardButton::SolutionsDialog(event) => {
+                solutions_ev_writer.send(event.clone());
+            }
+            TrainyardButton::SolutionsDialogClose => {
+                next_state.set(SolutionsDialogState::Closed);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn handle_solutions_dialog_events(
+    mut events: EventReader<SolutionsDialogEvent>,
+    mut dialog: ResMut<SolutionsDialog>,
+    mut name_input: ResMut<SolutionNameInput>,
+    mut persistence: ResMut<GameLevelProgress>,
+    curr_lvl_name: Res<CurrentLevelName>,
+    mut history: ResMut<EditHistory>,
+    mut yard_query: Query<&mut Yard>,
+) {
+    let (Some(name), Ok(mut yard)) = (curr_lvl_name.0.as_ref(), yard_query.get_single_mut()) else {
+        events.clear();
+        return;
+    };
+    let progress = persistence.0.entry(name.clone()).or_default();
+
+    for event in events.read() {
+        let selected = dialog.selected.clone();
+        dialog.message = match event {
+            SolutionsDialogEvent::Select(slot) => {
+                dialog.selected = slot.clone();
+                Ok(String::new())
+            }
+            SolutionsDialogEvent::SaveAsNew => progress
+                .save_new_solution(&name_input.0, yard.sim.get_progress())
+                .map(|slot| {
+                    name_input.0.clear();
+                    dialog.selected = slot;
+                    format!("Saved as \"{}\"", dialog.selected.label())
+                }),
+            SolutionsDialogEvent::Rename => match &selected {
+                SolutionSlot::Autosave => Err(String::from("the autosave can't be renamed")),
+                SolutionSlot::Named(solution_name) => progress
+                    .rename_solution(solution_name, &name_input.0)
+                    .map(|slot| {
+                        name_input.0.clear();
+                        dialog.selected = slot;
+                        format!(
+                            "Renamed \"{}\" to \"{}\"",
+                            solution_name,
+                            dialog.selected.label()
+                        )
+                    }),
+            },
+            SolutionsDialogEvent::Load => match progress.solution(&selected) {
+                Some(tracks) => {
+                    let before = yard.sim.get_progress();
+                    yard.sim.set_progress(tracks);
+                    history.record_separate_edit(name, &before, &yard.sim.get_progress());
+                    Ok(format!("Loaded \"{}\"", selected.label()))
+                }
+                None => Err(String::from("that solution isn't there anymore")),
+            },
+            SolutionsDialogEvent::Duplicate => match progress.solution(&selected).cloned() {
+                Some(tracks) => progress
+                    .save_new_solution(&name_input.0, tracks)
+                    .map(|slot| {
+                        name_input.0.clear();
+                        dialog.selected = slot;
+                        format!(
+                            "Copied \"{}\" to \"{}\"",
+                            selected.label(),
+                            dialog.selected.label()
+                        )
+                    }),
+                None => Err(String::from("that solution isn't there anymore")),
+            },
+            SolutionsDialogEvent::Delete => match &selected {
+                // the autosave is written again every time the level is left anyway
+                SolutionSlot::Autosave => Err(String::from("the autosave can't be deleted")),
+                SolutionSlot::Named(solution_name) => {
+                    progress.saved_solutions.remove(solution_name);
+                    dialog.selected = SolutionSlot::Autosave;
+                    Ok(format!("Deleted \"{}\"", solution_name))
+                }
+            },
+        };
+    }
+}
+
+fn refresh_solutions_list(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    dialog: Res<SolutionsDialog>,
+    persistence: Res<GameLevelProgress>,
+    curr_lvl_name: Res<CurrentLevelName>,
+    list_query: Query<Entity, With<SolutionsList>>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
+    let slots: Vec<SolutionSlot> = curr_lvl_name
+        .0
+        .as_ref()
+        .and_then(|name| persistence.0.get(name))
+        .map_or(vec![SolutionSlot::Autosave], |progress| {
+            progress.solution_slots().collect()
+        });
+
+    for list in list_query.iter() {
+        commands.entity(list).despawn_descendants();
+        for slot in &slots {
+            let border_color = if *slot == dialog.selected {
+                SELECTED_SOLUTION_BORDER_COLOR
+            } else {
+                Color::WHITE
+            };
+            let button = create_trainyard_button(
+                &mut commands,
+                slot.label(),
+                380.0,
+                40.0,
+                20.0,
+                border_color,
+                font.clone(),
+                TrainyardButton::SolutionsDialog(SolutionsDialogEvent::Select(slot.clone())),
+            );
+            commands.entity(list).add_children(&[button]);
+        }
+    }
+}
+
+fn refresh_solutions_dialog_message(
+    dialog: Res<SolutionsDialog>,
+    mut message_query: Query<(&mut Text, &mut TextColor), With<SolutionsDialogMessage>>,
+) {
+    for (mut text, mut color) in message_query.iter_mut() {
+        (text.0, color.0) = match &dialog.message {
+            Ok(message) => (message.clone(), Color::srgb(0.0, 1.0, 0.0)),
+            Err(message) => (message.clone(), Color::srgb(1.0, 0.3, 0.3)),
+        };
+    }
+}
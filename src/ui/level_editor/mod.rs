@@ -0,0 +1,376 @@
+use bevy::prelude::*;
+use trainyard_core::{direction::Dir, trains::TrainColor};
+
+use crate::{
+    level::{yard::MAX_YARD_SIZE_PX, LevelState},
+    level_editor::{EditorTool, LevelEditor, LevelEditorEvent},
+};
+
+use super::{
+    buttons::{create_trainyard_button, TrainyardButton},
+    level::{
+        YardPlaceholderNode, BUTTON_BORDER_COLOR, BUTTON_HEIGHT, BUTTON_TEXT_SIZE, BUTTON_WIDTH,
+    },
+    UIState,
+};
+
+/// The palette's buttons are shorter than the level's, so that they all fit next to the yard.
+pub const PALETTE_BUTTON_HEIGHT: f32 = 40.0;
+pub const COLOR_SWATCH_SIZE_PX: f32 = 25.0;
+pub const SELECTED_TOOL_BORDER_COLOR: Color = super::BTN_BORDER_GREEN;
+pub const SELECTED_COLOR_BORDER_COLOR: Color = Color::WHITE;
+
+const COLORS: [TrainColor; 7] = [
+    TrainColor::Red,
+    TrainColor::Blue,
+    TrainColor::Yellow,
+    TrainColor::Purple,
+    TrainColor::Green,
+    TrainColor::Orange,
+    TrainColor::Brown,
+];
+
+#[derive(Component)]
+pub struct LevelEditorUIRoot;
+
+/// Shows the level's name and size.
+#[derive(Component)]
+pub struct LevelEditorTitle;
+
+/// Shows what happened last in the editor, like why the level couldn't be saved.
+#[derive(Component)]
+pub struct LevelEditorMessage;
+
+pub struct LevelEditorUIPlugin;
+impl Plugin for LevelEditorUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(UIState::LevelEditor), spawn_level_editor_ui)
+            .add_systems(OnExit(UIState::LevelEditor), teardown_level_editor_ui)
+            .add_systems(
+                Update,
+                (
+                    level_editor_button_handler,
+                    (update_level_editor_ui, update_level_editor_title)
+                        .run_if(in_state(UIState::LevelEditor)),
+                ),
+            );
+    }
+}
+
+fn spawn_level_editor_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ui_root_query: Query<Entity, With<super::UIRootContainer>>,
+    editor: Res<LevelEditor>,
+) {
+    let ui_root = ui_root_query.single();
+    let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
+
+    // =============================================================================================
+    // root container for the level editor, laid out like a level
+    // =============================================================================================
+    let editor_root = (
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        LevelEditorUIRoot,
+    );
+
+    let canvas_placeholder = (
+        Node {
+            width: Val::Px(MAX_YARD_SIZE_PX),
+            height: Val::Px(MAX_YARD_SIZE_PX),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(1.0, 0.0, 0.0, 0.05)),
+        YardPlaceholderNode,
+        Name::new("Yard placeholder in UI"),
+    );
+
+    // =============================================================================================
+    // the palette on the right
+    // =============================================================================================
+    let palette = Node {
+        width: Val::Auto,
+        height: Val::Percent(100.0),
+        justify_content: JustifyContent::Center,
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        margin: UiRect::all(Val::Px(20.0)),
+        ..default()
+    };
+    let row = || Node {
+        flex_direction: FlexDirection::Row,
+        flex_wrap: FlexWrap::Wrap,
+        justify_content: JustifyContent::Center,
+        width: Val::Px(BUTTON_WIDTH + 20.0),
+        ..default()
+    };
+
+    let name_text = (
+        Text::new(title_text(&editor)),
+        TextFont {
+            font: font.clone(),
+            font_size: 30.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        LevelEditorTitle,
+    );
+
+    let half_width_button = |commands: &mut Commands, text: &str, button_type| {
+        create_trainyard_button(
+            commands,
+            text,
+            BUTTON_WIDTH / 2.0 - 10.0,
+            PALETTE_BUTTON_HEIGHT,
+            BUTTON_TEXT_SIZE,
+            BUTTON_BORDER_COLOR,
+            font.clone(),
+            button_type,
+        )
+    };
+
+    let back_and_save_buttons = vec![
+        half_width_button(&mut commands, "Back", TrainyardButton::LevelBackButton),
+        half_width_button(&mut commands, "Save", TrainyardButton::LevelEditorSave),
+    ];
+
+    let tool_buttons: Vec<Entity> = EditorTool::ALL
+        .iter()
+        .map(|tool| {
+            half_width_button(
+                &mut commands,
+                tool.label(),
+                TrainyardButton::LevelEditorTool(*tool),
+            )
+        })
+        .collect();
+
+    let resize_buttons: Vec<Entity> = [
+        ("Narrower", -1, 0),
+        ("Wider", 1, 0),
+        ("Shorter", 0, -1),
+        ("Taller", 0, 1),
+    ]
+    .into_iter()
+    .map(|(text, width, height)| {
+        half_width_button(
+            &mut commands,
+            text,
+            TrainyardButton::LevelEditorResize { width, height },
+        )
+    })
+    .collect();
+
+    let turn_button = create_trainyard_button(
+        &mut commands,
+        &turn_button_text(editor.dir),
+        BUTTON_WIDTH,
+        PALETTE_BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::LevelEditorTurn,
+    );
+
+    let color_buttons: Vec<Entity> = COLORS
+        .iter()
+        .map(|color| {
+            let button = create_trainyard_button(
+                &mut commands,
+                "",
+                COLOR_SWATCH_SIZE_PX,
+                COLOR_SWATCH_SIZE_PX,
+                BUTTON_TEXT_SIZE,
+                BUTTON_BORDER_COLOR,
+                font.clone(),
+                TrainyardButton::LevelEditorColor(*color),
+            );
+            commands
+                .entity(button)
+                .insert(BackgroundColor(Color::from(*color)));
+            button
+        })
+        .collect();
+
+    let start_trains_button = create_trainyard_button(
+        &mut commands,
+        "Start trains (SPACE)",
+        BUTTON_WIDTH,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::LevelStartTrainsButton,
+    );
+
+    let message_text = (
+        Text::new(""),
+        TextFont {
+            font,
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            width: Val::Px(BUTTON_WIDTH),
+            min_height: Val::Px(50.0),
+            ..default()
+        },
+        LevelEditorMessage,
+    );
+
+    // putting it all together
+
+    let editor_root = commands.spawn(editor_root).id();
+    let canvas_placeholder = commands.spawn(canvas_placeholder).id();
+    let palette = commands.spawn(palette).id();
+    let name_text = commands.spawn(name_text).id();
+    let back_and_save_row = commands.spawn(row()).id();
+    let tool_row = commands.spawn(row()).id();
+    let resize_row = commands.spawn(row()).id();
+    let color_row = commands.spawn(row()).id();
+    let message_text = commands.spawn(message_text).id();
+
+    commands.entity(ui_root).add_children(&[editor_root]);
+    commands
+        .entity(editor_root)
+        .add_children(&[canvas_placeholder, palette]);
+    commands.entity(palette).add_children(&[
+        name_text,
+        back_and_save_row,
+        tool_row,
+        resize_row,
+        turn_button,
+        color_row,
+        start_trains_button,
+        message_text,
+    ]);
+    commands
+        .entity(back_and_save_row)
+        .add_children(&back_and_save_buttons);
+    commands.entity(tool_row).add_children(&tool_buttons);
+    commands.entity(resize_row).add_children(&resize_buttons);
+    commands.entity(color_row).add_children(&color_buttons);
+}
+
+fn title_text(editor: &LevelEditor) -> String {
+    format!(
+        "{} ({}x{})",
+        editor.level.name, editor.level.width, editor.level.height
+    )
+}
+
+fn turn_button_text(dir: Dir) -> String {
+    let dir = match dir {
+        Dir::Up => "up",
+        Dir::Right => "right",
+        Dir::Down => "down",
+        Dir::Left => "left",
+    };
+    format!("Facing {} (R)", dir)
+}
+
+fn level_editor_button_handler(
+    interaction_query: Query<(&Interaction, &TrainyardButton), Changed<Interaction>>,
+    level_state: Res<State<LevelState>>,
+    mut editor_ev_writer: EventWriter<LevelEditorEvent>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let event = match button {
+            TrainyardButton::LevelPickerNewLevel => LevelEditorEvent::Open(None),
+            TrainyardButton::LevelPickerEditLevel(level_name) => {
+                LevelEditorEvent::Open(Some(level_name.clone()))
+            }
+            TrainyardButton::LevelEditorTool(tool) => LevelEditorEvent::SelectTool(*tool),
+            TrainyardButton::LevelEditorColor(color) => LevelEditorEvent::SelectColor(*color),
+            TrainyardButton::LevelEditorTurn => LevelEditorEvent::Turn,
+            // like clicking on the yard, these only do anything while the trains aren't running
+            TrainyardButton::LevelEditorResize { width, height }
+                if *level_state.get() == LevelState::Editing =>
+            {
+                LevelEditorEvent::Resize {
+                    width: *width,
+                    height: *height,
+                }
+            }
+            // only the level's pieces are saved, so it's saved as it was before the trains started
+            TrainyardButton::LevelEditorSave if *level_state.get() == LevelState::Editing => {
+                LevelEditorEvent::Save
+            }
+            _ => continue,
+        };
+        editor_ev_writer.send(event);
+    }
+}
+
+/// Highlights the selected tool and color, and keeps the direction and message up to date.
+fn update_level_editor_ui(
+    editor: Res<LevelEditor>,
+    mut border_query: Query<(&mut BorderColor, &TrainyardButton)>,
+    mut button_text_query: Query<(&mut Text, &TrainyardButton)>,
+    mut message_query: Query<&mut Text, (With<LevelEditorMessage>, Without<TrainyardButton>)>,
+) {
+    for (mut border_color, button_type) in border_query.iter_mut() {
+        let color = match button_type {
+            TrainyardButton::LevelEditorTool(tool) if *tool == editor.tool => {
+                SELECTED_TOOL_BORDER_COLOR
+            }
+            TrainyardButton::LevelEditorColor(color) if *color == editor.color => {
+                SELECTED_COLOR_BORDER_COLOR
+            }
+            TrainyardButton::LevelEditorTool(_) | TrainyardButton::LevelEditorColor(_) => {
+                BUTTON_BORDER_COLOR
+            }
+            _ => continue,
+        };
+        if border_color.0 != color {
+            border_color.0 = color;
+        }
+    }
+
+    let turn_text = turn_button_text(editor.dir);
+    for (mut text, button_type) in button_text_query.iter_mut() {
+        if *button_type == TrainyardButton::LevelEditorTurn && text.0 != turn_text {
+            text.0 = turn_text.clone();
+        }
+    }
+
+    for mut text in message_query.iter_mut() {
+        if text.0 != editor.message {
+            text.0 = editor.message.clone();
+        }
+    }
+}
+
+fn update_level_editor_title(
+    editor: Res<LevelEditor>,
+    mut title_query: Query<&mut Text, With<LevelEditorTitle>>,
+) {
+    let title = title_text(&editor);
+    for mut text in title_query.iter_mut() {
+        if text.0 != title {
+            text.0 = title.clone();
+        }
+    }
+}
+
+fn teardown_level_editor_ui(
+    mut commands: Commands,
+    editor_root_query: Query<Entity, With<LevelEditorUIRoot>>,
+) {
+    for entity in editor_root_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
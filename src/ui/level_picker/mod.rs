@@ -1,12 +1,30 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use trainyard_core::{
+    level::LevelLoadInfo,
+    yard_code::{decode_yard_code, encode_yard_code},
+};
 
-use crate::level::{loader::StockLevelInfos, persistence::GameLevelProgress};
+use crate::{
+    level::{
+        loader::StockLevelInfos,
+        persistence::GameLevelProgress,
+    },
+    level_editor::my_levels::MY_LEVELS_CITY,
+    tutorial::Tutorials,
+};
 
 use super::{
     buttons::{create_trainyard_button, TrainyardButton},
+    clipboard::SystemClipboard,
     UIState,
 };
 
+/// The city that levels pasted in from a code are put under.
+pub const IMPORTED_CITY: &str = "Imported";
+
+#[derive(Component)]
+pub struct LevelPickerMessage;
+
 #[derive(Component)]
 pub struct LevelPickerUIRoot;
 
@@ -15,26 +33,123 @@ pub struct StartLevelEvent {
     pub level_name: String,
 }
 
+/// Sent to play the level whose code is on the clipboard.
+#[derive(Event, Default)]
+pub struct PasteLevelEvent;
+
+/// Which page of the level picker is showing: the overview of every city, or the levels in a single city.
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
+pub enum LevelPickerPage {
+    #[default]
+    Cities,
+    City(String),
+}
+
+/// An optional rule for opening cities one at a time, by earning stars in the cities before them. It's turned on with
+/// `Settings::stars_per_city`.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CityUnlockRule {
+    /// City number `k` (counting from 0) opens once `k * stars_per_city` stars have been earned in the cities before
+    /// it. `None` leaves every city open.
+    pub stars_per_city: Option<u32>,
+}
+
+impl CityUnlockRule {
+    /// How many more stars need to be earned before `city` opens, or `None` if it's already open.
+    pub fn stars_needed(
+        &self,
+        levels: &StockLevelInfos,
+        progress: &GameLevelProgress,
+        city: &str,
+    ) -> Option<u32> {
+        let stars_per_city = self.stars_per_city?;
+        let cities = levels.cities();
+        let index = cities.iter().position(|c| *c == city)?;
+
+        let required = index as u32 * stars_per_city;
+        let earned = progress.stars_earned(
+            cities[..index]
+                .iter()
+                .flat_map(|earlier_city| levels.levels_in_city(earlier_city)),
+        );
+        (earned < required).then(|| required - earned)
+    }
+
+    pub fn is_unlocked(
+        &self,
+        levels: &StockLevelInfos,
+        progress: &GameLevelProgress,
+        city: &str,
+    ) -> bool {
+        self.stars_needed(levels, progress, city).is_none()
+    }
+}
+
+pub const STAR_SIZE_PX: f32 = 14.0;
+
 pub struct LevelPickerUIPlugin;
 impl Plugin for LevelPickerUIPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<StartLevelEvent>()
+            .add_event::<PasteLevelEvent>()
+            .init_resource::<LevelPickerPage>()
+            .init_resource::<CityUnlockRule>()
             .add_systems(OnEnter(UIState::LevelPicker), spawn_level_picker)
-            .add_systems(OnExit(UIState::LevelPicker), teardown_level_picker);
+            .add_systems(OnExit(UIState::LevelPicker), teardown_level_picker)
+            .add_systems(
+                Update,
+                (
+                    paste_level.run_if(on_event::<PasteLevelEvent>),
+                    follow_started_level_city.run_if(on_event::<StartLevelEvent>),
+                    (teardown_level_picker, spawn_level_picker).chain().run_if(
+                        in_state(UIState::LevelPicker).and(resource_changed::<LevelPickerPage>),
+                    ),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Keeps the picker on the city of the level being played, so that going back to the picker after moving on to
+/// the next city's levels shows the right page.
+fn follow_started_level_city(
+    mut start_events: EventReader<StartLevelEvent>,
+    levels: Res<StockLevelInfos>,
+    mut page: ResMut<LevelPickerPage>,
+) {
+    for event in start_events.read() {
+        if let Some(level) = levels.find_level(&event.level_name) {
+            page.set_if_neq(LevelPickerPage::City(level.city.clone()));
+        }
     }
 }
+
+/// Everything the level picker shows, none of which it changes.
+#[derive(SystemParam)]
+struct LevelPickerContents<'w> {
+    levels: Res<'w, StockLevelInfos>,
+    progress: Res<'w, GameLevelProgress>,
+    page: Res<'w, LevelPickerPage>,
+    unlock_rule: Res<'w, CityUnlockRule>,
+    tutorials: Res<'w, Tutorials>,
+}
+
 fn spawn_level_picker(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     ui_root_query: Query<Entity, With<super::UIRootContainer>>,
-    level_names: Res<StockLevelInfos>,
-    progress: Res<GameLevelProgress>,
+    contents: LevelPickerContents,
 ) {
+    let LevelPickerContents {
+        levels,
+        progress,
+        page,
+        unlock_rule,
+        tutorials,
+    } = contents;
     let ui_root = ui_root_query.single();
     let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
 
-    let level_names = level_names.0.iter().map(|level| &level.name);
-
     // =============================================================================================
     // root container for the level picker
     // =============================================================================================
@@ -51,8 +166,18 @@ fn spawn_level_picker(
     );
 
     // =============================================================================================
-    // text which says "TRAINYARD"
+    // text with the name of the city, or "Cities" on the overview
     // =============================================================================================
+    let title = match page.as_ref() {
+        LevelPickerPage::Cities => {
+            let stars = progress.stars_earned(levels.0.iter());
+            format!("Cities ({} stars)", stars)
+        }
+        LevelPickerPage::City(city) => {
+            let (won, total) = city_progress(&levels, &progress, city);
+            format!("{} ({}/{})", city, won, total)
+        }
+    };
     let title_text_box = Node {
         width: Val::Percent(100.0),
         height: Val::Px(120.0),
@@ -63,7 +188,7 @@ fn spawn_level_picker(
         ..default()
     };
     let title_text = (
-        Text::new("Abbotsford"),
+        Text::new(title),
         TextFont {
             font: font.clone(),
             font_size: 85.0,
@@ -79,64 +204,409 @@ fn spawn_level_picker(
     );
 
     // =============================================================================================
-    // box that holds the rest of the GUI (all the buttons for selecting each individual level)
+    // box that holds the buttons for each city or level
     // =============================================================================================
 
     let body_box = Node {
         width: Val::Percent(85.0),
-        height: Val::Percent(100.0),
+        flex_grow: 1.0,
         flex_direction: FlexDirection::Row,
         justify_content: JustifyContent::Center,
         align_items: AlignItems::FlexStart,
+        align_content: AlignContent::FlexStart,
         flex_wrap: FlexWrap::Wrap,
         ..default()
     };
 
     // =============================================================================================
-    // construct one button for each level
+    // row of buttons for moving between pages
     // =============================================================================================
 
-    let mut buttons: Vec<Entity> = Vec::new();
-    for name in level_names {
-        let mut has_won_this_level = false;
+    let nav_box = Node {
+        width: Val::Percent(100.0),
+        flex_direction: FlexDirection::Row,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        margin: UiRect::bottom(Val::Px(20.0)),
+        ..default()
+    };
 
-        if progress.0.contains_key(name) {
-            has_won_this_level = progress.0.get(name).unwrap().has_won;
+    let (buttons, nav_buttons) = match page.as_ref() {
+        LevelPickerPage::Cities => (
+            spawn_city_buttons(&mut commands, &levels, &progress, &unlock_rule, &font),
+            vec![
+                create_nav_button(
+                    &mut commands,
+                    "Main menu",
+                    &font,
+                    TrainyardButton::LevelPickerMainMenu,
+                ),
+                create_nav_button(
+                    &mut commands,
+                    "Paste level",
+                    &font,
+                    TrainyardButton::LevelPickerPasteLevel,
+                ),
+                create_nav_button(
+                    &mut commands,
+                    "Level editor",
+                    &font,
+                    TrainyardButton::LevelPickerNewLevel,
+                ),
+            ],
+        ),
+        LevelPickerPage::City(city) => {
+            let cities = levels.cities();
+            let index = cities.iter().position(|c| c == city);
+
+            let mut nav_buttons = Vec::new();
+            if index.is_some_and(|index| index > 0) {
+                nav_buttons.push(create_nav_button(
+                    &mut commands,
+                    "Previous city",
+                    &font,
+                    TrainyardButton::LevelPickerPreviousCity,
+                ));
+            }
+            nav_buttons.push(create_nav_button(
+                &mut commands,
+                "All cities",
+                &font,
+                TrainyardButton::LevelPickerCityOverview,
+            ));
+            if city == MY_LEVELS_CITY {
+                nav_buttons.push(create_nav_button(
+                    &mut commands,
+                    "New level",
+                    &font,
+                    TrainyardButton::LevelPickerNewLevel,
+                ));
+            }
+            let next_city_is_open = index
+                .and_then(|index| cities.get(index + 1))
+                .is_some_and(|next| unlock_rule.is_unlocked(&levels, &progress, next));
+            if next_city_is_open {
+                nav_buttons.push(create_nav_button(
+                    &mut commands,
+                    "Next city",
+                    &font,
+                    TrainyardButton::LevelPickerNextCity,
+                ));
+            }
+            (
+                spawn_level_buttons(
+                    &mut commands,
+                    &asset_server,
+                    &levels,
+                    &progress,
+                    &tutorials,
+                    &font,
+                    city,
+                ),
+                nav_buttons,
+            )
         }
+    };
+
+    // =============================================================================================
+    // text for reporting problems with pasted levels
+    // =============================================================================================
 
-        let border_color = if has_won_this_level {
+    let message_text = (
+        Text::new(""),
+        TextFont {
+            font: font.clone(),
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.3, 0.3)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            margin: UiRect::bottom(Val::Px(10.0)),
+            ..default()
+        },
+        LevelPickerMessage,
+    );
+
+    // putting it all together
+
+    let level_picker_root = commands.spawn(level_picker_root).id();
+    let title_text_box = commands.spawn(title_text_box).id();
+    let title_text = commands.spawn(title_text).id();
+    let body_box = commands.spawn(body_box).id();
+    let nav_box = commands.spawn(nav_box).id();
+    let message_text = commands.spawn(message_text).id();
+
+    commands.entity(ui_root).add_children(&[level_picker_root]);
+    commands.entity(level_picker_root).add_children(&[
+        title_text_box,
+        body_box,
+        nav_box,
+        message_text,
+    ]);
+    commands.entity(title_text_box).add_children(&[title_text]);
+
+    commands.entity(body_box).add_children(&buttons);
+    commands.entity(nav_box).add_children(&nav_buttons);
+}
+
+/// Starts the level whose code is on the clipboard, along with any tracks in the code.
+/// Codes for levels that aren't in the game yet add them under `IMPORTED_CITY`. If tracks have already been drawn on
+/// the level, the ones in the code are saved as a solution of their own instead of replacing them.
+fn paste_level(
+    mut events: EventReader<PasteLevelEvent>,
+    mut clipboard: ResMut<SystemClipboard>,
+    mut levels: ResMut<StockLevelInfos>,
+    mut progress: ResMut<GameLevelProgress>,
+    mut start_lvl_ev_writer: EventWriter<StartLevelEvent>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+    mut message_query: Query<&mut Text, With<LevelPickerMessage>>,
+) {
+    events.clear();
+    let pasted = clipboard.get_text().and_then(|code| {
+        let (level, tracks) = decode_yard_code(&code).map_err(|e| e.to_string())?;
+        let code = encode_yard_code(&level, None).map_err(|e| e.to_string())?;
+        Ok((level, tracks, code))
+    });
+    let (level, tracks, code) = match pasted {
+        Ok(pasted) => pasted,
+        Err(e) => {
+            for mut text in message_query.iter_mut() {
+                text.0 = format!("Couldn't paste a level: {}", e);
+            }
+            return;
+        }
+    };
+
+    let existing_level = levels
+        .0
+        .iter()
+        .find(|other| encode_yard_code(other, None).is_ok_and(|other_code| other_code == code));
+    let name = match existing_level {
+        Some(existing_level) => existing_level.name.clone(),
+        None => {
+            let count = levels.levels_in_city(IMPORTED_CITY).count();
+            let name = format!("Imported level {}", count + 1);
+            levels.0.push(LevelLoadInfo {
+                name: name.clone(),
+                city: String::from(IMPORTED_CITY),
+                ..level
+            });
+            // progress for imported levels isn't saved, but save files from before that was the case can still have
+            // some under this name, for whichever level was pasted first back then
+            progress.0.remove(&name);
+            name
+        }
+    };
+
+    if let Some(tracks) = tracks {
+        let level_progress = progress.0.entry(name.clone()).or_default();
+        // the tracks already drawn on the level are kept, and the pasted ones can be loaded from the solutions list
+        if level_progress.drawn_tracks.iter().all(|&track| track == 0) {
+            level_progress.drawn_tracks = tracks;
+        } else if let Err(e) = level_progress.save_new_solution("", tracks) {
+            error!("could not save the pasted tracks: {}", e);
+        }
+    }
+    start_lvl_ev_writer.send(StartLevelEvent { level_name: name });
+    next_ui_state.set(UIState::Level);
+}
+
+/// How many levels in `city` have been won, and how many levels it has in total.
+fn city_progress(
+    levels: &StockLevelInfos,
+    progress: &GameLevelProgress,
+    city: &str,
+) -> (usize, usize) {
+    let mut won = 0;
+    let mut total = 0;
+    for level in levels.levels_in_city(city) {
+        total += 1;
+        if progress.0.get(&level.name).is_some_and(|p| p.has_won) {
+            won += 1;
+        }
+    }
+    (won, total)
+}
+
+fn spawn_city_buttons(
+    commands: &mut Commands,
+    levels: &StockLevelInfos,
+    progress: &GameLevelProgress,
+    unlock_rule: &CityUnlockRule,
+    font: &Handle<Font>,
+) -> Vec<Entity> {
+    let mut buttons: Vec<Entity> = Vec::new();
+    for city in levels.cities() {
+        let (won, total) = city_progress(levels, progress, city);
+
+        let border_color = if won == total {
             super::BTN_BORDER_GREEN
         } else {
             super::BTN_BORDER_BLACK
         };
 
+        // locked cities get a button that the button handler ignores
+        let (text, button_type) = match unlock_rule.stars_needed(levels, progress, city) {
+            None => (
+                format!("{}\n{}/{}", city, won, total),
+                TrainyardButton::LevelPickerCity(city.to_string()),
+            ),
+            Some(stars_needed) => (
+                format!("{}\nneeds {} more stars", city, stars_needed),
+                TrainyardButton::Unknown,
+            ),
+        };
+
         let button = create_trainyard_button(
-            &mut commands,
-            &name,
-            200.0,
-            90.0,
+            commands,
+            &text,
+            180.0,
+            70.0,
             20.0,
             border_color,
             font.clone(),
-            TrainyardButton::LevelPickerStartLevel(name.clone()),
+            button_type,
         );
         buttons.push(button);
     }
+    buttons
+}
 
-    // putting it all together
+fn spawn_level_buttons(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    levels: &StockLevelInfos,
+    progress: &GameLevelProgress,
+    tutorials: &Tutorials,
+    font: &Handle<Font>,
+    city: &str,
+) -> Vec<Entity> {
+    let mut buttons: Vec<Entity> = Vec::new();
+    for level in levels.levels_in_city(city) {
+        let name = &level.name;
+        let tutorial = tutorials.before_level(name);
 
-    let level_picker_root = commands.spawn(level_picker_root).id();
-    let title_text_box = commands.spawn(title_text_box).id();
-    let title_text = commands.spawn(title_text).id();
-    let body_box = commands.spawn(body_box).id();
+        if let Some(tutorial) = tutorial {
+            let button = create_trainyard_button(
+                commands,
+                &format!("Tutorial:\n{}", tutorial.name),
+                200.0,
+                90.0,
+                20.0,
+                super::BTN_BORDER_BLACK,
+                font.clone(),
+                TrainyardButton::LevelPickerStartTutorial(tutorial.slug.clone()),
+            );
+            buttons.push(button);
+        }
 
-    commands.entity(ui_root).add_children(&[level_picker_root]);
-    commands
-        .entity(level_picker_root)
-        .add_children(&[title_text_box, body_box]);
-    commands.entity(title_text_box).add_children(&[title_text]);
+        let mut has_won_this_level = false;
+        let mut used_hints = false;
 
-    commands.entity(body_box).add_children(&buttons);
+        if let Some(level_progress) = progress.0.get(name) {
+            has_won_this_level = level_progress.has_won;
+            used_hints = level_progress.hints_used > 0;
+        }
+
+        // levels that were beaten with the help of hints are shown in blue instead of green
+        let border_color = match (has_won_this_level, used_hints) {
+            (true, false) => super::BTN_BORDER_GREEN,
+            (true, true) => super::BTN_BORDER_BLUE,
+            (false, _) => super::BTN_BORDER_BLACK,
+        };
+
+        // the first time a level with a tutorial is played, the tutorial comes first
+        let button_type = match tutorial {
+            Some(tutorial) if !progress.0.contains_key(name) => {
+                TrainyardButton::LevelPickerStartTutorial(tutorial.slug.clone())
+            }
+            _ => TrainyardButton::LevelPickerStartLevel(name.clone()),
+        };
+        let button = create_trainyard_button(
+            commands,
+            name,
+            200.0,
+            90.0,
+            20.0,
+            border_color,
+            font.clone(),
+            button_type,
+        );
+        let stars = spawn_star_row(commands, asset_server, level.stars, has_won_this_level);
+        commands.entity(button).add_children(&[stars]);
+        buttons.push(button);
+
+        // levels from the level editor can be opened in it again
+        if city == MY_LEVELS_CITY {
+            let edit_button = create_trainyard_button(
+                commands,
+                "Edit",
+                90.0,
+                90.0,
+                20.0,
+                super::BTN_BORDER_BLACK,
+                font.clone(),
+                TrainyardButton::LevelPickerEditLevel(name.clone()),
+            );
+            buttons.push(edit_button);
+        }
+    }
+    buttons
+}
+
+/// A row of stars along the bottom of a level's button, showing its difficulty. They light up once it's been won.
+fn spawn_star_row(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    stars: u8,
+    bright: bool,
+) -> Entity {
+    let image = if bright {
+        "sprites/Star_bright.png"
+    } else {
+        "sprites/Star.png"
+    };
+    let row = commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(6.0),
+            flex_direction: FlexDirection::Row,
+            ..default()
+        })
+        .id();
+    for _ in 0..stars {
+        let star = commands
+            .spawn((
+                ImageNode::new(asset_server.load(image)),
+                Node {
+                    width: Val::Px(STAR_SIZE_PX),
+                    height: Val::Px(STAR_SIZE_PX),
+                    ..default()
+                },
+            ))
+            .id();
+        commands.entity(row).add_children(&[star]);
+    }
+    row
+}
+
+fn create_nav_button(
+    commands: &mut Commands,
+    text: &str,
+    font: &Handle<Font>,
+    button_type: TrainyardButton,
+) -> Entity {
+    create_trainyard_button(
+        commands,
+        text,
+        180.0,
+        60.0,
+        23.0,
+        super::BTN_BORDER_BLACK,
+        font.clone(),
+        button_type,
+    )
 }
 
 fn teardown_level_picker(
@@ -147,3 +617,101 @@ fn teardown_level_picker(
         commands.entity(entity).despawn_recursive();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level::{loader::LEVEL_DATA, persistence::LevelProgress},
+        settings::STARS_PER_CITY_CHOICES,
+    };
+    use trainyard_core::levels_txt::parse_levels_txt;
+
+    fn level(name: &str, city: &str, stars: u8) -> LevelLoadInfo {
+        LevelLoadInfo {
+            name: String::from(name),
+            city: String::from(city),
+            stars,
+            ..default()
+        }
+    }
+
+    fn win(progress: &mut GameLevelProgress, name: &str) {
+        progress.0.insert(
+            String::from(name),
+            LevelProgress {
+                has_won: true,
+                ..default()
+            },
+        );
+    }
+
+    fn three_cities() -> StockLevelInfos {
+        StockLevelInfos(vec![
+            level("A", "Abbotsford", 2),
+            level("B", "Abbotsford", 3),
+            level("C", "Brampton", 1),
+            level("D", "Calgary", 1),
+        ])
+    }
+
+    #[test]
+    fn every_city_is_open_without_the_rule() {
+        let levels = three_cities();
+        let progress = GameLevelProgress::default();
+        for city in levels.cities() {
+            assert!(CityUnlockRule::default().is_unlocked(&levels, &progress, city));
+        }
+    }
+
+    #[test]
+    fn cities_open_with_the_stars_of_the_cities_before_them() {
+        let levels = three_cities();
+        let rule = CityUnlockRule {
+            stars_per_city: Some(2),
+        };
+        let mut progress = GameLevelProgress::default();
+        assert!(rule.is_unlocked(&levels, &progress, "Abbotsford"));
+        assert_eq!(rule.stars_needed(&levels, &progress, "Brampton"), Some(2));
+        assert_eq!(rule.stars_needed(&levels, &progress, "Calgary"), Some(4));
+
+        // drawing tracks on a level doesn't earn its stars, only winning it does
+        progress
+            .0
+            .insert(String::from("B"), LevelProgress::default());
+        assert_eq!(rule.stars_needed(&levels, &progress, "Brampton"), Some(2));
+
+        win(&mut progress, "A");
+        assert!(rule.is_unlocked(&levels, &progress, "Brampton"));
+        assert_eq!(rule.stars_needed(&levels, &progress, "Calgary"), Some(2));
+
+        // a level in the city itself doesn't count towards opening it
+        win(&mut progress, "D");
+        assert_eq!(rule.stars_needed(&levels, &progress, "Calgary"), Some(2));
+
+        win(&mut progress, "C");
+        assert_eq!(rule.stars_needed(&levels, &progress, "Calgary"), Some(1));
+        win(&mut progress, "B");
+        assert!(rule.is_unlocked(&levels, &progress, "Calgary"));
+    }
+
+    #[test]
+    fn every_stock_city_can_be_opened() {
+        let levels = StockLevelInfos(parse_levels_txt(LEVEL_DATA).unwrap());
+        let mut progress = GameLevelProgress::default();
+        for level in levels.0.iter() {
+            win(&mut progress, &level.name);
+        }
+        for stars_per_city in STARS_PER_CITY_CHOICES {
+            let rule = CityUnlockRule { stars_per_city };
+            for city in levels.cities() {
+                assert!(
+                    rule.is_unlocked(&levels, &progress, city),
+                    "{} can't be opened with {:?} stars per city",
+                    city,
+                    stars_per_city
+                );
+            }
+        }
+    }
+}
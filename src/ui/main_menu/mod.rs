@@ -91,6 +91,21 @@ fn spawn_main_menu(
         font.clone(),
         TrainyardButton::MainMenuCredits,
     );
+
+    // =============================================================================================
+    // "settings" button
+    // =============================================================================================
+    let settings_button = create_trainyard_button(
+        &mut commands,
+        "SETTINGS",
+        200.0,
+        90.0,
+        50.0,
+        super::BTN_BORDER_BLACK,
+        font.clone(),
+        TrainyardButton::MainMenuSettings,
+    );
+
     // =============================================================================================
     // put it together
     // =============================================================================================
@@ -100,9 +115,12 @@ fn spawn_main_menu(
     let title_text = commands.spawn(title_text).id();
 
     commands.entity(ui_root).add_children(&[main_menu_root]);
-    commands
-        .entity(main_menu_root)
-        .add_children(&[title_text_box, play_button, credits_button]);
+    commands.entity(main_menu_root).add_children(&[
+        title_text_box,
+        play_button,
+        settings_button,
+        credits_button,
+    ]);
     commands.entity(title_text_box).add_children(&[title_text]);
 }
 
@@ -1,8 +1,12 @@
 pub mod buttons;
+pub mod clipboard;
 pub mod credits;
 pub mod level;
+pub mod level_editor;
 pub mod level_picker;
 pub mod main_menu;
+pub mod settings;
+pub mod tutorial;
 
 use bevy::prelude::*;
 
@@ -24,7 +28,10 @@ pub enum UIState {
     MainMenu,
     LevelPicker,
     Level,
+    Tutorial,
+    LevelEditor,
     Credits,
+    Settings,
 }
 
 pub struct TrainyardUIPlugin;
@@ -32,11 +39,15 @@ pub struct TrainyardUIPlugin;
 impl Plugin for TrainyardUIPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<UIState>()
+            .init_resource::<clipboard::SystemClipboard>()
             .add_plugins((
                 main_menu::MainMenuUIPlugin,
                 level_picker::LevelPickerUIPlugin,
                 level::LevelUIPlugin,
+                tutorial::TutorialUIPlugin,
+                level_editor::LevelEditorUIPlugin,
                 credits::CreditsUIPlugin,
+                settings::SettingsUIPlugin,
                 buttons::ButtonPlugin,
             ))
             .add_systems(
@@ -0,0 +1,554 @@
+use bevy::prelude::*;
+
+use super::{
+    buttons::{create_trainyard_button, TrainyardButton},
+    level::speed_slider::TrainSpeed,
+    UIState,
+};
+use crate::{
+    input::{InputAction, KeyBinding, KeyBindings},
+    settings::Settings,
+};
+
+/// How much a press of "-" or "+" changes a volume or the train speed by.
+pub const SETTING_STEP: f32 = 0.1;
+const ROW_HEIGHT: f32 = 50.0;
+const ROW_TEXT_SIZE: f32 = 30.0;
+const CONTROL_ROW_HEIGHT: f32 = 36.0;
+const CONTROL_TEXT_SIZE: f32 = 25.0;
+/// How many of the actions are listed in the left column of the controls page, with the rest on the right.
+const CONTROLS_PER_COLUMN: usize = 8;
+
+/// The settings are split over two pages, since there are too many actions to fit next to the rest of them.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingsPage {
+    #[default]
+    General,
+    Controls,
+}
+
+/// The action that the next key pressed gets bound to, after its button on the controls page was pressed.
+#[derive(Resource, Default)]
+pub struct KeyCapture(pub Option<InputAction>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    MasterVolume,
+    SfxVolume,
+    UiVolume,
+    TrainSpeed,
+    Window,
+    Palette,
+    ColorGlyphs,
+    CityUnlocking,
+}
+
+impl Setting {
+    /// Every setting, in the order they're shown in.
+    pub const ALL: [Setting; 8] = [
+        Setting::MasterVolume,
+        Setting::SfxVolume,
+        Setting::UiVolume,
+        Setting::TrainSpeed,
+        Setting::Window,
+        Setting::Palette,
+        Setting::ColorGlyphs,
+        Setting::CityUnlocking,
+    ];
+
+    /// Whether the setting has a few values that are gone through with a single button, instead of being turned up
+    /// and down.
+    fn is_cycled(self) -> bool {
+        matches!(
+            self,
+            Setting::Window | Setting::Palette | Setting::ColorGlyphs | Setting::CityUnlocking
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingsButton {
+    Decrease(Setting),
+    Increase(Setting),
+    Cycle(Setting),
+    Page(SettingsPage),
+    /// Waits for a key to add to the action's keys, or stops waiting if it already was.
+    Rebind(InputAction),
+    ClearBindings(InputAction),
+    ResetBindings,
+}
+
+#[derive(Component)]
+pub struct SettingsUIRoot;
+
+/// The text showing the value of a setting that's turned up and down.
+#[derive(Component)]
+pub struct SettingText(pub Setting);
+
+pub struct SettingsUIPlugin;
+
+impl Plugin for SettingsUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsPage>()
+            .init_resource::<KeyCapture>()
+            .add_systems(OnEnter(UIState::Settings), spawn_settings)
+            .add_systems(
+                OnExit(UIState::Settings),
+                (teardown_settings, reset_settings_page),
+            )
+            .add_systems(
+                Update,
+                (
+                    settings_button_handler,
+                    capture_key_binding.run_if(|capture: Res<KeyCapture>| capture.0.is_some()),
+                    (teardown_settings, spawn_settings)
+                        .chain()
+                        .run_if(resource_changed::<SettingsPage>),
+                    update_setting_texts
+                        .run_if(resource_changed::<Settings>.or(resource_changed::<KeyCapture>)),
+                )
+                    .chain()
+                    .run_if(in_state(UIState::Settings)),
+            );
+    }
+}
+
+fn setting_text(settings: &Settings, setting: Setting) -> String {
+    let percent = |value: f32| format!("{}%", (value * 100.0).round());
+    match setting {
+        Setting::MasterVolume => format!("MASTER VOLUME: {}", percent(settings.master_volume)),
+        Setting::SfxVolume => format!("SFX VOLUME: {}", percent(settings.sfx_volume)),
+        Setting::UiVolume => format!("UI VOLUME: {}", percent(settings.ui_volume)),
+        Setting::TrainSpeed => format!("TRAIN SPEED: {}", percent(settings.default_train_speed)),
+        Setting::Window => format!("WINDOW: {}", settings.window.label().to_uppercase()),
+        Setting::Palette => format!(
+            "COLORS: {}",
+            settings.colorblind.palette.to_str().to_uppercase()
+        ),
+        Setting::ColorGlyphs => format!(
+            "COLOR LETTERS: {}",
+            if settings.colorblind.glyphs {
+                "ON"
+            } else {
+                "OFF"
+            }
+        ),
+        Setting::CityUnlocking => match settings.stars_per_city {
+            Some(stars) => format!("CITY LOCKS: {} STARS", stars),
+            None => String::from("CITY LOCKS: OFF"),
+        },
+    }
+}
+
+fn bindings_text(settings: &Settings, capture: &KeyCapture, action: InputAction) -> String {
+    if capture.0 == Some(action) {
+        String::from("PRESS A KEY")
+    } else {
+        settings.key_bindings.label(action).to_uppercase()
+    }
+}
+
+fn spawn_settings(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ui_root_query: Query<Entity, With<super::UIRootContainer>>,
+    settings: Res<Settings>,
+    capture: Res<KeyCapture>,
+    page: Res<SettingsPage>,
+) {
+    let ui_root = ui_root_query.single();
+    let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
+
+    // =============================================================================================
+    // root container for the settings, with the page's title on top
+    // =============================================================================================
+    let settings_root = (
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::FlexStart,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        SettingsUIRoot,
+    );
+    let title_text = (
+        Text::new(match *page {
+            SettingsPage::General => "Settings",
+            SettingsPage::Controls => "Controls",
+        }),
+        TextFont {
+            font: font.clone(),
+            font_size: 85.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            padding: UiRect::all(Val::Px(5.0)),
+            ..default()
+        },
+    );
+    let columns = Node {
+        flex_direction: FlexDirection::Row,
+        justify_content: JustifyContent::Center,
+        column_gap: Val::Px(40.0),
+        ..default()
+    };
+
+    let settings_root = commands.spawn(settings_root).id();
+    let title_text = commands.spawn(title_text).id();
+    let columns = commands.spawn(columns).id();
+    commands.entity(ui_root).add_children(&[settings_root]);
+    commands
+        .entity(settings_root)
+        .add_children(&[title_text, columns]);
+
+    match *page {
+        SettingsPage::General => spawn_general_page(&mut commands, columns, font, &settings),
+        SettingsPage::Controls => spawn_controls_page(
+            &mut commands,
+            settings_root,
+            columns,
+            font,
+            &settings,
+            &capture,
+        ),
+    }
+}
+
+fn spawn_column(commands: &mut Commands) -> Entity {
+    commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id()
+}
+
+fn spawn_row(commands: &mut Commands) -> Entity {
+    commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id()
+}
+
+/// The settings on the left, and the buttons to the controls and back to the main menu on the right.
+fn spawn_general_page(
+    commands: &mut Commands,
+    columns: Entity,
+    font: Handle<Font>,
+    settings: &Settings,
+) {
+    let settings_column = spawn_column(commands);
+    let buttons_column = spawn_column(commands);
+    commands
+        .entity(columns)
+        .add_children(&[settings_column, buttons_column]);
+
+    // =============================================================================================
+    // a row for each setting, either turned up and down with "-" and "+" or changed with one button
+    // =============================================================================================
+    for setting in Setting::ALL {
+        let row = spawn_row(commands);
+        let children = if setting.is_cycled() {
+            vec![create_trainyard_button(
+                commands,
+                &setting_text(settings, setting),
+                420.0,
+                ROW_HEIGHT,
+                ROW_TEXT_SIZE,
+                super::BTN_BORDER_BLACK,
+                font.clone(),
+                TrainyardButton::Settings(SettingsButton::Cycle(setting)),
+            )]
+        } else {
+            let decrease_button = create_trainyard_button(
+                commands,
+                "-",
+                ROW_HEIGHT,
+                ROW_HEIGHT,
+                ROW_TEXT_SIZE,
+                super::BTN_BORDER_BLACK,
+                font.clone(),
+                TrainyardButton::Settings(SettingsButton::Decrease(setting)),
+            );
+            let value_text = commands
+                .spawn((
+                    Text::new(setting_text(settings, setting)),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: ROW_TEXT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    Node {
+                        width: Val::Px(280.0),
+                        ..default()
+                    },
+                    SettingText(setting),
+                ))
+                .id();
+            let increase_button = create_trainyard_button(
+                commands,
+                "+",
+                ROW_HEIGHT,
+                ROW_HEIGHT,
+                ROW_TEXT_SIZE,
+                super::BTN_BORDER_BLACK,
+                font.clone(),
+                TrainyardButton::Settings(SettingsButton::Increase(setting)),
+            );
+            vec![decrease_button, value_text, increase_button]
+        };
+        commands.entity(row).add_children(&children);
+        commands.entity(settings_column).add_children(&[row]);
+    }
+
+    // =============================================================================================
+    // the button to the controls, and the button to return to the main menu
+    // =============================================================================================
+    let controls_button = create_trainyard_button(
+        commands,
+        "CONTROLS",
+        200.0,
+        90.0,
+        50.0,
+        super::BTN_BORDER_BLUE,
+        font.clone(),
+        TrainyardButton::Settings(SettingsButton::Page(SettingsPage::Controls)),
+    );
+    let back_button = create_trainyard_button(
+        commands,
+        "BACK",
+        200.0,
+        90.0,
+        50.0,
+        super::BTN_BORDER_GREEN,
+        font,
+        TrainyardButton::SettingsBack,
+    );
+    commands
+        .entity(buttons_column)
+        .add_children(&[controls_button, back_button]);
+}
+
+/// A row for each action, with a button to bind another key to it and one to clear its keys, and the buttons to
+/// reset every action's keys and to go back to the other settings below them.
+fn spawn_controls_page(
+    commands: &mut Commands,
+    settings_root: Entity,
+    columns: Entity,
+    font: Handle<Font>,
+    settings: &Settings,
+    capture: &KeyCapture,
+) {
+    for actions in InputAction::ALL.chunks(CONTROLS_PER_COLUMN) {
+        let column = spawn_column(commands);
+        commands.entity(columns).add_children(&[column]);
+        for action in actions {
+            let row = spawn_row(commands);
+            let action_text = commands
+                .spawn((
+                    Text::new(action.to_str().to_uppercase()),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: CONTROL_TEXT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Node {
+                        width: Val::Px(220.0),
+                        ..default()
+                    },
+                ))
+                .id();
+            let rebind_button = create_trainyard_button(
+                commands,
+                &bindings_text(settings, capture, *action),
+                220.0,
+                CONTROL_ROW_HEIGHT,
+                CONTROL_TEXT_SIZE,
+                super::BTN_BORDER_BLACK,
+                font.clone(),
+                TrainyardButton::Settings(SettingsButton::Rebind(*action)),
+            );
+            let clear_button = create_trainyard_button(
+                commands,
+                "CLEAR",
+                80.0,
+                CONTROL_ROW_HEIGHT,
+                CONTROL_TEXT_SIZE,
+                super::BTN_BORDER_BLACK,
+                font.clone(),
+                TrainyardButton::Settings(SettingsButton::ClearBindings(*action)),
+            );
+            commands
+                .entity(row)
+                .add_children(&[action_text, rebind_button, clear_button]);
+            commands.entity(column).add_children(&[row]);
+        }
+    }
+
+    let buttons_row = spawn_row(commands);
+    let reset_button = create_trainyard_button(
+        commands,
+        "RESET",
+        200.0,
+        90.0,
+        50.0,
+        super::BTN_BORDER_BLUE,
+        font.clone(),
+        TrainyardButton::Settings(SettingsButton::ResetBindings),
+    );
+    let back_button = create_trainyard_button(
+        commands,
+        "BACK",
+        200.0,
+        90.0,
+        50.0,
+        super::BTN_BORDER_GREEN,
+        font,
+        TrainyardButton::Settings(SettingsButton::Page(SettingsPage::General)),
+    );
+    commands
+        .entity(buttons_row)
+        .add_children(&[reset_button, back_button]);
+    commands.entity(settings_root).add_children(&[buttons_row]);
+}
+
+fn teardown_settings(
+    mut commands: Commands,
+    settings_root_query: Query<Entity, With<SettingsUIRoot>>,
+) {
+    for entity in settings_root_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Moves `value` by `steps` of `SETTING_STEP`, keeping it between 0 and 1.
+fn step_setting(value: &mut f32, steps: f32) {
+    // rounding keeps the steps from drifting away from whole percentages
+    *value = ((*value + steps * SETTING_STEP) * 100.0)
+        .round()
+        .clamp(0.0, 100.0)
+        / 100.0;
+}
+
+fn change_setting(
+    settings: &mut Settings,
+    train_speed: &mut TrainSpeed,
+    setting: Setting,
+    steps: f32,
+) {
+    match setting {
+        Setting::MasterVolume => step_setting(&mut settings.master_volume, steps),
+        Setting::SfxVolume => step_setting(&mut settings.sfx_volume, steps),
+        Setting::UiVolume => step_setting(&mut settings.ui_volume, steps),
+        Setting::TrainSpeed => {
+            step_setting(&mut settings.default_train_speed, steps);
+            train_speed.0 = settings.default_train_speed;
+        }
+        Setting::Window => settings.window = settings.window.next(),
+        Setting::Palette => settings.colorblind.palette = settings.colorblind.palette.next(),
+        Setting::ColorGlyphs => settings.colorblind.glyphs = !settings.colorblind.glyphs,
+        Setting::CityUnlocking => settings.next_stars_per_city(),
+    }
+}
+
+fn settings_button_handler(
+    interaction_query: Query<(&Interaction, &TrainyardButton), Changed<Interaction>>,
+    mut settings: ResMut<Settings>,
+    mut train_speed: ResMut<TrainSpeed>,
+    mut page: ResMut<SettingsPage>,
+    mut capture: ResMut<KeyCapture>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        let (Interaction::Pressed, TrainyardButton::Settings(button)) = (interaction, button)
+        else {
+            continue;
+        };
+        match *button {
+            SettingsButton::Decrease(setting) => {
+                change_setting(&mut settings, &mut train_speed, setting, -1.0);
+            }
+            SettingsButton::Increase(setting) | SettingsButton::Cycle(setting) => {
+                change_setting(&mut settings, &mut train_speed, setting, 1.0);
+            }
+            SettingsButton::Page(new_page) => {
+                *page = new_page;
+                capture.0 = None;
+            }
+            SettingsButton::Rebind(action) => {
+                capture.0 = if capture.0 == Some(action) {
+                    None
+                } else {
+                    Some(action)
+                };
+            }
+            SettingsButton::ClearBindings(action) => {
+                settings.key_bindings.clear(action);
+                capture.0 = None;
+            }
+            SettingsButton::ResetBindings => {
+                settings.key_bindings = KeyBindings::default();
+                capture.0 = None;
+            }
+        }
+    }
+}
+
+/// Binds the first key pressed while waiting for one, along with the modifiers held down with it.
+fn capture_key_binding(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut capture: ResMut<KeyCapture>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(action) = capture.0 else {
+        return;
+    };
+    let Some(key) = keys
+        .get_just_pressed()
+        .copied()
+        .find(|key| !KeyBinding::is_modifier(*key))
+    else {
+        return;
+    };
+    settings
+        .key_bindings
+        .bind(action, KeyBinding::from_keys(key, &keys));
+    capture.0 = None;
+}
+
+fn reset_settings_page(mut page: ResMut<SettingsPage>, mut capture: ResMut<KeyCapture>) {
+    page.set_if_neq(SettingsPage::General);
+    capture.0 = None;
+}
+
+fn update_setting_texts(
+    settings: Res<Settings>,
+    capture: Res<KeyCapture>,
+    mut value_text_query: Query<(&mut Text, &SettingText)>,
+    mut button_text_query: Query<(&mut Text, &TrainyardButton), Without<SettingText>>,
+) {
+    for (mut text, SettingText(setting)) in value_text_query.iter_mut() {
+        text.0 = setting_text(&settings, *setting);
+    }
+    for (mut text, button) in button_text_query.iter_mut() {
+        match button {
+            TrainyardButton::Settings(SettingsButton::Cycle(setting)) => {
+                text.0 = setting_text(&settings, *setting);
+            }
+            TrainyardButton::Settings(SettingsButton::Rebind(action)) => {
+                text.0 = bindings_text(&settings, &capture, *action);
+            }
+            _ => {}
+        }
+    }
+}
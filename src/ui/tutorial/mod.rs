@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+
+use crate::{level::yard::MAX_YARD_SIZE_PX, tutorial::player::TutorialPlayer};
+
+use super::{
+    buttons::{create_trainyard_button, TrainyardButton},
+    level::{
+        YardPlaceholderNode, BUTTON_BORDER_COLOR, BUTTON_HEIGHT, BUTTON_TEXT_SIZE, BUTTON_WIDTH,
+    },
+    UIState,
+};
+
+pub const NEXT_BUTTON_READY_COLOR: Color = Color::WHITE;
+pub const NEXT_BUTTON_WAITING_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+#[derive(Component)]
+pub struct TutorialUIRoot;
+
+/// Shows which step of the tutorial is playing.
+#[derive(Component)]
+pub struct TutorialStepText;
+
+pub struct TutorialUIPlugin;
+impl Plugin for TutorialUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(UIState::Tutorial), spawn_tutorial_ui)
+            .add_systems(OnExit(UIState::Tutorial), teardown_tutorial_ui)
+            .add_systems(
+                Update,
+                update_tutorial_ui.run_if(in_state(UIState::Tutorial)),
+            );
+    }
+}
+
+fn spawn_tutorial_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ui_root_query: Query<Entity, With<super::UIRootContainer>>,
+    player: Res<TutorialPlayer>,
+) {
+    let ui_root = ui_root_query.single();
+    let font: Handle<Font> = asset_server.load("fonts/kenyan_coffee_rg.otf");
+    let tutorial_name = player
+        .tutorial()
+        .map_or(String::new(), |tutorial| tutorial.name.clone());
+
+    // =============================================================================================
+    // root container for the tutorial UI, laid out like a level
+    // =============================================================================================
+    let tutorial_root = (
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        TutorialUIRoot,
+    );
+
+    let canvas_placeholder = (
+        Node {
+            width: Val::Px(MAX_YARD_SIZE_PX),
+            height: Val::Px(MAX_YARD_SIZE_PX),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(1.0, 0.0, 0.0, 0.05)),
+        YardPlaceholderNode,
+        Name::new("Yard placeholder in UI"),
+    );
+
+    // =============================================================================================
+    // the column on the right. The boxes above and below the NEXT button are the same height, so that the button
+    // sits level with the middle of the yard where the first tutorial's arrow points at it
+    // =============================================================================================
+    let button_container = Node {
+        width: Val::Auto,
+        height: Val::Percent(100.0),
+        justify_content: JustifyContent::Center,
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        margin: UiRect::all(Val::Px(20.0)),
+        ..default()
+    };
+    let side_box = || Node {
+        width: Val::Px(BUTTON_WIDTH),
+        height: Val::Px(140.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let title_box = Node {
+        justify_content: JustifyContent::FlexEnd,
+        ..side_box()
+    };
+
+    let title_text = (
+        Text::new(tutorial_name),
+        TextFont {
+            font: font.clone(),
+            font_size: 35.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+    );
+    let step_text = (
+        Text::new(""),
+        TextFont {
+            font: font.clone(),
+            font_size: BUTTON_TEXT_SIZE,
+            ..default()
+        },
+        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TutorialStepText,
+    );
+
+    let next_button = create_trainyard_button(
+        &mut commands,
+        "Next",
+        BUTTON_WIDTH,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font.clone(),
+        TrainyardButton::TutorialNext,
+    );
+    let skip_button = create_trainyard_button(
+        &mut commands,
+        "Skip tutorial",
+        BUTTON_WIDTH,
+        BUTTON_HEIGHT,
+        BUTTON_TEXT_SIZE,
+        BUTTON_BORDER_COLOR,
+        font,
+        TrainyardButton::TutorialSkip,
+    );
+
+    // putting it all together
+
+    let tutorial_root = commands.spawn(tutorial_root).id();
+    let canvas_placeholder = commands.spawn(canvas_placeholder).id();
+    let button_container = commands.spawn(button_container).id();
+    let title_box = commands.spawn(title_box).id();
+    let skip_box = commands.spawn(side_box()).id();
+    let title_text = commands.spawn(title_text).id();
+    let step_text = commands.spawn(step_text).id();
+
+    commands.entity(ui_root).add_children(&[tutorial_root]);
+    commands
+        .entity(tutorial_root)
+        .add_children(&[canvas_placeholder, button_container]);
+    commands
+        .entity(button_container)
+        .add_children(&[title_box, next_button, skip_box]);
+    commands
+        .entity(title_box)
+        .add_children(&[title_text, step_text]);
+    commands.entity(skip_box).add_children(&[skip_button]);
+}
+
+/// Keeps the step count up to date, and greys out NEXT until the step has finished playing.
+fn update_tutorial_ui(
+    player: Res<TutorialPlayer>,
+    mut step_text_query: Query<&mut Text, (With<TutorialStepText>, Without<TrainyardButton>)>,
+    mut button_text_query: Query<(&mut Text, &mut TextColor, &TrainyardButton)>,
+) {
+    let step_text = format!("Step {} of {}", player.step_number(), player.step_count());
+    for mut text in step_text_query.iter_mut() {
+        if text.0 != step_text {
+            text.0 = step_text.clone();
+        }
+    }
+
+    let next_text = match player.tutorial() {
+        Some(tutorial) if player.is_last_step() && tutorial.before_level.is_some() => "Next puzzle",
+        _ if player.is_last_step() => "Done",
+        _ => "Next",
+    };
+    let next_color = if player.step_finished() {
+        NEXT_BUTTON_READY_COLOR
+    } else {
+        NEXT_BUTTON_WAITING_COLOR
+    };
+    for (mut text, mut color, button_type) in button_text_query.iter_mut() {
+        if *button_type == TrainyardButton::TutorialNext {
+            if text.0 != next_text {
+                text.0 = String::from(next_text);
+            }
+            if color.0 != next_color {
+                color.0 = next_color;
+            }
+        }
+    }
+}
+
+fn teardown_tutorial_ui(
+    mut commands: Commands,
+    tutorial_root_query: Query<Entity, With<TutorialUIRoot>>,
+) {
+    for entity in tutorial_root_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}